@@ -3,41 +3,79 @@
 // App composition root. This wires together the pure week/day state, the
 // Supabase sync state, the async result queue, and the top-level egui shell.
 
+mod backup;
+mod clockify;
+mod conflict;
+mod contract;
+mod export;
+mod files;
+mod ical;
+mod import;
+mod login_link;
+mod outbox;
+mod overview;
+mod report;
+mod schedule;
+mod snapshots;
 mod state;
 mod sync;
 mod tasks;
+mod timer;
 mod ui_shell;
 mod ui_state;
+mod undo;
 
 use crate::config::AppConfig;
-use state::State;
+use backup::{Backup, PendingRestore, SyncMetadata};
+use conflict::ConflictMerge;
+use export::ExportFormat;
+use files::{FilePurpose, OpenedFile, SavePurpose, SavedFile};
+use ical::CalendarImport;
+use import::ImportSession;
+use overview::OverviewView;
+use report::DateRange;
+use schedule::WeekSchedule;
+use snapshots::Snapshots;
+use state::{State, WeekKey};
 use sync::{AsyncResult, ProcessAsyncContext, SyncState};
 use tasks::{new_async_results, take_async_results, AsyncResults};
 use tracing::{debug, info, warn};
 use ui_state::AppUiState;
+use undo::UndoLog;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct TemplateApp {
     pub state: State,
-    pub undoer: egui::util::undoer::Undoer<State>,
+    pub undo_log: UndoLog,
     pub sync: SyncState,
     pub ui_state: AppUiState,
+    snapshots: Snapshots,
+    /// Snapshot taken right before the last import, for rolling it back.
+    import_snapshot: Option<u64>,
     #[serde(skip)]
     config: Option<AppConfig>,
     #[serde(skip)]
     async_results: AsyncResults<AsyncResult>,
+    #[serde(skip)]
+    file_results: AsyncResults<Option<OpenedFile>>,
+    #[serde(skip)]
+    saved_files: AsyncResults<SavedFile>,
 }
 
 impl Default for TemplateApp {
     fn default() -> Self {
         Self {
             state: State::default(),
-            undoer: Default::default(),
+            undo_log: Default::default(),
             sync: Default::default(),
             ui_state: Default::default(),
+            snapshots: Default::default(),
+            import_snapshot: None,
             config: None,
             async_results: new_async_results(),
+            file_results: new_async_results(),
+            saved_files: new_async_results(),
         }
     }
 }
@@ -56,6 +94,8 @@ impl TemplateApp {
         };
 
         app.async_results = new_async_results();
+        app.file_results = new_async_results();
+        app.saved_files = new_async_results();
         app.config = AppConfig::load_public().ok();
         info!(
             target = "app",
@@ -64,13 +104,29 @@ impl TemplateApp {
             "initialized runtime app state"
         );
         app.ui_state.adopt_stored_session_email(app.sync.stored_session.as_ref());
-        app.sync.initialize_session(
-            &app.state,
-            &mut app.ui_state,
-            app.config.as_ref(),
-            &app.async_results,
-            cc.egui_ctx.clone(),
-        );
+        match login_link::take_redirect() {
+            Some(Ok(redirect)) => app.sync.start_link_login(
+                &mut app.ui_state,
+                app.config.as_ref(),
+                &app.async_results,
+                cc.egui_ctx.clone(),
+                redirect,
+            ),
+            redirect => {
+                if let Some(Err(err)) = redirect {
+                    warn!(target = "auth", error = %err, "magic link login failed");
+                    app.ui_state.set_error_message(format!("Login failed: {err}"));
+                    app.ui_state.set_show_login_window(true);
+                }
+                app.sync.initialize_session(
+                    &app.state,
+                    &mut app.ui_state,
+                    app.config.as_ref(),
+                    &app.async_results,
+                    cc.egui_ctx.clone(),
+                );
+            }
+        }
         app
     }
 
@@ -80,8 +136,9 @@ impl TemplateApp {
             results,
             ProcessAsyncContext {
                 state: &mut self.state,
-                undoer: &mut self.undoer,
+                undo_log: &mut self.undo_log,
                 ui_state: &mut self.ui_state,
+                snapshots: &mut self.snapshots,
                 config: self.config.as_ref(),
                 async_results: &self.async_results,
                 ctx,
@@ -89,10 +146,87 @@ impl TemplateApp {
         );
     }
 
+    /// Routes files picked through `files::open_text_file` to their window.
+    fn process_file_results(&mut self) {
+        for file in take_async_results(&self.file_results).into_iter().flatten() {
+            match (file.purpose, file.contents) {
+                (FilePurpose::Import, Ok(text)) => {
+                    debug!(target = "app", file_name = %file.name, bytes = text.len(), "opened file for import");
+                    self.ui_state.set_import_session(Some(ImportSession::new(file.name, text)));
+                }
+                (FilePurpose::Calendar, Ok(text)) => {
+                    debug!(target = "app", file_name = %file.name, bytes = text.len(), "opened calendar file");
+                    self.ui_state.set_calendar_import(Some(CalendarImport::new(file.name, text)));
+                }
+                (FilePurpose::Backup, Ok(text)) => {
+                    let backup = Backup::from_json(&text).map_err(|err| format!("{err:#}"));
+                    debug!(target = "app", file_name = %file.name, valid = backup.is_ok(), "opened backup file");
+                    self.ui_state.set_pending_restore(Some(PendingRestore {
+                        file_name: file.name,
+                        backup,
+                    }));
+                }
+                (_, Err(err)) => self.ui_state.set_error_message(err),
+            }
+        }
+    }
+
+    /// Reports files written through `files::save_text_file`.
+    fn process_saved_files(&mut self) {
+        for saved in take_async_results(&self.saved_files) {
+            match (saved.purpose, saved.result) {
+                (SavePurpose::Export { range, format, day_count }, Ok(true)) => {
+                    info!(target = "app", start_date = %range.0, end_date = %range.1, day_count, ?format, "exported days");
+                    self.ui_state.set_status_message(format!("Exported {} to {}", range.0, range.1));
+                    self.ui_state.set_show_export_window(false, range);
+                }
+                (SavePurpose::Backup { day_count }, Ok(true)) => {
+                    info!(target = "app", day_count, "wrote backup");
+                    self.ui_state.set_status_message(format!("Backed up {day_count} days."));
+                }
+                (purpose, Ok(false)) => debug!(target = "app", ?purpose, "saving file cancelled"),
+                (SavePurpose::Export { format, .. }, Err(err)) => {
+                    warn!(target = "app", error = %err, ?format, "export failed");
+                    self.ui_state.set_error_message(format!("Export failed: {err}"));
+                }
+                (SavePurpose::Backup { .. }, Err(err)) => {
+                    warn!(target = "app", error = %err, "backup failed");
+                    self.ui_state.set_error_message(format!("Backup failed: {err}"));
+                }
+            }
+        }
+    }
+
+    /// Swaps in restored local data. Restores are saved like any other edit,
+    /// so the days they change are queued for the server as well.
+    fn replace_state(&mut self, restored: State, time: f64) {
+        let before = std::mem::replace(&mut self.state, restored);
+        self.sync.queue_restored_days(&before, &self.state, &mut self.ui_state, time);
+    }
+
     fn reset_state(&mut self) {
+        self.snapshots
+            .take_all("Before resetting all data", &self.state, crate::ui::duration::local_now());
         self.state = State::default();
-        self.undoer = Default::default();
-        self.sync.clear_synced_week();
+        self.undo_log.clear();
+        // Resetting is local; the server keeps its days.
+        self.sync.rebase_outbox(&self.state);
+    }
+
+    fn undo(&mut self, time: f64) {
+        if !self.undo_log.undo(time, &mut self.state) {
+            warn!(target = "app", "undo skipped edits whose entries changed since");
+            self.ui_state
+                .set_status_message("Some undone edits no longer matched the week and were skipped.".to_string());
+        }
+    }
+
+    fn redo(&mut self, time: f64) {
+        if !self.undo_log.redo(time, &mut self.state) {
+            warn!(target = "app", "redo skipped edits whose entries changed since");
+            self.ui_state
+                .set_status_message("Some redone edits no longer matched the week and were skipped.".to_string());
+        }
     }
 
     pub fn duration(&self) -> time::Duration {
@@ -108,22 +242,339 @@ impl TemplateApp {
             .start_login(&mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
     }
 
+    fn send_login_email(&mut self, ctx: egui::Context) {
+        self.sync
+            .start_send_login_email(&mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
+    }
+
+    fn verify_login_code(&mut self, ctx: egui::Context) {
+        self.sync
+            .start_verify_login_code(&mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
+    }
+
     fn request_visible_week_load(&mut self, ctx: egui::Context) {
         self.sync
             .request_visible_week_load(&self.state, &mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
     }
 
-    fn save_visible_week(&mut self, ctx: egui::Context) {
+    fn save_now(&mut self, ctx: egui::Context) {
         self.sync
-            .save_visible_week(&self.state, &mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
+            .save_now(&self.state, &mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
     }
 
     fn logout(&mut self) {
         self.sync.logout(&mut self.ui_state);
     }
 
+    /// Applies a new week schedule. A clean week is reloaded afterwards so newly
+    /// shown weekdays pick up server data instead of local defaults.
+    fn set_schedule(&mut self, ctx: egui::Context, schedule: WeekSchedule) {
+        let reload = self.sync.is_logged_in() && !self.sync.is_week_dirty(&self.state);
+        debug!(
+            target = "app",
+            shown_days = schedule.shown_count(),
+            reload,
+            "applying week schedule"
+        );
+        self.state.set_schedule(schedule);
+        if reload {
+            self.request_visible_week_load(ctx);
+        }
+    }
+
+    /// Sets where the flex balance starts counting and fetches the server
+    /// history needed to cover the new range.
+    fn set_balance_start(&mut self, ctx: egui::Context, start: Option<chrono::NaiveDate>) {
+        self.state.set_balance_start(start);
+        self.sync
+            .request_balance_history_load(&self.state, &mut self.ui_state, self.config.as_ref(), &self.async_results, ctx);
+    }
+
+    /// Applies new break rules to every total, local or from server history.
+    fn set_break_rules(&mut self, rules: crate::ui::BreakRules) {
+        debug!(target = "app", rule_count = rules.rules.len(), "applying break rules");
+        self.state.set_break_rules(rules);
+    }
+
+    /// Returns the running flex balance through today, if a start date is set.
+    pub fn flex_balance(&self) -> Option<time::Duration> {
+        let start = self.state.balance_start()?;
+        let today = chrono::Local::now().date_naive();
+        Some(self.state.flex_balance(start, today, self.sync.history_days()))
+    }
+
+    /// Starts the clock-in timer on today's day and queues that day, which
+    /// need not be in the visible week.
+    fn start_timer(&mut self, time: f64) {
+        let now = crate::ui::duration::local_now();
+        if self.state.start_timer(now) {
+            info!(target = "app", started_at = %now, "started timer");
+            if let Some(date) = self.state.running_timer().map(|running| running.date) {
+                self.sync.queue_timer_day(&self.state, date, &mut self.ui_state, time);
+            }
+        }
+    }
+
+    /// Stops the clock-in timer, closing its entry at the current time, and
+    /// queues the entry's day.
+    fn stop_timer(&mut self, time: f64) {
+        if let Some(stopped) = self.state.stop_timer(crate::ui::duration::local_now()) {
+            info!(target = "app", date = %stopped.date, "stopped timer");
+            self.sync.queue_timer_day(&self.state, stopped.date, &mut self.ui_state, time);
+        }
+    }
+
+    /// Writes the days in `range` as CSV or iCalendar and offers the file for
+    /// saving.
+    fn export(&mut self, ctx: egui::Context, range: DateRange, format: ExportFormat, day_summary: bool) {
+        let days = export::days_in_range(&self.state, self.sync.history_days(), range);
+        let contents = match format {
+            ExportFormat::Csv => export::days_to_csv(&days, self.state.break_rules(), day_summary, crate::ui::duration::local_now()),
+            ExportFormat::Ical => ical::days_to_ics(&days, chrono::Utc::now()),
+        };
+        match contents {
+            Ok(contents) => files::save_text_file(
+                ctx,
+                &self.saved_files,
+                SavePurpose::Export {
+                    range,
+                    format,
+                    day_count: days.len(),
+                },
+                &export::export_file_name(range, format),
+                format.extension(),
+                format.mime_type(),
+                contents,
+            ),
+            Err(err) => {
+                warn!(target = "app", error = %err, ?format, "export failed");
+                self.ui_state.set_error_message(format!("Export failed: {err}"));
+            }
+        }
+    }
+
+    fn pick_import_file(&self, ctx: egui::Context) {
+        files::open_text_file(ctx, &self.file_results, FilePurpose::Import, "CSV or JSON", &["csv", "json", "txt"]);
+    }
+
+    /// Merges the previewed import into the state after taking a snapshot the
+    /// import can be rolled back to, and optionally queues the changed days for
+    /// saving to the server.
+    fn apply_import(&mut self, time: f64) {
+        let Some(session) = self.ui_state.import_session_mut() else {
+            return;
+        };
+        let Some(plan) = session.plan.as_ref().filter(|plan| plan.added() > 0) else {
+            return;
+        };
+        let days = plan.changed_days();
+        let (added, day_count) = (plan.added(), days.len());
+        let reason = format!("Before importing {}", session.file_name);
+        let push = session.push_to_server && self.sync.is_logged_in();
+        // The snapshot holds every imported date as it was before, including
+        // dates only known from server history, so a rollback restores them.
+        let previous = plan.previous_days();
+        session.invalidate_plan();
+
+        let snapshot = self.snapshots.take_days(reason, previous, crate::ui::duration::local_now());
+        self.import_snapshot = Some(snapshot);
+        info!(target = "app", day_count, entry_count = added, snapshot, "applying import");
+        self.state.merge_days(days.clone());
+        self.ui_state.set_status_message(format!(
+            "Imported {added} entries on {day_count} days. The import can be rolled back."
+        ));
+        if push {
+            self.sync.queue_days(&days, &mut self.ui_state, time);
+        }
+    }
+
+    pub(crate) fn snapshots(&self) -> &Snapshots {
+        &self.snapshots
+    }
+
+    pub(crate) fn can_roll_back_import(&self) -> bool {
+        self.import_snapshot.is_some_and(|id| self.snapshots.get(id).is_some())
+    }
+
+    /// Restores the state from before the last import.
+    fn roll_back_import(&mut self, time: f64) {
+        let Some(snapshot) = self.import_snapshot.take().and_then(|id| self.snapshots.remove(id)) else {
+            return;
+        };
+        info!(target = "app", snapshot = snapshot.id, "rolling back import");
+        let mut restored = self.state.clone();
+        snapshot.restore_into(&mut restored);
+        self.replace_state(restored, time);
+        if let Some(session) = self.ui_state.import_session_mut() {
+            session.invalidate_plan();
+        }
+        self.ui_state.set_status_message("Import rolled back.".to_string());
+    }
+
+    /// Writes all local days and settings to a JSON backup file.
+    fn export_backup(&mut self, ctx: egui::Context) {
+        let sync = SyncMetadata::new(
+            self.sync.stored_session.as_ref(),
+            self.config.as_ref().map(|config| config.supabase_url.as_str()),
+        );
+        let backup = Backup::new(&self.state, sync, crate::ui::duration::local_now());
+        match backup.to_json() {
+            Ok(contents) => files::save_text_file(
+                ctx,
+                &self.saved_files,
+                SavePurpose::Backup {
+                    day_count: backup.days.len(),
+                },
+                &backup.file_name(),
+                "json",
+                "application/json",
+                contents,
+            ),
+            Err(err) => {
+                warn!(target = "app", error = %err, "backup failed");
+                self.ui_state.set_error_message(format!("Backup failed: {err}"));
+            }
+        }
+    }
+
+    fn pick_backup_file(&self, ctx: egui::Context) {
+        files::open_text_file(ctx, &self.file_results, FilePurpose::Backup, "Backup", &["json"]);
+    }
+
+    /// Replaces all local data with the confirmed backup. The current data is
+    /// kept as a snapshot first.
+    fn restore_backup(&mut self, time: f64) {
+        let Some(PendingRestore {
+            file_name,
+            backup: Ok(backup),
+        }) = self.ui_state.pending_restore().cloned()
+        else {
+            return;
+        };
+        let snapshot = self.snapshots.take_all(
+            format!("Before restoring {file_name}"),
+            &self.state,
+            crate::ui::duration::local_now(),
+        );
+        info!(target = "app", day_count = backup.days.len(), snapshot, "restoring backup");
+        let mut restored = self.state.clone();
+        restored.restore_backup(backup.days, backup.settings);
+        self.replace_state(restored, time);
+        self.undo_log.rebase(&self.state);
+        self.import_snapshot = None;
+        self.ui_state.set_pending_restore(None);
+        self.ui_state.set_status_message(format!("Restored {file_name}."));
+    }
+
+    fn pick_calendar_file(&self, ctx: egui::Context) {
+        files::open_text_file(ctx, &self.file_results, FilePurpose::Calendar, "iCalendar", &["ics", "ical"]);
+    }
+
+    /// Adds the selected calendar events that still fit to the visible week.
+    /// This is a normal edit of the week, so it can be undone and is saved
+    /// with the week.
+    fn apply_calendar_import(&mut self) {
+        let now = crate::ui::duration::local_now();
+        let Some(calendar) = self.ui_state.calendar_import_mut() else {
+            return;
+        };
+        let Ok(events) = &calendar.events else {
+            return;
+        };
+        let statuses = ical::event_statuses(self.state.days(), events, &calendar.selected, now);
+        let mut added = 0;
+        for ((event, selected), status) in events.iter().zip(&calendar.selected).zip(statuses) {
+            if !*selected || status != ical::EventStatus::New {
+                continue;
+            }
+            let Some(day) = self.state.days_mut().iter_mut().find(|day| day.date == event.start.date()) else {
+                continue;
+            };
+            let entry = event.to_duration();
+            let start = entry.start_clock().total_minutes();
+            let ix = day
+                .durations
+                .iter()
+                .position(|duration| duration.start_clock().total_minutes() > start)
+                .unwrap_or(day.durations.len());
+            day.durations.insert(ix, entry);
+            day.enabled = true;
+            added += 1;
+        }
+        calendar.selected.iter_mut().for_each(|selected| *selected = false);
+        info!(target = "app", file_name = %calendar.file_name, added, "imported calendar events");
+        self.ui_state
+            .set_status_message(format!("Added {added} calendar events to this week."));
+    }
+
+    /// Keeps a snapshot of the day on `date` as it was before "Clear" removed
+    /// `entries` from it.
+    fn snapshot_cleared_day(&mut self, date: chrono::NaiveDate, entries: Vec<crate::ui::Duration>) {
+        let Some(mut previous) = self.state.days().iter().find(|day| day.date == date).cloned() else {
+            return;
+        };
+        previous.durations = entries;
+        let reason = format!("Before clearing {}", date.format("%a %Y-%m-%d"));
+        self.snapshots.take_days(reason, vec![previous], crate::ui::duration::local_now());
+    }
+
+    /// Puts back the days of a snapshot from the History window. The days it
+    /// replaces become a snapshot themselves, so restoring can be reverted too.
+    fn restore_snapshot(&mut self, id: u64, time: f64) {
+        let Some(snapshot) = self.snapshots.get(id).cloned() else {
+            return;
+        };
+        let taken_at = snapshot.taken_at_label();
+        let reason = format!("Before restoring the snapshot of {taken_at}");
+        let now = crate::ui::duration::local_now();
+        if snapshot.settings.is_some() {
+            self.snapshots.take_all(reason, &self.state, now);
+        } else {
+            let current = snapshot.days.iter().map(|day| self.state.local_day_or_default(day.date)).collect();
+            self.snapshots.take_days(reason, current, now);
+        }
+        info!(target = "app", snapshot = id, "restoring snapshot");
+        let mut restored = self.state.clone();
+        snapshot.restore_into(&mut restored);
+        self.replace_state(restored, time);
+        self.undo_log.rebase(&self.state);
+        self.import_snapshot = None;
+        self.ui_state
+            .set_status_message(format!("Restored the snapshot of {taken_at} ({}).", snapshot.reason));
+    }
+
+    /// Queues the merged version of a conflicting day and shows it.
+    fn resolve_conflict(&mut self, merge: &ConflictMerge, time: f64) {
+        match self.sync.resolve_conflict(&mut self.state, merge, time) {
+            Ok(()) => {
+                self.undo_log.rebase(&self.state);
+                self.ui_state
+                    .set_status_message(format!("Merged {}; saving it.", merge.date.format("%a %Y-%m-%d")));
+            }
+            Err(err) => {
+                warn!(target = "sync", error = %err, date = %merge.date, "cannot merge conflict");
+                self.ui_state.set_error_message(format!("Cannot merge {}: {err}", merge.date));
+            }
+        }
+    }
+
+    /// Makes sure server history for `range` is cached for the overviews.
+    fn request_history_range(&mut self, ctx: egui::Context, range: (chrono::NaiveDate, chrono::NaiveDate)) {
+        self.sync
+            .request_history_range(&mut self.ui_state, self.config.as_ref(), &self.async_results, ctx, range);
+    }
+
+    /// Jumps from an overview into the week editor for `week`. The overview stays
+    /// open when navigation is blocked, e.g. while logging in.
+    fn open_week(&mut self, ctx: egui::Context, week: WeekKey) {
+        self.navigate_to_week(ctx, week.year, week.week_nr as i32);
+        if self.state.current_week_key() == week {
+            self.ui_state.set_view(OverviewView::Week);
+        }
+    }
+
     fn navigate_to_week(&mut self, ctx: egui::Context, year: i32, week_nr: i32) {
-        if !self.sync.can_change_week(&self.state) {
+        if !self.sync.can_change_week() {
             warn!(
                 target = "sync",
                 year,
@@ -132,15 +583,20 @@ impl TemplateApp {
                 "blocked week navigation because current week cannot change"
             );
             self.ui_state
-                .set_error_message("Save the current week before changing weeks.".to_string());
+                .set_error_message("Wait for the login to finish before changing weeks.".to_string());
             return;
         }
 
+        // Queue edits made this frame before the visible week changes.
+        self.sync.observe_edits(&self.state, ctx.input(|input| input.time));
         self.ui_state.clear_error();
         debug!(target = "sync", year, week_nr, "navigating to week");
         self.state.set_current_week_normalized(year, week_nr);
-        self.sync.clear_synced_week();
+        if self.sync.show_cached_week(&mut self.state, &mut self.snapshots) {
+            self.undo_log.rebase(&self.state);
+        }
         if self.sync.is_logged_in() {
+            // Revalidate even a cached week; the cache may be stale.
             self.request_visible_week_load(ctx);
         }
     }
@@ -154,6 +610,30 @@ impl eframe::App for TemplateApp {
 
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         self.process_async_results(ctx);
+        self.process_file_results();
+        self.process_saved_files();
+        self.state.refresh_timer(crate::ui::duration::local_now());
+        if self.state.running_timer().is_some() {
+            // Keep the elapsed time in the header ticking.
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
         ui_shell::render(self, ctx, frame);
+        self.sync.observe_edits(&self.state, ctx.input(|input| input.time));
+        self.sync.flush_outbox(
+            &self.state,
+            &mut self.ui_state,
+            self.config.as_ref(),
+            &self.async_results,
+            ctx.clone(),
+            ctx.input(|input| input.time),
+        );
+        self.sync.maintain_realtime(
+            &self.state,
+            &mut self.ui_state,
+            self.config.as_ref(),
+            &self.async_results,
+            ctx,
+            ctx.input(|input| input.time),
+        );
     }
 }
@@ -0,0 +1,193 @@
+// Versioned JSON backups of everything the app keeps locally: all stored days,
+// the settings and which account they were synced with. Unlike the eframe
+// storage, a backup is an explicit file the user owns, so "Reset state" or a
+// new device does not have to mean losing data. Each file records its version
+// so later layouts can migrate older files on restore.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use super::contract::{ContractPeriod, ContractProfile};
+use super::schedule::WeekSchedule;
+use super::state::State;
+use crate::supabase::StoredSession;
+use crate::ui;
+
+/// Marks a JSON file as a backup of this app.
+const FORMAT_NAME: &str = "work-hours-backup";
+
+/// Version written by this build. Bump it together with a step in
+/// `migrate` whenever the layout of `Backup` changes incompatibly.
+pub(crate) const BACKUP_VERSION: u32 = 1;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct BackupSettings {
+    pub schedule: WeekSchedule,
+    pub contract: ContractProfile,
+    pub contract_periods: Vec<ContractPeriod>,
+    pub balance_start: Option<NaiveDate>,
+    pub break_rules: ui::BreakRules,
+}
+
+/// The account the backed up days were synced with. Tokens are never written
+/// to a backup, so restoring one does not log anybody in.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub(crate) struct SyncMetadata {
+    pub user_id: Option<String>,
+    pub email: Option<String>,
+    pub server_url: Option<String>,
+}
+
+impl SyncMetadata {
+    pub(crate) fn new(session: Option<&StoredSession>, server_url: Option<&str>) -> Self {
+        Self {
+            user_id: session.map(|session| session.user_id.clone()),
+            email: session.and_then(|session| session.email.clone()),
+            server_url: server_url.map(str::to_string),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct Backup {
+    format: String,
+    version: u32,
+    pub created_at: Option<time::OffsetDateTime>,
+    /// Every locally stored day, sorted by date.
+    pub days: Vec<ui::Day>,
+    pub settings: BackupSettings,
+    pub sync: SyncMetadata,
+}
+
+impl Backup {
+    pub(crate) fn new(state: &State, sync: SyncMetadata, now: time::OffsetDateTime) -> Self {
+        Self {
+            format: FORMAT_NAME.to_string(),
+            version: BACKUP_VERSION,
+            created_at: Some(now),
+            days: state.stored_days(),
+            settings: state.backup_settings(),
+            sync,
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("failed to write backup")
+    }
+
+    /// Reads a backup of any known version.
+    pub(crate) fn from_json(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text).context("not a JSON file")?;
+        let backup: Self = serde_json::from_value(migrate(value)?).context("backup does not match its version")?;
+        // The app always shows at least one weekday; a week without any breaks
+        // every week range.
+        if backup.settings.schedule.shown_count() == 0 {
+            return Err(anyhow!("backup schedule shows no weekdays"));
+        }
+        Ok(backup)
+    }
+
+    pub(crate) fn file_name(&self) -> String {
+        let date = self
+            .created_at
+            .map_or_else(String::new, |created_at| format!("_{}", created_at.date()));
+        format!("work_hours_backup{date}.json")
+    }
+}
+
+/// Upgrades a parsed backup to `BACKUP_VERSION`, one version at a time.
+fn migrate(value: Value) -> Result<Value> {
+    let version = match value.get("format").and_then(Value::as_str) {
+        Some(FORMAT_NAME) => value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| anyhow!("backup has no version"))?,
+        Some(other) => return Err(anyhow!("'{other}' is not a work hours backup")),
+        None => return Err(anyhow!("not a work hours backup")),
+    };
+    if version > u64::from(BACKUP_VERSION) {
+        return Err(anyhow!(
+            "backup version {version} was made by a newer version of the app (this one reads up to {BACKUP_VERSION})"
+        ));
+    }
+    upgrade(value, version)
+}
+
+/// One step per older layout: each arm converts its version to the next one
+/// and continues from there, until the current layout is reached.
+fn upgrade(value: Value, version: u64) -> Result<Value> {
+    match version {
+        // The current layout. Once version 2 exists, this arm becomes
+        // `1 => upgrade(from_v1(value), 2)` and `2 => Ok(value)` is added.
+        1 => Ok(value),
+        _ => Err(anyhow!("backup version {version} is not known")),
+    }
+}
+
+/// A picked backup file waiting for the user to confirm the restore.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingRestore {
+    pub file_name: String,
+    pub backup: Result<Backup, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backup, SyncMetadata, BACKUP_VERSION};
+    use crate::app::state::State;
+    use crate::ui;
+
+    #[test]
+    fn backup_round_trips_days_and_settings() {
+        let mut state = State::default();
+        state.set_balance_start(chrono::NaiveDate::from_ymd_opt(2026, 1, 5));
+        let day = &mut state.days_mut()[0];
+        day.durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        let sync = SyncMetadata {
+            user_id: Some("user-1".to_string()),
+            email: Some("me@example.com".to_string()),
+            server_url: None,
+        };
+
+        let backup = Backup::new(&state, sync.clone(), time::OffsetDateTime::UNIX_EPOCH);
+        let restored = Backup::from_json(&backup.to_json().unwrap()).unwrap();
+        assert_eq!(restored, backup);
+        assert_eq!(restored.sync, sync);
+
+        let mut target = State::default();
+        target.restore_backup(restored.days, restored.settings);
+        assert_eq!(target.balance_start(), state.balance_start());
+        assert_eq!(target.duration(), state.duration());
+    }
+
+    #[test]
+    fn other_files_and_newer_versions_are_rejected() {
+        let state = State::default();
+        let persisted = serde_json::json!({"state": serde_json::to_value(&state).unwrap()});
+        assert!(Backup::from_json(&persisted.to_string()).is_err());
+
+        let newer = serde_json::json!({"format": "work-hours-backup", "version": BACKUP_VERSION + 1});
+        let err = Backup::from_json(&newer.to_string()).unwrap_err();
+        assert!(err.to_string().contains("made by a newer version of the app"));
+
+        let unknown = serde_json::json!({"format": "work-hours-backup", "version": 0});
+        assert!(Backup::from_json(&unknown.to_string()).is_err());
+    }
+
+    #[test]
+    fn backup_without_shown_weekdays_is_rejected() {
+        let state = State::default();
+        let mut backup = Backup::new(&state, SyncMetadata::default(), time::OffsetDateTime::UNIX_EPOCH);
+        for weekday in crate::app::schedule::WEEKDAYS {
+            backup.settings.schedule.weekday_mut(weekday).shown = false;
+        }
+
+        let err = Backup::from_json(&backup.to_json().unwrap()).unwrap_err();
+        assert!(format!("{err:#}").contains("no weekdays"));
+    }
+}
@@ -0,0 +1,274 @@
+// Reader for Clockify detailed-report exports. Both the CSV export and the
+// JSON returned by Clockify's report/time-entry APIs are turned into
+// `import::ImportedEntry` values, so they go through the same preview, merge
+// and rollback as a generic CSV import.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use super::import::{ImportedEntry, ImportedTime};
+use crate::ui;
+
+const CSV_TIME_COLUMNS: [&str; 4] = ["Start Date", "Start Time", "End Date", "End Time"];
+const TIME_FORMATS: [&str; 4] = ["%H:%M:%S", "%I:%M:%S %p", "%H:%M", "%I:%M %p"];
+
+/// Recognizes a Clockify export by its JSON shape or its CSV time columns.
+pub(crate) fn is_clockify_export(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        return true;
+    }
+    let header = trimmed.lines().next().unwrap_or_default();
+    CSV_TIME_COLUMNS.iter().all(|column| header.contains(column))
+}
+
+/// Reads a Clockify export into entries plus per-entry errors, e.g. for
+/// entries that were still running when the report was made.
+pub(crate) fn parse_export(text: &str) -> Result<(Vec<ImportedEntry>, Vec<String>)> {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        parse_json(trimmed)
+    } else {
+        parse_csv(text)
+    }
+}
+
+/// A Clockify entry in local time, before it is assigned to a work date.
+struct ClockifyEntry {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    project: String,
+    description: String,
+    tags: Vec<String>,
+}
+
+impl ClockifyEntry {
+    /// Assigns the entry to the day it started on. Entries that run past
+    /// midnight keep their real end through the end day offset.
+    fn into_imported(self) -> Result<ImportedEntry> {
+        if self.end <= self.start {
+            return Err(anyhow!("entry starting {} does not end after its start", self.start));
+        }
+        let date = self.start.date();
+        let end_day_offset = (self.end.date() - date).num_days();
+        let end_day_offset = i8::try_from(end_day_offset).map_err(|_| anyhow!("entry starting {} is too long", self.start))?;
+        Ok(ImportedEntry {
+            date,
+            time: ImportedTime::Clocks {
+                start: clock(self.start),
+                end: clock(self.end),
+                end_day_offset,
+            },
+            project: self.project,
+            note: self.description,
+            tags: self.tags,
+        })
+    }
+}
+
+fn clock(value: NaiveDateTime) -> ui::TimePoint {
+    ui::TimePoint::new(value.hour() as u8, value.minute() as u8)
+}
+
+fn parse_csv(text: &str) -> Result<(Vec<ImportedEntry>, Vec<String>)> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+    let header = reader.headers().context("failed to read Clockify CSV header")?.clone();
+    let column = |name: &str| header.iter().position(|column| column.trim() == name);
+    let required = |name: &str| column(name).ok_or_else(|| anyhow!("Clockify CSV has no '{name}' column"));
+    let (start_date, start_time) = (required("Start Date")?, required("Start Time")?);
+    let (end_date, end_time) = (required("End Date")?, required("End Time")?);
+    let (project, description, tags) = (column("Project"), column("Description"), column("Tags"));
+
+    let rows = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .context("failed to read Clockify CSV")?;
+    let date_format = detect_date_format(rows.iter().filter_map(|row| row.get(start_date)));
+
+    let mut entries = vec![];
+    let mut errors = vec![];
+    for (ix, row) in rows.iter().enumerate() {
+        let cell = |ix: Option<usize>| ix.and_then(|ix| row.get(ix)).map_or("", str::trim);
+        let entry = parse_csv_datetime(cell(Some(start_date)), cell(Some(start_time)), date_format).and_then(|start| {
+            let end = parse_csv_datetime(cell(Some(end_date)), cell(Some(end_time)), date_format)?;
+            ClockifyEntry {
+                start,
+                end,
+                project: cell(project).to_string(),
+                description: cell(description).to_string(),
+                tags: split_tags(cell(tags)),
+            }
+            .into_imported()
+        });
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push(format!("line {}: {err}", ix + 2)),
+        }
+    }
+    Ok((entries, errors))
+}
+
+/// Clockify writes dates in the workspace's format. Slash dates are read as
+/// month-first, like Clockify's default, unless a day above 12 shows that the
+/// file is day-first.
+fn detect_date_format<'a>(dates: impl Iterator<Item = &'a str>) -> &'static str {
+    let dates: Vec<&str> = dates.map(str::trim).filter(|date| !date.is_empty()).collect();
+    let Some(sample) = dates.first() else {
+        return "%m/%d/%Y";
+    };
+    if NaiveDate::parse_from_str(sample, "%Y-%m-%d").is_ok() {
+        "%Y-%m-%d"
+    } else if sample.contains('.') {
+        "%d.%m.%Y"
+    } else if sample.contains('-') {
+        "%d-%m-%Y"
+    } else if dates.iter().any(|date| {
+        date.split('/')
+            .next()
+            .and_then(|first| first.parse::<u32>().ok())
+            .is_some_and(|first| first > 12)
+    }) {
+        "%d/%m/%Y"
+    } else {
+        "%m/%d/%Y"
+    }
+}
+
+fn parse_csv_datetime(date: &str, time: &str, date_format: &str) -> Result<NaiveDateTime> {
+    let date = NaiveDate::parse_from_str(date, date_format).with_context(|| format!("'{date}' is not a date"))?;
+    let time = TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&time.to_uppercase(), format).ok())
+        .ok_or_else(|| anyhow!("'{time}' is not a time of day"))?;
+    Ok(date.and_time(time))
+}
+
+fn split_tags(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads either a detailed report (`{"timeentries": [...]}`) or a plain list
+/// of time entries. Timestamps are converted to the local time zone.
+fn parse_json(text: &str) -> Result<(Vec<ImportedEntry>, Vec<String>)> {
+    let value: serde_json::Value = serde_json::from_str(text).context("failed to read Clockify JSON")?;
+    let items = match &value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(object) => object
+            .get("timeentries")
+            .or_else(|| object.get("timeEntries"))
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| anyhow!("Clockify JSON has no time entries"))?,
+        _ => return Err(anyhow!("Clockify JSON has no time entries")),
+    };
+
+    let mut entries = vec![];
+    let mut errors = vec![];
+    for (ix, item) in items.iter().enumerate() {
+        match parse_json_entry(item).and_then(ClockifyEntry::into_imported) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push(format!("entry {}: {err}", ix + 1)),
+        }
+    }
+    Ok((entries, errors))
+}
+
+fn parse_json_entry(item: &serde_json::Value) -> Result<ClockifyEntry> {
+    let text = |value: Option<&serde_json::Value>| value.and_then(serde_json::Value::as_str).unwrap_or_default().trim().to_string();
+    let interval = item.get("timeInterval").ok_or_else(|| anyhow!("no time interval"))?;
+    let start = json_local_time(interval.get("start")).context("invalid start")?;
+    let end = match interval.get("end") {
+        Some(serde_json::Value::Null) | None => return Err(anyhow!("still running")),
+        end => json_local_time(end).context("invalid end")?,
+    };
+    let project = match item.get("projectName") {
+        Some(name) => text(Some(name)),
+        None => text(item.get("project").and_then(|project| project.get("name"))),
+    };
+    let tags = item
+        .get("tags")
+        .and_then(serde_json::Value::as_array)
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|tag| tag.as_str().or_else(|| tag.get("name").and_then(serde_json::Value::as_str)))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(ClockifyEntry {
+        start,
+        end,
+        project,
+        description: text(item.get("description")),
+        tags,
+    })
+}
+
+fn json_local_time(value: Option<&serde_json::Value>) -> Result<NaiveDateTime> {
+    let value = value
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| anyhow!("missing timestamp"))?;
+    let timestamp = chrono::DateTime::parse_from_rfc3339(value).with_context(|| format!("'{value}' is not an RFC 3339 timestamp"))?;
+    Ok(timestamp.with_timezone(&chrono::Local).naive_local())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_clockify_export, parse_export};
+    use crate::app::import::ImportedTime;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn detailed_csv_splits_overnight_entries_by_start_date() {
+        let csv = "\"Project\",\"Client\",\"Description\",\"Task\",\"Tags\",\"Start Date\",\"Start Time\",\"End Date\",\"End Time\",\"Duration (h)\"\n\
+            \"Ops\",\"\",\"Release\",\"\",\"oncall, night\",\"05/11/2026\",\"10:30:00 PM\",\"05/12/2026\",\"01:15:00 AM\",\"02:45:00\"\n\
+            \"Web\",\"\",\"Review\",\"\",\"\",\"05/12/2026\",\"09:00:00 AM\",\"05/12/2026\",\"11:00:00 AM\",\"02:00:00\"\n\
+            \"Web\",\"\",\"Broken\",\"\",\"\",\"05/12/2026\",\"noon\",\"05/12/2026\",\"11:00:00 AM\",\"\"\n";
+        assert!(is_clockify_export(csv));
+
+        let (entries, errors) = parse_export(csv).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("line 4:"));
+
+        let overnight = &entries[0];
+        assert_eq!(overnight.date, NaiveDate::from_ymd_opt(2026, 5, 11).unwrap());
+        assert_eq!(overnight.project, "Ops");
+        assert_eq!(overnight.note, "Release");
+        assert_eq!(overnight.tags, ["oncall", "night"]);
+        let ImportedTime::Clocks {
+            start,
+            end,
+            end_day_offset,
+        } = &overnight.time
+        else {
+            panic!("expected clock times");
+        };
+        assert_eq!((start.total_minutes(), end.total_minutes(), *end_day_offset), (22 * 60 + 30, 75, 1));
+    }
+
+    #[test]
+    fn json_report_reads_hydrated_entries_and_skips_running_ones() {
+        let json = r#"{"timeentries": [
+            {"description": "Standup", "projectName": "Team", "tags": [{"name": "meeting"}],
+             "timeInterval": {"start": "2026-05-11T09:00:00Z", "end": "2026-05-11T09:15:00Z", "duration": 900}},
+            {"description": "Timer", "project": {"name": "Team"}, "tags": ["focus"],
+             "timeInterval": {"start": "2026-05-11T10:00:00Z", "end": null}}
+        ]}"#;
+        assert!(is_clockify_export(json));
+
+        let (entries, errors) = parse_export(json).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(errors, ["entry 2: still running"]);
+        assert_eq!(entries[0].project, "Team");
+        assert_eq!(entries[0].tags, ["meeting"]);
+        let ImportedTime::Clocks { start, end, .. } = &entries[0].time else {
+            panic!("expected clock times");
+        };
+        assert_eq!(end.total_minutes() - start.total_minutes(), 15);
+    }
+}
@@ -0,0 +1,244 @@
+// Three-way merge of a day that was saved on another device while this one
+// still had unsaved edits of it. Both versions are compared with the base
+// they started from: an entry one side added is kept, an entry one side
+// removed is dropped, and the user can flip any entry before the merged day
+// is saved on top of the server version.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::supabase::{WorkDayDraft, WorkEntryDraft};
+
+/// A day the server rejected as stale, kept until the user merged it.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct DayConflict {
+    /// The server version the local edits started from, if the app saw one.
+    pub base: Option<WorkDayDraft>,
+    pub local: WorkDayDraft,
+    pub server: WorkDayDraft,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Side {
+    Local,
+    Server,
+}
+
+/// One distinct entry of either version and whether the merge keeps it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MergeEntry {
+    pub entry: WorkEntryDraft,
+    pub in_base: bool,
+    pub in_local: bool,
+    pub in_server: bool,
+    pub keep: bool,
+}
+
+impl MergeEntry {
+    pub(crate) fn label(&self) -> String {
+        entry_label(&self.entry)
+    }
+}
+
+/// The user's choices for one conflicting day.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConflictMerge {
+    pub date: NaiveDate,
+    pub entries: Vec<MergeEntry>,
+    /// Whose target and enabled flag the merged day takes.
+    pub settings_from: Side,
+}
+
+impl ConflictMerge {
+    pub(crate) fn new(date: NaiveDate, conflict: &DayConflict) -> Self {
+        let base = conflict.base.as_ref().map_or(&[][..], |base| base.work_entries.as_slice());
+        let mut entries: Vec<MergeEntry> = vec![];
+        let local = conflict.local.work_entries.iter().map(|entry| (entry, Side::Local));
+        let server = conflict.server.work_entries.iter().map(|entry| (entry, Side::Server));
+        for (entry, side) in local.chain(server) {
+            let index = match entries.iter().position(|merge| same_entry(&merge.entry, entry)) {
+                Some(index) => index,
+                None => {
+                    entries.push(MergeEntry {
+                        entry: entry.clone(),
+                        in_base: base.iter().any(|base| same_entry(base, entry)),
+                        in_local: false,
+                        in_server: false,
+                        keep: false,
+                    });
+                    entries.len() - 1
+                }
+            };
+            match side {
+                Side::Local => entries[index].in_local = true,
+                Side::Server => entries[index].in_server = true,
+            }
+        }
+        for merge in &mut entries {
+            // Present on both sides, or added by one of them since the base.
+            merge.keep = (merge.in_local && merge.in_server) || !merge.in_base;
+        }
+        entries.sort_by_key(|merge| merge.entry.starts_at);
+
+        let local_changed_settings = conflict.base.as_ref().map_or(true, |base| {
+            base.enabled != conflict.local.enabled || base.target_minutes != conflict.local.target_minutes
+        });
+        Self {
+            date,
+            entries,
+            settings_from: if local_changed_settings { Side::Local } else { Side::Server },
+        }
+    }
+
+    /// Chooses one version as a whole.
+    pub(crate) fn take_side(&mut self, side: Side) {
+        for merge in &mut self.entries {
+            merge.keep = match side {
+                Side::Local => merge.in_local,
+                Side::Server => merge.in_server,
+            };
+        }
+        self.settings_from = side;
+    }
+
+    /// The merged day, based on the server version so it saves cleanly.
+    pub(crate) fn merged(&self, conflict: &DayConflict) -> WorkDayDraft {
+        let settings = match self.settings_from {
+            Side::Local => &conflict.local,
+            Side::Server => &conflict.server,
+        };
+        let mut ids = HashSet::new();
+        let work_entries = self
+            .entries
+            .iter()
+            .filter(|merge| merge.keep)
+            .zip(0..)
+            .map(|(merge, sort_index)| {
+                let mut entry = WorkEntryDraft {
+                    sort_index,
+                    ..merge.entry.clone()
+                };
+                // An entry edited on both sides is listed once per version,
+                // with the same id; keeping both needs a new id for one.
+                if !ids.insert(entry.id) {
+                    entry.id = Uuid::new_v4();
+                }
+                entry
+            })
+            .collect();
+        WorkDayDraft {
+            work_date: self.date,
+            target_minutes: settings.target_minutes,
+            enabled: settings.enabled,
+            work_entries,
+            updated_at: conflict.server.updated_at,
+        }
+    }
+}
+
+/// Entries are the same when they cover the same time with the same fields;
+/// their position in the day does not matter.
+fn same_entry(a: &WorkEntryDraft, b: &WorkEntryDraft) -> bool {
+    a.starts_at == b.starts_at && a.ends_at == b.ends_at && a.metadata == b.metadata
+}
+
+/// Local clock times plus the most telling field, e.g. "09:00-12:00 billing".
+pub(crate) fn entry_label(entry: &WorkEntryDraft) -> String {
+    let clock = |at: chrono::DateTime<chrono::Utc>| at.with_timezone(&chrono::Local).format("%H:%M").to_string();
+    let end = entry.ends_at.map_or_else(|| "running".to_string(), clock);
+    let field = |name: &str| {
+        entry
+            .metadata
+            .get(name)
+            .and_then(serde_json::Value::as_str)
+            .filter(|value| !value.is_empty())
+    };
+    let detail = if field("kind") == Some("break") {
+        Some("break")
+    } else {
+        field("project").or_else(|| field("note"))
+    };
+    match detail {
+        Some(detail) => format!("{}-{end} {detail}", clock(entry.starts_at)),
+        None => format!("{}-{end}", clock(entry.starts_at)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConflictMerge, DayConflict, Side};
+    use crate::supabase::{WorkDayDraft, WorkEntryDraft};
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn entry(hour: u32) -> WorkEntryDraft {
+        WorkEntryDraft {
+            id: Uuid::from_u128(u128::from(hour)),
+            starts_at: Utc.with_ymd_and_hms(2026, 5, 4, hour, 0, 0).unwrap(),
+            ends_at: Some(Utc.with_ymd_and_hms(2026, 5, 4, hour + 1, 0, 0).unwrap()),
+            metadata: serde_json::json!({}),
+            sort_index: 0,
+        }
+    }
+
+    fn day(entries: &[u32], target_minutes: i32) -> WorkDayDraft {
+        WorkDayDraft {
+            work_date: NaiveDate::from_ymd_opt(2026, 5, 4).unwrap(),
+            target_minutes,
+            enabled: true,
+            work_entries: entries.iter().map(|hour| entry(*hour)).collect(),
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn merge_keeps_additions_and_drops_removals_of_either_side() {
+        // Base 8 and 10; this device removed 10 and added 12, the server
+        // removed 8 and added 14.
+        let mut server = day(&[10, 14], 480);
+        server.updated_at = Some(Utc.with_ymd_and_hms(2026, 5, 4, 18, 0, 0).unwrap());
+        let conflict = DayConflict {
+            base: Some(day(&[8, 10], 480)),
+            local: day(&[8, 12], 240),
+            server,
+        };
+        let mut merge = ConflictMerge::new(conflict.local.work_date, &conflict);
+        let kept: Vec<_> = merge
+            .entries
+            .iter()
+            .filter(|merge| merge.keep)
+            .map(|merge| merge.entry.starts_at)
+            .collect();
+        assert_eq!(kept, [entry(12).starts_at, entry(14).starts_at]);
+        assert_eq!(merge.settings_from, Side::Local);
+
+        let merged = merge.merged(&conflict);
+        assert_eq!(merged.target_minutes, 240);
+        assert_eq!(merged.updated_at, conflict.server.updated_at);
+        assert_eq!(merged.work_entries.iter().map(|entry| entry.sort_index).collect::<Vec<_>>(), [0, 1]);
+
+        merge.take_side(Side::Server);
+        let starts = |day: &WorkDayDraft| day.work_entries.iter().map(|entry| entry.starts_at).collect::<Vec<_>>();
+        assert_eq!(starts(&merge.merged(&conflict)), starts(&conflict.server));
+    }
+
+    #[test]
+    fn keeping_both_versions_of_an_entry_gives_one_a_new_id() {
+        // The same entry was moved to 12:00 here and to 14:00 on the server.
+        let mut local = day(&[12], 480);
+        let mut server = day(&[14], 480);
+        local.work_entries[0].id = entry(8).id;
+        server.work_entries[0].id = entry(8).id;
+        let conflict = DayConflict {
+            base: Some(day(&[8], 480)),
+            local,
+            server,
+        };
+        let merged = ConflictMerge::new(conflict.local.work_date, &conflict).merged(&conflict);
+        assert_eq!(merged.work_entries.len(), 2);
+        assert_eq!(merged.work_entries[0].id, entry(8).id);
+        assert_ne!(merged.work_entries[1].id, entry(8).id);
+    }
+}
@@ -0,0 +1,245 @@
+// Contract model for daily targets. A profile answers "how long should I work
+// on this weekday", independent of which weekdays the week schedule shows.
+// Contract periods attach a profile to a date range so targets can change over
+// time without rewriting history.
+
+use crate::supabase::{duration_to_minutes, minutes_to_duration, ContractPeriodDraft};
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use super::schedule::WEEKDAYS;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct ContractProfile {
+    pub name: String,
+    weekday_targets: [time::Duration; 7],
+}
+
+impl Default for ContractProfile {
+    fn default() -> Self {
+        Self::full_time()
+    }
+}
+
+impl ContractProfile {
+    /// Full-time 38h contract spread evenly over Monday to Friday.
+    pub fn full_time() -> Self {
+        Self::from_weekly_target("Full-time 38h", time::Duration::hours(38), &WEEKDAYS[..5])
+    }
+
+    /// Spreads a weekly target evenly over `weekdays` at minute precision.
+    ///
+    /// Any leftover minutes go to the earliest weekdays so the profile always
+    /// adds up to exactly `weekly_target`.
+    pub fn from_weekly_target(name: &str, weekly_target: time::Duration, weekdays: &[Weekday]) -> Self {
+        let mut weekday_targets = [time::Duration::ZERO; 7];
+        if !weekdays.is_empty() {
+            let count = weekdays.len() as i64;
+            let total_minutes = weekly_target.whole_minutes();
+            let (per_day, remainder) = (total_minutes / count, total_minutes % count);
+            for (ix, weekday) in weekdays.iter().enumerate() {
+                let extra = i64::from((ix as i64) < remainder);
+                weekday_targets[weekday.num_days_from_monday() as usize] = time::Duration::minutes(per_day + extra);
+            }
+        }
+        Self {
+            name: name.to_string(),
+            weekday_targets,
+        }
+    }
+
+    /// Built-in profiles offered in the settings window.
+    pub fn presets() -> Vec<Self> {
+        let mut short_friday = Self::from_weekly_target("Part-time 36h (8h Mon-Thu, 4h Fri)", time::Duration::hours(32), &WEEKDAYS[..4]);
+        short_friday.set_target(Weekday::Fri, time::Duration::hours(4));
+
+        vec![
+            Self::full_time(),
+            Self::from_weekly_target("Full-time 40h", time::Duration::hours(40), &WEEKDAYS[..5]),
+            Self::from_weekly_target("4/5 (Mon-Thu)", time::Duration::hours(38) * 4 / 5, &WEEKDAYS[..4]),
+            short_friday,
+            Self::from_weekly_target("Half-time 19h", time::Duration::hours(19), &WEEKDAYS[..5]),
+        ]
+    }
+
+    pub fn target_for(&self, weekday: Weekday) -> time::Duration {
+        self.weekday_targets[weekday.num_days_from_monday() as usize]
+    }
+
+    pub fn set_target(&mut self, weekday: Weekday, target: time::Duration) {
+        self.weekday_targets[weekday.num_days_from_monday() as usize] = target;
+    }
+
+    /// Sum of all weekday targets, regardless of which weekdays are shown.
+    pub fn weekly_target(&self) -> time::Duration {
+        self.weekday_targets.iter().fold(time::Duration::ZERO, |sum, target| sum + *target)
+    }
+}
+
+/// A contract profile that is valid from `start` up to and including `end`.
+/// An open `end` means the period is still running.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct ContractPeriod {
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+    pub profile: ContractProfile,
+}
+
+impl ContractPeriod {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.start && self.end.map_or(true, |end| date <= end)
+    }
+
+    pub(crate) fn to_draft(&self) -> Result<ContractPeriodDraft> {
+        let weekday_target_minutes = WEEKDAYS
+            .into_iter()
+            .map(|weekday| duration_to_minutes(self.profile.target_for(weekday)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ContractPeriodDraft {
+            starts_on: self.start,
+            ends_on: self.end,
+            name: self.profile.name.clone(),
+            weekday_target_minutes,
+        })
+    }
+
+    pub(crate) fn from_draft(draft: ContractPeriodDraft) -> Result<Self> {
+        if draft.weekday_target_minutes.len() != WEEKDAYS.len() {
+            return Err(anyhow!(
+                "contract period starting {} has {} weekday targets instead of 7",
+                draft.starts_on,
+                draft.weekday_target_minutes.len()
+            ));
+        }
+        let mut profile = ContractProfile {
+            name: draft.name,
+            weekday_targets: [time::Duration::ZERO; 7],
+        };
+        for (weekday, minutes) in WEEKDAYS.into_iter().zip(draft.weekday_target_minutes) {
+            profile.set_target(weekday, minutes_to_duration(minutes));
+        }
+        Ok(Self {
+            start: draft.starts_on,
+            end: draft.ends_on,
+            profile,
+        })
+    }
+}
+
+/// Picks the profile that was valid on `date`.
+///
+/// When periods overlap, the one that started most recently wins. Dates not
+/// covered by any period fall back to `fallback`.
+pub(crate) fn profile_for_date<'a>(periods: &'a [ContractPeriod], fallback: &'a ContractProfile, date: NaiveDate) -> &'a ContractProfile {
+    periods
+        .iter()
+        .filter(|period| period.contains(date))
+        .max_by_key(|period| period.start)
+        .map(|period| &period.profile)
+        .unwrap_or(fallback)
+}
+
+/// Target for `date` according to the profile that was valid on that day.
+pub(crate) fn target_for_date(periods: &[ContractPeriod], fallback: &ContractProfile, date: NaiveDate) -> time::Duration {
+    profile_for_date(periods, fallback, date).target_for(date.weekday())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{profile_for_date, target_for_date, ContractPeriod, ContractProfile};
+    use crate::app::schedule::WEEKDAYS;
+    use chrono::{NaiveDate, Weekday};
+
+    #[test]
+    fn full_time_profile_matches_legacy_daily_target() {
+        let profile = ContractProfile::full_time();
+        assert_eq!(
+            profile.target_for(Weekday::Mon),
+            time::Duration::hours(7) + time::Duration::minutes(36)
+        );
+        assert_eq!(profile.target_for(Weekday::Sat), time::Duration::ZERO);
+        assert_eq!(profile.weekly_target(), time::Duration::hours(38));
+    }
+
+    #[test]
+    fn weekly_target_remainder_goes_to_earliest_days() {
+        let profile = ContractProfile::from_weekly_target("odd", time::Duration::minutes(302), &WEEKDAYS[..3]);
+        assert_eq!(profile.target_for(Weekday::Mon), time::Duration::minutes(101));
+        assert_eq!(profile.target_for(Weekday::Tue), time::Duration::minutes(101));
+        assert_eq!(profile.target_for(Weekday::Wed), time::Duration::minutes(100));
+        assert_eq!(profile.weekly_target(), time::Duration::minutes(302));
+    }
+
+    #[test]
+    fn presets_include_uneven_part_time_contract() {
+        let profile = ContractProfile::presets()
+            .into_iter()
+            .find(|profile| profile.target_for(Weekday::Fri) == time::Duration::hours(4))
+            .unwrap();
+        assert_eq!(profile.target_for(Weekday::Thu), time::Duration::hours(8));
+        assert_eq!(profile.weekly_target(), time::Duration::hours(36));
+    }
+
+    #[test]
+    fn period_target_follows_effective_date() {
+        let fallback = ContractProfile::full_time();
+        let reduced = ContractProfile::from_weekly_target("32h", time::Duration::hours(32), &WEEKDAYS[..4]);
+        let periods = vec![ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            end: None,
+            profile: reduced,
+        }];
+
+        let before = NaiveDate::from_ymd_opt(2026, 6, 29).unwrap();
+        let after = NaiveDate::from_ymd_opt(2026, 7, 6).unwrap();
+        assert_eq!(profile_for_date(&periods, &fallback, before).name, fallback.name);
+        assert_eq!(target_for_date(&periods, &fallback, after), time::Duration::hours(8));
+        assert_eq!(
+            target_for_date(&periods, &fallback, NaiveDate::from_ymd_opt(2026, 7, 10).unwrap()),
+            time::Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn later_period_wins_and_closed_periods_end() {
+        let fallback = ContractProfile::full_time();
+        let periods = vec![
+            ContractPeriod {
+                start: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                end: Some(NaiveDate::from_ymd_opt(2025, 12, 31).unwrap()),
+                profile: ContractProfile::from_weekly_target("20h", time::Duration::hours(20), &WEEKDAYS[..5]),
+            },
+            ContractPeriod {
+                start: NaiveDate::from_ymd_opt(2025, 6, 1).unwrap(),
+                end: Some(NaiveDate::from_ymd_opt(2025, 6, 30).unwrap()),
+                profile: ContractProfile::from_weekly_target("10h", time::Duration::hours(10), &WEEKDAYS[..5]),
+            },
+        ];
+
+        assert_eq!(
+            profile_for_date(&periods, &fallback, NaiveDate::from_ymd_opt(2025, 6, 10).unwrap()).name,
+            "10h"
+        );
+        assert_eq!(
+            profile_for_date(&periods, &fallback, NaiveDate::from_ymd_opt(2025, 7, 10).unwrap()).name,
+            "20h"
+        );
+        assert_eq!(
+            profile_for_date(&periods, &fallback, NaiveDate::from_ymd_opt(2026, 1, 5).unwrap()).name,
+            fallback.name
+        );
+    }
+
+    #[test]
+    fn contract_period_round_trips_through_draft() {
+        let period = ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            end: Some(NaiveDate::from_ymd_opt(2027, 6, 30).unwrap()),
+            profile: ContractProfile::presets().remove(3),
+        };
+
+        let draft = period.to_draft().unwrap();
+        assert_eq!(draft.weekday_target_minutes, vec![480, 480, 480, 480, 240, 0, 0]);
+        assert_eq!(ContractPeriod::from_draft(draft).unwrap(), period);
+    }
+}
@@ -0,0 +1,174 @@
+// CSV export for payroll and spreadsheets, and the export format choice shared
+// with the calendar export in `ical`. Days are read through
+// `State::recorded_day`, like the overviews, so unsaved local edits are
+// exported as shown in the app.
+
+use std::collections::HashMap;
+
+use crate::ui::{self, duration};
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDate};
+
+use super::report::DateRange;
+use super::state::State;
+
+const ENTRY_HEADER: [&str; 11] = [
+    "date",
+    "weekday",
+    "start",
+    "end",
+    "duration",
+    "day_target",
+    "enabled",
+    "kind",
+    "project",
+    "note",
+    "tags",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ExportFormat {
+    /// Spreadsheet rows, see `days_to_csv`.
+    #[default]
+    Csv,
+    /// Calendar events, see `ical::days_to_ics`.
+    Ical,
+}
+
+impl ExportFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ical => "ics",
+        }
+    }
+
+    pub(crate) fn mime_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Ical => "text/calendar",
+        }
+    }
+}
+
+const DAY_HEADER: [&str; 8] = ["date", "weekday", "target", "done", "break", "balance", "enabled", "entries"];
+
+/// Days to export for `range`: recorded days, plus default days for shown
+/// weekdays without data so the summary lists every expected workday.
+pub(crate) fn days_in_range(state: &State, history: &HashMap<NaiveDate, ui::Day>, (start, end): DateRange) -> Vec<ui::Day> {
+    start
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .filter_map(|date| match state.recorded_day(date, history) {
+            Some(day) => Some(day.clone()),
+            None if state.schedule().weekday(date.weekday()).shown => Some(state.default_day_for_date(date)),
+            None => None,
+        })
+        .collect()
+}
+
+/// Writes one row per entry, with its tags joined by `;`, and, when
+/// `day_summary` is set, a second table with one row per day below it,
+/// separated by an empty line. Open entries are exported without end and
+/// counted up to `now`; day totals deduct breaks by `rules`.
+pub(crate) fn days_to_csv(days: &[ui::Day], rules: &ui::BreakRules, day_summary: bool, now: time::OffsetDateTime) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(vec![]);
+    writer.write_record(ENTRY_HEADER)?;
+    for day in days {
+        let weekday = day.date.format("%A").to_string();
+        let target = format_hours_minutes(day.configured_target());
+        for entry in &day.durations {
+            writer.write_record([
+                day.date.to_string(),
+                weekday.clone(),
+                format_clock(entry.start_clock()),
+                entry.end_clock().map(format_clock).unwrap_or_default(),
+                format_hours_minutes(entry.duration_at(day.date, now)),
+                target.clone(),
+                day.enabled.to_string(),
+                if entry.is_break() { "break" } else { "work" }.to_string(),
+                entry.project().to_string(),
+                entry.note().to_string(),
+                entry.tags().join(";"),
+            ])?;
+        }
+    }
+
+    if day_summary {
+        writer.write_record([""])?;
+        writer.write_record(DAY_HEADER)?;
+        for day in days {
+            let done = day.duration_at(now, rules);
+            writer.write_record([
+                day.date.to_string(),
+                day.date.format("%A").to_string(),
+                format_hours_minutes(day.target()),
+                format_hours_minutes(done),
+                format_hours_minutes(day.break_at(now, rules)),
+                duration::format_signed_duration(done - day.target(), duration::DURATION_FORMAT),
+                day.enabled.to_string(),
+                day.durations.len().to_string(),
+            ])?;
+        }
+    }
+
+    let bytes = writer.into_inner().context("failed to finish CSV export")?;
+    String::from_utf8(bytes).context("CSV export is not valid UTF-8")
+}
+
+/// Suggested file name for an export of `range`.
+pub(crate) fn export_file_name((start, end): DateRange, format: ExportFormat) -> String {
+    format!("work_hours_{start}_{end}.{}", format.extension())
+}
+
+fn format_clock(clock: &ui::TimePoint) -> String {
+    format!("{:02}:{:02}", clock.hour(), clock.minute())
+}
+
+fn format_hours_minutes(value: time::Duration) -> String {
+    duration::format_duration(value, duration::DURATION_FORMAT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{days_in_range, days_to_csv};
+    use crate::app::state::State;
+    use crate::ui;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    #[test]
+    fn csv_has_entry_rows_and_optional_day_summary() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let monday = NaiveDate::from_ymd_opt(2026, 5, 11).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_778_482_800).unwrap();
+        let mut entry = ui::Duration::new(monday, start, start + time::Duration::hours(2));
+        *entry.project_mut() = "billing".to_string();
+        *entry.note_mut() = "invoices, reminders".to_string();
+        entry.set_tags(vec!["client".to_string(), "urgent".to_string()]);
+        state.days_mut()[0].durations.push(entry);
+
+        let days = days_in_range(&state, &HashMap::new(), (monday, monday + chrono::Duration::days(6)));
+        assert_eq!(days.len(), 5);
+
+        let csv = days_to_csv(&days, &Default::default(), false, start).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "date,weekday,start,end,duration,day_target,enabled,kind,project,note,tags"
+        );
+        assert_eq!(
+            lines[1],
+            "2026-05-11,Monday,07:00,09:00,02:00,07:36,true,work,billing,\"invoices, reminders\",client;urgent"
+        );
+        assert_eq!(lines.len(), 2);
+
+        let csv = days_to_csv(&days, &Default::default(), true, start).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[2], "\"\"");
+        assert_eq!(lines[3], "date,weekday,target,done,break,balance,enabled,entries");
+        assert_eq!(lines[4], "2026-05-11,Monday,07:36,02:00,00:00,-05:36,true,1");
+        assert_eq!(lines.len(), 9);
+    }
+}
@@ -0,0 +1,166 @@
+// Platform file access for exports and imports. Native builds use rfd's async
+// save dialog and write the file directly; web builds hand the contents to the
+// browser as a download. Opening goes through rfd's async dialog on both
+// platforms, since browsers only hand out file contents asynchronously. Both
+// directions report back through `AsyncResults`.
+
+use super::export::ExportFormat;
+use super::report::DateRange;
+use super::tasks::{spawn_async_task, AsyncResults};
+
+/// What a picked file is going to be used for, so the result can be routed
+/// back to the right window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FilePurpose {
+    Import,
+    Calendar,
+    Backup,
+}
+
+/// What a saved file contained, so the outcome can be reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SavePurpose {
+    Export {
+        range: DateRange,
+        format: ExportFormat,
+        day_count: usize,
+    },
+    Backup {
+        day_count: usize,
+    },
+}
+
+/// Outcome of offering a file for saving; `Ok(false)` when the user cancelled
+/// the save dialog.
+#[derive(Debug, Clone)]
+pub(crate) struct SavedFile {
+    pub purpose: SavePurpose,
+    pub result: Result<bool, String>,
+}
+
+/// A text file picked by the user. `contents` holds a readable error when the
+/// file was not valid UTF-8.
+#[derive(Debug, Clone)]
+pub(crate) struct OpenedFile {
+    pub purpose: FilePurpose,
+    pub name: String,
+    pub contents: Result<String, String>,
+}
+
+/// Lets the user pick a text file and queues its contents. Nothing is queued
+/// when the dialog is cancelled.
+pub(crate) fn open_text_file(
+    ctx: egui::Context,
+    results: &AsyncResults<Option<OpenedFile>>,
+    purpose: FilePurpose,
+    filter_name: &str,
+    extensions: &[&str],
+) {
+    let filter_name = filter_name.to_string();
+    let extensions: Vec<String> = extensions.iter().map(|extension| extension.to_string()).collect();
+    spawn_async_task(ctx, results.clone(), async move {
+        let file = rfd::AsyncFileDialog::new().add_filter(filter_name, &extensions).pick_file().await?;
+        let bytes = file.read().await;
+        let contents = String::from_utf8(bytes)
+            .map(|text| text.trim_start_matches('\u{feff}').to_string())
+            .map_err(|_| format!("{} is not a UTF-8 text file", file.file_name()));
+        Some(OpenedFile {
+            purpose,
+            name: file.file_name(),
+            contents,
+        })
+    });
+}
+
+/// Offers `contents` as a file named `file_name` and queues the outcome. The
+/// native dialog runs on a background task: rfd's portal backend needs an
+/// async runtime, which the UI thread does not have.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save_text_file(
+    ctx: egui::Context,
+    results: &AsyncResults<SavedFile>,
+    purpose: SavePurpose,
+    file_name: &str,
+    extension: &str,
+    _mime_type: &str,
+    contents: String,
+) {
+    let file_name = file_name.to_string();
+    let extension = extension.to_string();
+    spawn_async_task(ctx, results.clone(), async move {
+        let file = rfd::AsyncFileDialog::new()
+            .set_file_name(file_name)
+            .add_filter(extension.to_uppercase(), &[extension])
+            .save_file()
+            .await;
+        let result = match file {
+            Some(file) => std::fs::write(file.path(), contents)
+                .map(|()| true)
+                .map_err(|err| format!("failed to write {}: {err}", file.path().display())),
+            None => Ok(false),
+        };
+        SavedFile { purpose, result }
+    });
+}
+
+/// Offers `contents` as a file named `file_name` and queues the outcome.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn save_text_file(
+    ctx: egui::Context,
+    results: &AsyncResults<SavedFile>,
+    purpose: SavePurpose,
+    file_name: &str,
+    _extension: &str,
+    mime_type: &str,
+    contents: String,
+) {
+    let result = download_text_file(file_name, mime_type, &contents)
+        .map(|()| true)
+        .map_err(|err| format!("{err:#}"));
+    spawn_async_task(ctx, results.clone(), async move { SavedFile { purpose, result } });
+}
+
+/// Browsers start a download asynchronously after the click, so the blob URL
+/// is only revoked after this many milliseconds.
+#[cfg(target_arch = "wasm32")]
+const REVOKE_DOWNLOAD_URL_MS: i32 = 60_000;
+
+/// Hands `contents` to the browser as a download.
+#[cfg(target_arch = "wasm32")]
+fn download_text_file(file_name: &str, mime_type: &str, contents: &str) -> anyhow::Result<()> {
+    use anyhow::anyhow;
+    use web_sys::js_sys;
+    use web_sys::wasm_bindgen::closure::Closure;
+    use web_sys::wasm_bindgen::{JsCast, JsValue};
+
+    let js_error = |err: JsValue| anyhow!("browser download failed: {err:?}");
+
+    let parts = js_sys::Array::of1(&JsValue::from_str(contents));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime_type);
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+    let window = web_sys::window().ok_or_else(|| anyhow!("browser window is not available"))?;
+    let document = window.document().ok_or_else(|| anyhow!("browser document is not available"))?;
+    let body = document.body().ok_or_else(|| anyhow!("browser document has no body"))?;
+    let anchor = document
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| anyhow!("failed to create download link"))?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    // Some browsers ignore clicks on links that are not part of the page.
+    body.append_child(&anchor).map_err(js_error)?;
+    anchor.click();
+    anchor.remove();
+
+    let revoke = Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window
+        .set_timeout_with_callback_and_timeout_and_arguments_0(revoke.unchecked_ref(), REVOKE_DOWNLOAD_URL_MS)
+        .map_err(js_error)?;
+    Ok(())
+}
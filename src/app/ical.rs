@@ -0,0 +1,951 @@
+// iCalendar (RFC 5545) export and import. Exported times are written in UTC
+// using the same conversion as the Supabase rows, so a calendar shows exactly
+// what is stored on the server. Imported events become entries on the visible
+// week, e.g. to pre-fill meetings. Imported times with a TZID are converted
+// to local time through the VTIMEZONE definitions in the file.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::supabase::local_duration_to_utc_range;
+use crate::ui;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike, Utc, Weekday};
+
+use super::state::{State, WeekKey};
+
+const PRODUCT_ID: &str = "-//work_hours_calculator//Work hours//EN";
+/// Content lines longer than this many octets are folded.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Writes one VEVENT per finished entry. Running entries have no end yet and
+/// are left out. `stamp` is used as the DTSTAMP of every event, and the
+/// entry id as its UID, so re-exported entries update the same event.
+pub(crate) fn days_to_ics(days: &[ui::Day], stamp: DateTime<Utc>) -> Result<String> {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{PRODUCT_ID}"),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for day in days {
+        for entry in &day.durations {
+            let (start, Some(end)) = local_duration_to_utc_range(day.date, entry)? else {
+                continue;
+            };
+            if end <= start {
+                continue;
+            }
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}@work-hours", entry.id()));
+            lines.push(format!("DTSTAMP:{}", format_utc(stamp)));
+            lines.push(format!("DTSTART:{}", format_utc(start)));
+            lines.push(format!("DTEND:{}", format_utc(end)));
+            lines.push(format!("SUMMARY:{}", escape_text(&summary(entry))));
+            if !entry.tags().is_empty() {
+                let tags: Vec<_> = entry.tags().iter().map(|tag| escape_text(tag)).collect();
+                lines.push(format!("CATEGORIES:{}", tags.join(",")));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    Ok(lines.iter().map(|line| fold_line(line)).collect::<Vec<_>>().join("\r\n") + "\r\n")
+}
+
+/// Project and note joined for the event title, with a fallback for entries
+/// that have neither.
+fn summary(entry: &ui::Duration) -> String {
+    let parts: Vec<&str> = [entry.project(), entry.note()]
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect();
+    match (parts.is_empty(), entry.is_break()) {
+        (true, true) => "Break".to_string(),
+        (true, false) => "Work".to_string(),
+        (false, _) => parts.join(": "),
+    }
+}
+
+fn format_utc(value: DateTime<Utc>) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Splits a content line into 75-octet chunks, continuing with a space, and
+/// never cuts inside a UTF-8 character.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+    for ch in line.chars() {
+        if octets + ch.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line.
+            octets = 1;
+        }
+        folded.push(ch);
+        octets += ch.len_utf8();
+    }
+    folded
+}
+
+/// `EntrySource::kind` of entries created from calendar events.
+pub(crate) const SOURCE_KIND: &str = "ics";
+
+/// Recurring events are expanded at most this many periods past their start.
+const MAX_RECURRENCES: u32 = 5_000;
+
+/// One occurrence of a calendar event, in local time.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// The event uses a time zone the file does not define, so its times are
+    /// read as local wall-clock time and may be off.
+    pub approximate: bool,
+}
+
+impl CalendarEvent {
+    /// The entry for this event on the day it starts on. Events that run past
+    /// midnight keep their end through the end day offset.
+    pub(crate) fn to_duration(&self) -> ui::Duration {
+        let end_day_offset = (self.end.date() - self.start.date()).num_days().clamp(0, i64::from(i8::MAX)) as i8;
+        let mut duration = ui::Duration::from_clocks(clock(self.start), Some(clock(self.end)), end_day_offset);
+        *duration.note_mut() = self.summary.clone();
+        duration.set_source(Some(ui::EntrySource {
+            kind: SOURCE_KIND.to_string(),
+            id: self.uid.clone(),
+        }));
+        duration
+    }
+
+    /// Start and end in minutes after midnight of the start date.
+    fn minutes(&self) -> (i64, i64) {
+        let start = i64::from(self.start.hour()) * 60 + i64::from(self.start.minute());
+        (start, start + (self.end - self.start).num_minutes())
+    }
+}
+
+fn clock(value: NaiveDateTime) -> ui::TimePoint {
+    ui::TimePoint::new(value.hour() as u8, value.minute() as u8)
+}
+
+/// A content line: name, parameters and raw value.
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Occurrences of the timed events in `text` that start within `range`, in
+/// local time. All-day and cancelled events are skipped. Times with a TZID the
+/// file defines in a VTIMEZONE are converted to local time; other TZIDs are
+/// read as local wall-clock time and their events marked as approximate.
+pub(crate) fn parse_events(text: &str, (first, last): (NaiveDate, NaiveDate)) -> Result<Vec<CalendarEvent>> {
+    let mut components = vec![];
+    let mut timezones = Timezones::default();
+    let mut current: Option<Vec<Property>> = None;
+    let mut timezone: Option<(String, Vec<Observance>)> = None;
+    let mut observance: Option<Vec<Property>> = None;
+    for line in unfold_lines(text) {
+        let property = parse_property(&line)?;
+        match (property.name.as_str(), property.value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(vec![]),
+            ("END", "VEVENT") => components.extend(current.take()),
+            ("BEGIN", "VTIMEZONE") => timezone = Some((String::new(), vec![])),
+            ("END", "VTIMEZONE") => {
+                if let Some((id, observances)) = timezone.take() {
+                    timezones.0.insert(id, observances);
+                }
+            }
+            ("BEGIN", "STANDARD" | "DAYLIGHT") if timezone.is_some() => observance = Some(vec![]),
+            ("END", "STANDARD" | "DAYLIGHT") => {
+                if let (Some(properties), Some((_, observances))) = (observance.take(), timezone.as_mut()) {
+                    observances.extend(Observance::parse(&properties)?);
+                }
+            }
+            ("TZID", _) if observance.is_none() => {
+                if let Some((id, _)) = timezone.as_mut() {
+                    id.clone_from(&property.value);
+                }
+            }
+            _ => {
+                if let Some(properties) = observance.as_mut().or(current.as_mut()) {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    if components.is_empty() && !text.contains("BEGIN:VCALENDAR") {
+        return Err(anyhow!("not an iCalendar file"));
+    }
+    let local_time = |property: &Property| -> Result<Option<NaiveDateTime>> {
+        Ok(parse_time(property)?.map(|(time, zone)| timezones.to_local(time, &zone).0))
+    };
+
+    // Moved or edited occurrences are separate components with a
+    // RECURRENCE-ID; the recurring master must skip the original slot.
+    let mut overridden: HashMap<String, HashSet<NaiveDateTime>> = HashMap::new();
+    for properties in &components {
+        let recurrence_id = properties.iter().find(|property| property.name == "RECURRENCE-ID");
+        let uid = properties.iter().find(|property| property.name == "UID");
+        if let (Some(recurrence_id), Some(uid)) = (recurrence_id, uid) {
+            if let Some(time) = local_time(recurrence_id)? {
+                overridden.entry(uid.value.clone()).or_default().insert(time);
+            }
+        }
+    }
+
+    let mut events = vec![];
+    for properties in &components {
+        let get = |name: &str| properties.iter().find(|property| property.name == name);
+        if get("STATUS").is_some_and(|status| status.value.eq_ignore_ascii_case("CANCELLED")) {
+            continue;
+        }
+        let Some((start, zone)) = get("DTSTART").map(parse_time).transpose()?.flatten() else {
+            continue;
+        };
+        let (local_start, known_zone) = timezones.to_local(start, &zone);
+        let length = match (get("DTEND"), get("DURATION")) {
+            (Some(end), _) => match local_time(end)? {
+                Some(end) => end - local_start,
+                None => continue,
+            },
+            (None, Some(duration)) => parse_duration(&duration.value)?,
+            (None, None) => continue,
+        };
+        if length <= chrono::Duration::zero() {
+            continue;
+        }
+        let uid = get("UID").map(|uid| uid.value.clone()).unwrap_or_default();
+        let summary = get("SUMMARY").map(|summary| unescape_text(&summary.value)).unwrap_or_default();
+
+        // Recurrences repeat the wall-clock time of the event's own zone, so
+        // they are expanded there and converted to local time afterwards.
+        let (starts, skipped) = match get("RRULE").filter(|_| get("RECURRENCE-ID").is_none()) {
+            Some(rule) => {
+                let mut skipped: HashSet<NaiveDateTime> = overridden.get(&uid).cloned().unwrap_or_default();
+                for exdate in properties.iter().filter(|property| property.name == "EXDATE") {
+                    for value in exdate.value.split(',') {
+                        let single = Property {
+                            name: exdate.name.clone(),
+                            params: exdate.params.clone(),
+                            value: value.to_string(),
+                        };
+                        skipped.extend(local_time(&single)?);
+                    }
+                }
+                let last = last.succ_opt().unwrap_or(last);
+                (recurrence_starts(start, &rule.value, last, &timezones, &zone)?, skipped)
+            }
+            None => (vec![start], HashSet::new()),
+        };
+        for start in starts {
+            let start = timezones.to_local(start, &zone).0;
+            if skipped.contains(&start) || !(first..=last).contains(&start.date()) {
+                continue;
+            }
+            let end = start
+                .checked_add_signed(length)
+                .ok_or_else(|| anyhow!("invalid calendar duration for '{summary}'"))?;
+            events.push(CalendarEvent {
+                uid: uid.clone(),
+                summary: summary.clone(),
+                start,
+                end,
+                approximate: !known_zone,
+            });
+        }
+    }
+    events.sort_by_key(|event| event.start);
+    Ok(events)
+}
+
+/// Where the wall-clock time of a date-time value applies.
+#[derive(Debug, Clone, PartialEq)]
+enum Zone {
+    /// No zone: local time wherever the calendar is read.
+    Floating,
+    Utc,
+    /// A TZID parameter.
+    Named(String),
+}
+
+/// The VTIMEZONE definitions of a file by TZID.
+#[derive(Default)]
+struct Timezones(HashMap<String, Vec<Observance>>);
+
+impl Timezones {
+    /// `time` read in `zone` as local time, and whether the zone is known.
+    fn to_local(&self, time: NaiveDateTime, zone: &Zone) -> (NaiveDateTime, bool) {
+        let utc = match zone {
+            Zone::Floating => return (time, true),
+            Zone::Utc => time,
+            Zone::Named(id) => match self.0.get(id) {
+                Some(observances) => time - offset_duration(offset_at(observances, time)),
+                None => return (time, false),
+            },
+        };
+        (Utc.from_utc_datetime(&utc).with_timezone(&chrono::Local).naive_local(), true)
+    }
+
+    /// A UTC time as wall-clock time in `zone`. Unknown zones use local time.
+    fn utc_to_wall(&self, utc: NaiveDateTime, zone: &Zone) -> NaiveDateTime {
+        let observances = match zone {
+            Zone::Utc => return utc,
+            Zone::Named(id) => self.0.get(id),
+            Zone::Floating => None,
+        };
+        match observances {
+            Some(observances) => utc + offset_duration(offset_at(observances, utc)),
+            None => Utc.from_utc_datetime(&utc).with_timezone(&chrono::Local).naive_local(),
+        }
+    }
+}
+
+/// Standard or daylight time within a VTIMEZONE. It takes effect at `start`,
+/// a wall-clock time in the previous offset, and again at each yearly `rule`
+/// date or listed RDATE.
+struct Observance {
+    start: NaiveDateTime,
+    offset_from: FixedOffset,
+    offset_to: FixedOffset,
+    rule: Option<YearlyRule>,
+    dates: Vec<NaiveDateTime>,
+}
+
+impl Observance {
+    /// Reads a STANDARD or DAYLIGHT component; incomplete ones are skipped.
+    fn parse(properties: &[Property]) -> Result<Option<Self>> {
+        let get = |name: &str| properties.iter().find(|property| property.name == name);
+        let (Some(start), Some(offset_from), Some(offset_to)) = (get("DTSTART"), get("TZOFFSETFROM"), get("TZOFFSETTO")) else {
+            return Ok(None);
+        };
+        let Some((start, _)) = parse_time(start)? else {
+            return Ok(None);
+        };
+        let mut dates = vec![];
+        for rdate in properties.iter().filter(|property| property.name == "RDATE") {
+            for value in rdate.value.split(',') {
+                let single = Property {
+                    name: rdate.name.clone(),
+                    params: rdate.params.clone(),
+                    value: value.to_string(),
+                };
+                dates.extend(parse_time(&single)?.map(|(time, _)| time));
+            }
+        }
+        Ok(Some(Self {
+            start,
+            offset_from: parse_offset(&offset_from.value)?,
+            offset_to: parse_offset(&offset_to.value)?,
+            rule: get("RRULE").and_then(|rule| YearlyRule::parse(&rule.value)),
+            dates,
+        }))
+    }
+
+    /// The latest time at or before `time` at which this observance began.
+    fn last_onset(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let yearly = self
+            .rule
+            .iter()
+            .flat_map(|rule| [time.year() - 1, time.year()].into_iter().filter_map(|year| rule.date(year)))
+            .map(|date| date.and_time(self.start.time()));
+        std::iter::once(self.start)
+            .chain(self.dates.iter().copied())
+            .chain(yearly)
+            .filter(|onset| *onset >= self.start && *onset <= time)
+            .max()
+    }
+}
+
+/// The UTC offset in effect at wall-clock `time`.
+fn offset_at(observances: &[Observance], time: NaiveDateTime) -> FixedOffset {
+    observances
+        .iter()
+        .filter_map(|observance| observance.last_onset(time).map(|onset| (onset, observance.offset_to)))
+        .max_by_key(|(onset, _)| *onset)
+        .map(|(_, offset)| offset)
+        .or_else(|| {
+            observances
+                .iter()
+                .min_by_key(|observance| observance.start)
+                .map(|observance| observance.offset_from)
+        })
+        .unwrap_or(Utc.fix())
+}
+
+fn offset_duration(offset: FixedOffset) -> chrono::Duration {
+    chrono::Duration::seconds(i64::from(offset.local_minus_utc()))
+}
+
+/// Reads UTC offsets such as `+0100` or `-053000`.
+fn parse_offset(value: &str) -> Result<FixedOffset> {
+    let invalid = || anyhow!("invalid UTC offset '{value}'");
+    let value = value.trim();
+    let sign = match value.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Err(invalid()),
+    };
+    let digits = &value[1..];
+    if !matches!(digits.len(), 4 | 6) || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let field = |range: std::ops::Range<usize>| digits.get(range).map_or(0, |part| part.parse::<i32>().unwrap_or_default());
+    FixedOffset::east_opt(sign * (field(0..2) * 3600 + field(2..4) * 60 + field(4..6))).ok_or_else(invalid)
+}
+
+/// A time zone change rule such as `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`.
+struct YearlyRule {
+    month: u32,
+    weekday: Weekday,
+    /// Which `weekday` of the month; negative values count from its end.
+    ordinal: i32,
+    until: Option<NaiveDate>,
+}
+
+impl YearlyRule {
+    /// Reads yearly rules on a weekday of a month; other rules are ignored.
+    fn parse(rule: &str) -> Option<Self> {
+        let parts: HashMap<String, String> = rule
+            .split(';')
+            .filter_map(|part| part.split_once('='))
+            .map(|(key, value)| (key.to_ascii_uppercase(), value.to_string()))
+            .collect();
+        if parts.get("FREQ").map(String::as_str) != Some("YEARLY") {
+            return None;
+        }
+        let by_day = parts.get("BYDAY")?.trim();
+        let ordinal = by_day.get(..by_day.len().checked_sub(2)?)?;
+        Some(Self {
+            month: parts.get("BYMONTH")?.parse().ok()?,
+            weekday: parse_weekday(by_day)?,
+            ordinal: if ordinal.is_empty() { 1 } else { ordinal.parse().ok()? },
+            until: parts
+                .get("UNTIL")
+                .and_then(|until| NaiveDate::parse_from_str(until.get(..8)?, "%Y%m%d").ok()),
+        })
+    }
+
+    fn date(&self, year: i32) -> Option<NaiveDate> {
+        let date = if self.ordinal > 0 {
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, u8::try_from(self.ordinal).ok()?)?
+        } else {
+            let next_month = match self.month {
+                12 => NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+                month => NaiveDate::from_ymd_opt(year, month + 1, 1)?,
+            };
+            let last = next_month.pred_opt()?;
+            let back = (last.weekday().num_days_from_monday() + 7 - self.weekday.num_days_from_monday()) % 7;
+            let weeks = i64::from(self.ordinal.unsigned_abs()) - 1;
+            let date = last
+                .checked_sub_signed(chrono::Duration::days(i64::from(back)))?
+                .checked_sub_signed(chrono::Duration::try_weeks(weeks)?)?;
+            (date.month() == self.month).then_some(date)?
+        };
+        self.until.map_or(true, |until| date <= until).then_some(date)
+    }
+}
+
+/// Joins folded lines: a line starting with a space or tab continues the
+/// previous one.
+fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ if !line.trim().is_empty() => lines.push(line.to_string()),
+            _ => {}
+        }
+    }
+    lines
+}
+
+fn parse_property(line: &str) -> Result<Property> {
+    // The value starts at the first colon outside a quoted parameter value.
+    let mut in_quotes = false;
+    let split = line
+        .char_indices()
+        .find(|(_, ch)| {
+            if *ch == '"' {
+                in_quotes = !in_quotes;
+            }
+            *ch == ':' && !in_quotes
+        })
+        .map(|(ix, _)| ix)
+        .ok_or_else(|| anyhow!("invalid calendar line '{line}'"))?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+    let mut parts = head.split(';');
+    let name = parts.next().unwrap_or_default().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.trim_matches('"').to_string()))
+        .collect();
+    Ok(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// The wall-clock time of a date-time value and the zone it applies in, or
+/// `None` for all-day dates.
+fn parse_time(property: &Property) -> Result<Option<(NaiveDateTime, Zone)>> {
+    let value = property.value.trim();
+    if property.param("VALUE").is_some_and(|kind| kind.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+        return Ok(None);
+    }
+    let (value, zone) = match (value.strip_suffix('Z'), property.param("TZID")) {
+        (Some(value), _) => (value, Zone::Utc),
+        (None, Some(id)) => (value, Zone::Named(id.to_string())),
+        (None, None) => (value, Zone::Floating),
+    };
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").with_context(|| format!("invalid calendar time '{value}'"))?;
+    Ok(Some((naive, zone)))
+}
+
+/// Reads durations such as `PT1H30M` or `P1D`.
+fn parse_duration(value: &str) -> Result<chrono::Duration> {
+    let invalid = || anyhow!("invalid calendar duration '{value}'");
+    let (sign, rest) = match value.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+    let mut seconds: i64 = 0;
+    let mut number = String::new();
+    for ch in rest.chars() {
+        let unit = match ch {
+            '0'..='9' => {
+                number.push(ch);
+                continue;
+            }
+            'T' => continue,
+            'W' => 7 * 24 * 3600,
+            'D' => 24 * 3600,
+            'H' => 3600,
+            'M' => 60,
+            'S' => 1,
+            _ => return Err(invalid()),
+        };
+        let part = number.parse::<i64>().ok().and_then(|number| number.checked_mul(unit));
+        seconds = part.and_then(|part| seconds.checked_add(part)).ok_or_else(invalid)?;
+        number.clear();
+    }
+    chrono::Duration::try_seconds(sign * seconds).ok_or_else(invalid)
+}
+
+/// Start times of a daily or weekly recurrence up to `last`, as wall-clock
+/// times in `zone`. Other frequencies only yield the first occurrence.
+fn recurrence_starts(start: NaiveDateTime, rule: &str, last: NaiveDate, timezones: &Timezones, zone: &Zone) -> Result<Vec<NaiveDateTime>> {
+    let parts: HashMap<String, String> = rule
+        .split(';')
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.to_ascii_uppercase(), value.to_string()))
+        .collect();
+    let interval = parts
+        .get("INTERVAL")
+        .map_or(Ok(1), |value| value.parse::<i64>())
+        .context("invalid RRULE interval")?;
+    let count = parts
+        .get("COUNT")
+        .map(|value| value.parse::<usize>())
+        .transpose()
+        .context("invalid RRULE count")?;
+    let until = parts
+        .get("UNTIL")
+        .map(|value| {
+            parse_time(&Property {
+                name: "UNTIL".to_string(),
+                params: vec![],
+                value: value.clone(),
+            })
+            .map(|until| match until {
+                Some((until, Zone::Utc)) => timezones.utc_to_wall(until, zone),
+                Some((until, _)) => until,
+                None => end_of_date(value),
+            })
+        })
+        .transpose()?;
+    let within = |candidate: NaiveDateTime| until.map_or(true, |until| candidate <= until);
+
+    let mut starts = vec![];
+    let mut push = |candidate: NaiveDateTime| -> bool {
+        if candidate.date() > last || !within(candidate) || count.is_some_and(|count| starts.len() >= count) {
+            return false;
+        }
+        starts.push(candidate);
+        true
+    };
+    match parts.get("FREQ").map(String::as_str) {
+        Some("DAILY") => {
+            for period in 0..i64::from(MAX_RECURRENCES) {
+                // Occurrences past the representable dates lie past `last` too.
+                let Some(candidate) = period
+                    .checked_mul(interval.max(1))
+                    .and_then(chrono::Duration::try_days)
+                    .and_then(|offset| start.checked_add_signed(offset))
+                else {
+                    break;
+                };
+                if !push(candidate) {
+                    break;
+                }
+            }
+        }
+        Some("WEEKLY") => {
+            let mut weekdays: Vec<Weekday> = match parts.get("BYDAY") {
+                Some(days) => days.split(',').filter_map(parse_weekday).collect(),
+                None => vec![start.weekday()],
+            };
+            weekdays.sort_by_key(Weekday::num_days_from_monday);
+            let monday = start.date() - chrono::Duration::days(i64::from(start.weekday().num_days_from_monday()));
+            'weeks: for period in 0..i64::from(MAX_RECURRENCES) {
+                let Some(week) = period
+                    .checked_mul(interval.max(1))
+                    .and_then(chrono::Duration::try_weeks)
+                    .and_then(|offset| monday.checked_add_signed(offset))
+                else {
+                    break;
+                };
+                for weekday in &weekdays {
+                    let Some(day) = week.checked_add_days(chrono::Days::new(u64::from(weekday.num_days_from_monday()))) else {
+                        break 'weeks;
+                    };
+                    let candidate = day.and_time(start.time());
+                    if candidate >= start && !push(candidate) {
+                        break 'weeks;
+                    }
+                }
+            }
+        }
+        _ => {
+            push(start);
+        }
+    }
+    Ok(starts)
+}
+
+fn end_of_date(value: &str) -> NaiveDateTime {
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .unwrap_or(NaiveDateTime::MAX)
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    // Weekly rules may carry an ordinal prefix such as `1MO`; it is ignored.
+    match value
+        .trim()
+        .trim_start_matches(|ch: char| ch == '-' || ch == '+' || ch.is_ascii_digit())
+    {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            text.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EventStatus {
+    /// Will be added when selected.
+    New,
+    /// Overlaps a recorded entry, or an earlier selected event.
+    Overlaps,
+    /// Starts on a date that is not shown in the visible week.
+    NotShown,
+}
+
+/// Decides which events can be added to `days`. Selected events claim their
+/// slot in order, so two overlapping meetings do not both get added.
+pub(crate) fn event_statuses(days: &[ui::Day], events: &[CalendarEvent], selected: &[bool], now: time::OffsetDateTime) -> Vec<EventStatus> {
+    let mut taken: HashMap<NaiveDate, Vec<(i64, i64)>> = HashMap::new();
+    for day in days {
+        let slots = day
+            .durations
+            .iter()
+            .map(|duration| {
+                let start = duration.start_clock().total_minutes();
+                (start, start + duration.duration_at(day.date, now).whole_minutes())
+            })
+            .collect();
+        taken.insert(day.date, slots);
+    }
+
+    events
+        .iter()
+        .zip(selected.iter().chain(std::iter::repeat(&false)))
+        .map(|(event, selected)| {
+            let Some(slots) = taken.get_mut(&event.start.date()) else {
+                return EventStatus::NotShown;
+            };
+            let (start, end) = event.minutes();
+            if slots.iter().any(|(slot_start, slot_end)| start < *slot_end && *slot_start < end) {
+                return EventStatus::Overlaps;
+            }
+            if *selected {
+                slots.push((start, end));
+            }
+            EventStatus::New
+        })
+        .collect()
+}
+
+/// A calendar file being picked from, re-read whenever the visible week
+/// changes.
+#[derive(Debug, Clone)]
+pub(crate) struct CalendarImport {
+    pub file_name: String,
+    text: String,
+    week: Option<WeekKey>,
+    pub events: Result<Vec<CalendarEvent>, String>,
+    pub selected: Vec<bool>,
+}
+
+impl CalendarImport {
+    pub(crate) fn new(file_name: String, text: String) -> Self {
+        Self {
+            file_name,
+            text,
+            week: None,
+            events: Ok(vec![]),
+            selected: vec![],
+        }
+    }
+
+    /// Loads the events of the visible week, preselecting those that fit.
+    pub(crate) fn refresh(&mut self, state: &State, now: time::OffsetDateTime) {
+        let week = state.current_week_key();
+        if self.week == Some(week) {
+            return;
+        }
+        self.week = Some(week);
+        let range = (week.monday(), week.monday() + chrono::Duration::days(6));
+        self.events = parse_events(&self.text, range).map_err(|err| format!("{err:#}"));
+        self.selected = match &self.events {
+            Ok(events) => {
+                let all = vec![true; events.len()];
+                event_statuses(state.days(), events, &all, now)
+                    .into_iter()
+                    .map(|status| status == EventStatus::New)
+                    .collect()
+            }
+            Err(_) => vec![],
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{days_to_ics, event_statuses, fold_line, parse_events, CalendarEvent, EventStatus};
+    use crate::ui;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    #[test]
+    fn ics_has_one_event_per_finished_entry() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_772_629_200).unwrap();
+        let mut day = ui::Day::new("Wednesday".to_string(), time::Duration::hours(8));
+        day.date = date;
+        let mut entry = ui::Duration::new(date, start, start + time::Duration::hours(1));
+        *entry.project_mut() = "onboarding".to_string();
+        *entry.note_mut() = "Q&A, new hires".to_string();
+        day.durations.push(entry);
+        day.durations.push(ui::Duration::new_open(start + time::Duration::hours(3)));
+
+        let stamp = Utc.with_ymd_and_hms(2026, 3, 5, 8, 0, 0).unwrap();
+        let ics = days_to_ics(std::slice::from_ref(&day), stamp).unwrap();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("SUMMARY:onboarding: Q&A\\, new hires\r\n"));
+        assert!(ics.contains("DTSTAMP:20260305T080000Z\r\n"));
+        assert!(ics.contains(&format!("UID:{}@work-hours\r\n", day.durations[0].id())));
+    }
+
+    #[test]
+    fn long_lines_fold_without_splitting_characters() {
+        let line = format!("SUMMARY:{}", "é".repeat(60));
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn recurring_events_respect_exceptions_and_overrides() {
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup\r\nDTSTART:20260504T090000\r\nDTEND:20260504T091500\r\n\
+            RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR\r\nEXDATE:20260513T090000\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:standup\r\nRECURRENCE-ID:20260515T090000\r\nSUMMARY:Standup\r\n\
+            DTSTART:20260515T100000\r\nDTEND:20260515T101500\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:review\r\nSUMMARY:Design review\\, part\r\n  two\r\nDTSTART;TZID=Europe/Brussels:20260512T130000\r\n\
+            DURATION:PT1H30M\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:offsite\r\nDTSTART;VALUE=DATE:20260513\r\nDTEND;VALUE=DATE:20260514\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:gone\r\nSTATUS:CANCELLED\r\nDTSTART:20260514T090000\r\nDTEND:20260514T100000\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let week = (
+            NaiveDate::from_ymd_opt(2026, 5, 11).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 5, 17).unwrap(),
+        );
+
+        let events = parse_events(ics, week).unwrap();
+        let summary: Vec<(String, &str, &str)> = events
+            .iter()
+            .map(|event| {
+                (
+                    event.start.format("%a %H:%M").to_string(),
+                    event.summary.as_str(),
+                    event.uid.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Mon 09:00".to_string(), "Standup", "standup"),
+                ("Tue 13:00".to_string(), "Design review, part two", "review"),
+                ("Fri 10:00".to_string(), "Standup", "standup"),
+            ]
+        );
+        assert_eq!((events[1].end - events[1].start).num_minutes(), 90);
+        assert_eq!(events[1].to_duration().source().unwrap().id, "review");
+        assert!(events[1].approximate && !events[0].approximate);
+    }
+
+    #[test]
+    fn times_with_a_defined_tzid_become_local() {
+        let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+            BEGIN:VTIMEZONE\r\nTZID:America/New_York\r\n\
+            BEGIN:DAYLIGHT\r\nDTSTART:19700308T020000\r\nTZOFFSETFROM:-0500\r\nTZOFFSETTO:-0400\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\nEND:DAYLIGHT\r\n\
+            BEGIN:STANDARD\r\nDTSTART:19701101T020000\r\nTZOFFSETFROM:-0400\r\nTZOFFSETTO:-0500\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\nEND:STANDARD\r\nEND:VTIMEZONE\r\n\
+            BEGIN:VEVENT\r\nUID:sync\r\nSUMMARY:Sync\r\nDTSTART;TZID=America/New_York:20260302T090000\r\n\
+            DTEND;TZID=America/New_York:20260302T100000\r\nRRULE:FREQ=WEEKLY\r\nEND:VEVENT\r\n\
+            BEGIN:VEVENT\r\nUID:mars\r\nSUMMARY:Rover\r\nDTSTART;TZID=Mars/Olympus:20260310T090000\r\n\
+            DURATION:PT30M\r\nEND:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let local = |utc: chrono::DateTime<Utc>| utc.with_timezone(&chrono::Local).naive_local();
+        let week = |monday: NaiveDate| (monday, monday + chrono::Duration::days(6));
+
+        let before = parse_events(ics, week(NaiveDate::from_ymd_opt(2026, 3, 2).unwrap())).unwrap();
+        assert_eq!(before[0].start, local(Utc.with_ymd_and_hms(2026, 3, 2, 14, 0, 0).unwrap()));
+
+        // Daylight saving time starts in New York on 8 March.
+        let after = parse_events(ics, week(NaiveDate::from_ymd_opt(2026, 3, 9).unwrap())).unwrap();
+        let sync = after.iter().find(|event| event.uid == "sync").unwrap();
+        assert_eq!(sync.start, local(Utc.with_ymd_and_hms(2026, 3, 9, 13, 0, 0).unwrap()));
+        assert_eq!((sync.end - sync.start).num_minutes(), 60);
+        assert!(!sync.approximate);
+
+        let rover = after.iter().find(|event| event.uid == "mars").unwrap();
+        assert_eq!(rover.start.format("%a %H:%M").to_string(), "Tue 09:00");
+        assert!(rover.approximate);
+    }
+
+    #[test]
+    fn oversized_durations_and_intervals_do_not_panic() {
+        let week = (
+            NaiveDate::from_ymd_opt(2026, 5, 11).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 5, 17).unwrap(),
+        );
+        let event = |extra: &str| {
+            format!(
+                "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:big\r\nSUMMARY:Big\r\n\
+                DTSTART:20260511T090000\r\n{extra}END:VEVENT\r\nEND:VCALENDAR\r\n"
+            )
+        };
+
+        let err = parse_events(&event("DURATION:PT9999999999999999S\r\n"), week).unwrap_err();
+        assert!(err.to_string().contains("invalid calendar duration"));
+        assert!(parse_events(&event("DURATION:P99999999999W\r\n"), week).is_err());
+        // Fits a duration but ends past the last representable date.
+        let err = parse_events(&event("DURATION:P99999999D\r\n"), week).unwrap_err();
+        assert!(err.to_string().contains("invalid calendar duration"));
+
+        for freq in ["DAILY", "WEEKLY"] {
+            let rule = format!("DURATION:PT1H\r\nRRULE:FREQ={freq};INTERVAL=99999999999999\r\n");
+            let events = parse_events(&event(&rule), week).unwrap();
+            assert_eq!(events.len(), 1);
+        }
+    }
+
+    #[test]
+    fn selected_events_claim_their_slot() {
+        let monday = NaiveDate::from_ymd_opt(2026, 5, 11).unwrap();
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
+        day.date = monday;
+        day.durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(10, 0)),
+            0,
+        ));
+        let event = |date: NaiveDate, start: (u32, u32), end: (u32, u32)| CalendarEvent {
+            uid: String::new(),
+            summary: String::new(),
+            start: date.and_hms_opt(start.0, start.1, 0).unwrap(),
+            end: date.and_hms_opt(end.0, end.1, 0).unwrap(),
+            approximate: false,
+        };
+        let events = [
+            event(monday, (9, 30), (10, 30)),
+            event(monday, (10, 0), (11, 0)),
+            event(monday, (10, 30), (11, 30)),
+            event(monday.succ_opt().unwrap(), (9, 0), (10, 0)),
+        ];
+
+        let statuses = event_statuses(&[day], &events, &[true, true, true, true], time::OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(
+            statuses,
+            [
+                EventStatus::Overlaps,
+                EventStatus::New,
+                EventStatus::Overlaps,
+                EventStatus::NotShown
+            ]
+        );
+    }
+}
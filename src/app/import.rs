@@ -0,0 +1,535 @@
+// Import of entries from files. Generic CSV files use a user-chosen column
+// mapping; Clockify reports are recognized and read by `clockify`. Parsing and
+// merge planning are pure, so the import window can preview exactly which days
+// change before anything is written to the state.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ui;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveTime, Timelike};
+
+use super::clockify;
+use super::state::State;
+
+/// Where duration-only rows start when the day has no entries yet.
+const DEFAULT_DAY_START: (u8, u8) = (9, 0);
+
+/// Parse errors shown in the preview before the list is cut off.
+pub(crate) const MAX_SHOWN_ERRORS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum TimeColumns {
+    /// Separate start and end clock columns.
+    #[default]
+    StartEnd,
+    /// A single duration column; entries are placed after the day's last entry.
+    Duration,
+}
+
+/// Which columns hold which field. Column indexes refer to the parsed table.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CsvMapping {
+    pub delimiter: u8,
+    pub has_header: bool,
+    pub date_format: String,
+    pub date: usize,
+    pub time_columns: TimeColumns,
+    pub start: usize,
+    pub end: usize,
+    pub duration: usize,
+    pub project: Option<usize>,
+    pub note: Option<usize>,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_header: true,
+            date_format: "%Y-%m-%d".to_string(),
+            date: 0,
+            time_columns: TimeColumns::StartEnd,
+            start: 1,
+            end: 2,
+            duration: 1,
+            project: None,
+            note: None,
+        }
+    }
+}
+
+impl CsvMapping {
+    /// Picks columns by header name, keeping the current choice for anything
+    /// that does not match. Our own export is recognized this way.
+    pub(crate) fn guess_columns(&mut self, header: &[String]) {
+        let find = |names: &[&str]| {
+            header
+                .iter()
+                .position(|column| names.iter().any(|name| column.trim().eq_ignore_ascii_case(name)))
+        };
+        if let Some(ix) = find(&["date", "day", "start date"]) {
+            self.date = ix;
+        }
+        if let Some(ix) = find(&["start", "start time", "from", "begin"]) {
+            self.start = ix;
+        }
+        if let Some(ix) = find(&["end", "end time", "to", "stop"]) {
+            self.end = ix;
+        }
+        if let Some(ix) = find(&["duration", "hours", "duration (h)", "time"]) {
+            self.duration = ix;
+            if find(&["start", "start time", "from", "begin"]).is_none() {
+                self.time_columns = TimeColumns::Duration;
+            }
+        }
+        self.project = find(&["project", "task"]).or(self.project);
+        self.note = find(&["note", "notes", "description", "comment"]).or(self.note);
+    }
+}
+
+/// Raw CSV cells, with generated column names when the file has no header.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct CsvTable {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+pub(crate) fn read_table(text: &str, delimiter: u8, has_header: bool) -> Result<CsvTable> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut rows = reader
+        .records()
+        .map(|record| record.map(|record| record.iter().map(str::to_string).collect::<Vec<_>>()))
+        .collect::<Result<Vec<_>, _>>()
+        .context("failed to read CSV")?;
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut header = if has_header && !rows.is_empty() { rows.remove(0) } else { vec![] };
+    for ix in header.len()..width {
+        header.push(format!("Column {}", ix + 1));
+    }
+    Ok(CsvTable { header, rows })
+}
+
+/// Recorded time of one imported row.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ImportedTime {
+    /// Clock values on the entry's date. An offset of 0 reads an end before
+    /// the start as the next day, like `ui::Duration`.
+    Clocks {
+        start: ui::TimePoint,
+        end: ui::TimePoint,
+        end_day_offset: i8,
+    },
+    Length(time::Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ImportedEntry {
+    pub date: NaiveDate,
+    pub time: ImportedTime,
+    pub project: String,
+    pub note: String,
+    pub tags: Vec<String>,
+}
+
+/// Turns table rows into entries. Rows that cannot be read are reported by
+/// their 1-based line in the file instead of failing the whole import.
+pub(crate) fn parse_entries(table: &CsvTable, mapping: &CsvMapping) -> (Vec<ImportedEntry>, Vec<String>) {
+    let first_line = if mapping.has_header { 2 } else { 1 };
+    let mut entries = vec![];
+    let mut errors = vec![];
+    for (ix, row) in table.rows.iter().enumerate() {
+        match parse_row(row, mapping) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push(format!("line {}: {err}", ix + first_line)),
+        }
+    }
+    (entries, errors)
+}
+
+fn parse_row(row: &[String], mapping: &CsvMapping) -> Result<ImportedEntry> {
+    let cell = |ix: usize| row.get(ix).map_or("", |cell| cell.trim());
+    let date = NaiveDate::parse_from_str(cell(mapping.date), &mapping.date_format)
+        .with_context(|| format!("date '{}' does not match '{}'", cell(mapping.date), mapping.date_format))?;
+    let time = match mapping.time_columns {
+        TimeColumns::StartEnd => ImportedTime::Clocks {
+            start: parse_clock(cell(mapping.start))?,
+            end: parse_clock(cell(mapping.end))?,
+            end_day_offset: 0,
+        },
+        TimeColumns::Duration => ImportedTime::Length(parse_length(cell(mapping.duration))?),
+    };
+    Ok(ImportedEntry {
+        date,
+        time,
+        project: mapping.project.map(|ix| cell(ix).to_string()).unwrap_or_default(),
+        note: mapping.note.map(|ix| cell(ix).to_string()).unwrap_or_default(),
+        tags: vec![],
+    })
+}
+
+/// Reads `HH:MM` or `HH:MM:SS`; seconds are dropped.
+pub(crate) fn parse_clock(value: &str) -> Result<ui::TimePoint> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .with_context(|| format!("'{value}' is not a time of day"))?;
+    Ok(ui::TimePoint::new(time.hour() as u8, time.minute() as u8))
+}
+
+/// Reads `H:MM`, `H:MM:SS` or decimal hours such as `7.5` or `7,5`.
+pub(crate) fn parse_length(value: &str) -> Result<time::Duration> {
+    let invalid = || anyhow::anyhow!("'{value}' is not a duration");
+    let minutes = if value.contains(':') {
+        let mut parts = value.split(':').map(|part| part.parse::<i64>().map_err(|_| invalid()));
+        let hours = parts.next().ok_or_else(invalid)??;
+        let minutes = parts.next().ok_or_else(invalid)??;
+        if !(0..60).contains(&minutes) {
+            return Err(invalid());
+        }
+        hours
+            .checked_mul(60)
+            .and_then(|hours| hours.checked_add(minutes))
+            .ok_or_else(invalid)?
+    } else {
+        let hours: f64 = value.replace(',', ".").parse().map_err(|_| invalid())?;
+        (hours * 60.0).round() as i64
+    };
+    if !(1..24 * 60).contains(&minutes) {
+        return Err(invalid());
+    }
+    Ok(time::Duration::minutes(minutes))
+}
+
+/// What importing changes on one date.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DayImport {
+    pub existing: usize,
+    pub added: usize,
+    pub duplicates: usize,
+    pub before: time::Duration,
+    pub after: time::Duration,
+    pub day: ui::Day,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct ImportPlan {
+    pub days: BTreeMap<NaiveDate, DayImport>,
+    pub errors: Vec<String>,
+}
+
+impl ImportPlan {
+    pub(crate) fn added(&self) -> usize {
+        self.days.values().map(|day| day.added).sum()
+    }
+
+    /// The days `changed_days` replaces, as they were before the import.
+    pub(crate) fn previous_days(&self) -> Vec<ui::Day> {
+        self.days
+            .values()
+            .filter(|day| day.added > 0)
+            .map(|day| {
+                let mut previous = day.day.clone();
+                previous.durations.truncate(day.existing);
+                previous
+            })
+            .collect()
+    }
+
+    /// Days that actually gain entries, ready for `State::merge_days`.
+    pub(crate) fn changed_days(&self) -> Vec<ui::Day> {
+        self.days.values().filter(|day| day.added > 0).map(|day| day.day.clone()).collect()
+    }
+}
+
+/// Merges `entries` into the best known data for each date. Entries whose
+/// start and end match an existing row are counted as duplicates and skipped,
+/// so importing the same file twice changes nothing.
+pub(crate) fn plan_import(
+    state: &State,
+    history: &HashMap<NaiveDate, ui::Day>,
+    entries: Vec<ImportedEntry>,
+    errors: Vec<String>,
+    now: time::OffsetDateTime,
+) -> ImportPlan {
+    let mut days: BTreeMap<NaiveDate, DayImport> = BTreeMap::new();
+    for entry in entries {
+        let plan = days.entry(entry.date).or_insert_with(|| {
+            let day = state
+                .recorded_day(entry.date, history)
+                .cloned()
+                .unwrap_or_else(|| state.default_day_for_date(entry.date));
+            DayImport {
+                existing: day.durations.len(),
+                added: 0,
+                duplicates: 0,
+                before: day.duration_at(now, state.break_rules()),
+                after: day.duration_at(now, state.break_rules()),
+                day,
+            }
+        });
+
+        let (start, end, end_day_offset) = match entry.time {
+            ImportedTime::Clocks {
+                start,
+                end,
+                end_day_offset,
+            } => (start, end, end_day_offset),
+            ImportedTime::Length(length) => {
+                // Entries start on their own date, so a row after an
+                // overnight entry cannot follow it and starts at the default.
+                let start_minutes = latest_end_minutes(&plan.day)
+                    .filter(|minutes| *minutes < 24 * 60)
+                    .unwrap_or(i64::from(DEFAULT_DAY_START.0) * 60 + i64::from(DEFAULT_DAY_START.1));
+                let end_minutes = start_minutes + length.whole_minutes();
+                (clock_at(start_minutes), clock_at(end_minutes), (end_minutes / (24 * 60)) as i8)
+            }
+        };
+        let duplicate = plan
+            .day
+            .durations
+            .iter()
+            .any(|duration| duration.start_clock() == &start && duration.end_clock() == Some(&end));
+        if duplicate {
+            plan.duplicates += 1;
+            continue;
+        }
+
+        let mut duration = ui::Duration::from_clocks(start, Some(end), end_day_offset);
+        *duration.project_mut() = entry.project;
+        *duration.note_mut() = entry.note;
+        duration.set_tags(entry.tags);
+        plan.day.durations.push(duration);
+        // Imported time was worked, so count it even on days that are off by
+        // default, such as weekends.
+        plan.day.enabled = true;
+        plan.added += 1;
+        plan.after = plan.day.duration_at(now, state.break_rules());
+    }
+    ImportPlan { days, errors }
+}
+
+/// End of the latest finished entry in minutes after midnight of the day,
+/// counting past midnight for overnight entries.
+fn latest_end_minutes(day: &ui::Day) -> Option<i64> {
+    day.durations
+        .iter()
+        .filter_map(|duration| {
+            let end = duration.end_clock()?;
+            Some(end.total_minutes() + i64::from(duration.effective_end_day_offset()) * 24 * 60)
+        })
+        .max()
+}
+
+fn clock_at(minutes: i64) -> ui::TimePoint {
+    let minutes = minutes.rem_euclid(24 * 60);
+    ui::TimePoint::new((minutes / 60) as u8, (minutes % 60) as u8)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportFormat {
+    /// Any CSV file, read through `CsvMapping`.
+    Csv,
+    /// A Clockify detailed report, as CSV or JSON.
+    Clockify,
+}
+
+/// An import in progress: the picked file, its mapping and the last preview.
+#[derive(Debug, Clone)]
+pub(crate) struct ImportSession {
+    pub file_name: String,
+    pub text: String,
+    pub format: ImportFormat,
+    pub mapping: CsvMapping,
+    pub table: Result<CsvTable, String>,
+    pub plan: Option<ImportPlan>,
+    /// Whether applying also saves the changed days to the server.
+    pub push_to_server: bool,
+    planned_mapping: Option<CsvMapping>,
+}
+
+impl ImportSession {
+    pub(crate) fn new(file_name: String, text: String) -> Self {
+        let format = if clockify::is_clockify_export(&text) {
+            ImportFormat::Clockify
+        } else {
+            ImportFormat::Csv
+        };
+        let mut session = Self {
+            file_name,
+            text,
+            format,
+            mapping: CsvMapping::default(),
+            table: Ok(CsvTable::default()),
+            plan: None,
+            push_to_server: true,
+            planned_mapping: None,
+        };
+        if session
+            .text
+            .lines()
+            .next()
+            .is_some_and(|line| line.contains(';') && !line.contains(','))
+        {
+            session.mapping.delimiter = b';';
+        }
+        session.reload_table();
+        if let Ok(table) = &session.table {
+            let header = table.header.clone();
+            session.mapping.guess_columns(&header);
+        }
+        session
+    }
+
+    fn reload_table(&mut self) {
+        self.table = read_table(&self.text, self.mapping.delimiter, self.mapping.has_header).map_err(|err| format!("{err:#}"));
+    }
+
+    /// Recomputes the preview when the mapping changed since the last one.
+    pub(crate) fn refresh_plan(&mut self, state: &State, history: &HashMap<NaiveDate, ui::Day>, now: time::OffsetDateTime) {
+        if self.planned_mapping.as_ref() == Some(&self.mapping) {
+            return;
+        }
+        let reread = self.planned_mapping.as_ref().map_or(true, |old| {
+            old.delimiter != self.mapping.delimiter || old.has_header != self.mapping.has_header
+        });
+        if self.format == ImportFormat::Clockify {
+            self.plan = match clockify::parse_export(&self.text) {
+                Ok((entries, errors)) => Some(plan_import(state, history, entries, errors, now)),
+                Err(err) => {
+                    self.table = Err(format!("{err:#}"));
+                    None
+                }
+            };
+            self.planned_mapping = Some(self.mapping.clone());
+            return;
+        }
+
+        if reread {
+            self.reload_table();
+        }
+        self.plan = self.table.as_ref().ok().map(|table| {
+            let (entries, errors) = parse_entries(table, &self.mapping);
+            plan_import(state, history, entries, errors, now)
+        });
+        self.planned_mapping = Some(self.mapping.clone());
+    }
+
+    /// Forces the next `refresh_plan` to run, e.g. after the state changed.
+    pub(crate) fn invalidate_plan(&mut self) {
+        self.planned_mapping = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_entries, parse_length, plan_import, read_table, CsvMapping, TimeColumns};
+    use crate::app::state::State;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    fn now() -> time::OffsetDateTime {
+        time::OffsetDateTime::from_unix_timestamp(1_778_490_000).unwrap()
+    }
+
+    #[test]
+    fn start_end_rows_merge_and_skip_duplicates() {
+        let csv =
+            "Date,Start,End,Project\n2026-05-11,08:00,12:00,alpha\n2026-05-11,08:00,12:00,alpha\n2026-05-16,22:00,01:30,beta\nbad,1,2,\n";
+        let mut mapping = CsvMapping::default();
+        let table = read_table(csv, mapping.delimiter, mapping.has_header).unwrap();
+        mapping.guess_columns(&table.header);
+        assert_eq!(mapping.project, Some(3));
+
+        let (entries, errors) = parse_entries(&table, &mapping);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("line 5:"));
+
+        let state = State::default();
+        let plan = plan_import(&state, &HashMap::new(), entries, errors, now());
+        let monday = &plan.days[&NaiveDate::from_ymd_opt(2026, 5, 11).unwrap()];
+        assert_eq!((monday.added, monday.duplicates), (1, 1));
+        assert_eq!(monday.after, time::Duration::hours(4));
+        assert_eq!(monday.day.durations[0].project(), "alpha");
+
+        // Saturday is off by default, and the entry runs past midnight.
+        let saturday = &plan.days[&NaiveDate::from_ymd_opt(2026, 5, 16).unwrap()];
+        assert!(saturday.day.enabled);
+        assert_eq!(saturday.after, time::Duration::minutes(210));
+        assert_eq!(plan.added(), 2);
+
+        let mut state = state;
+        state.merge_days(plan.changed_days());
+        let replanned = plan_import(&state, &HashMap::new(), parse_entries(&table, &mapping).0, vec![], now());
+        assert_eq!(replanned.added(), 0);
+    }
+
+    #[test]
+    fn duration_rows_follow_existing_entries() {
+        let csv = "11/05/2026;7,5\n11/05/2026;0:30\n";
+        let mapping = CsvMapping {
+            delimiter: b';',
+            has_header: false,
+            date_format: "%d/%m/%Y".to_string(),
+            time_columns: TimeColumns::Duration,
+            ..CsvMapping::default()
+        };
+        let table = read_table(csv, mapping.delimiter, mapping.has_header).unwrap();
+        assert_eq!(table.header, vec!["Column 1", "Column 2"]);
+
+        let (entries, errors) = parse_entries(&table, &mapping);
+        assert!(errors.is_empty());
+        let plan = plan_import(&State::default(), &HashMap::new(), entries, errors, now());
+        let day = &plan.days[&NaiveDate::from_ymd_opt(2026, 5, 11).unwrap()].day;
+        let clocks: Vec<_> = day
+            .durations
+            .iter()
+            .map(|duration| {
+                (
+                    duration.start_clock().total_minutes(),
+                    duration.end_clock().unwrap().total_minutes(),
+                )
+            })
+            .collect();
+        assert_eq!(clocks, vec![(9 * 60, 16 * 60 + 30), (16 * 60 + 30, 17 * 60)]);
+        assert!(parse_length("25:00").is_err());
+        assert!(parse_length("1:75").is_err());
+        assert!(parse_length("2:-10").is_err());
+        assert!(parse_length("99999999999999999:00").is_err());
+    }
+
+    #[test]
+    fn duration_rows_after_an_overnight_entry_start_at_the_default() {
+        let csv = "Date,Start,End\n2026-05-11,22:00,01:30\n";
+        let mut mapping = CsvMapping::default();
+        let table = read_table(csv, mapping.delimiter, mapping.has_header).unwrap();
+        mapping.guess_columns(&table.header);
+        let (overnight, _) = parse_entries(&table, &mapping);
+
+        let csv = "11/05/2026;0:30\n";
+        let mapping = CsvMapping {
+            delimiter: b';',
+            has_header: false,
+            date_format: "%d/%m/%Y".to_string(),
+            time_columns: TimeColumns::Duration,
+            ..CsvMapping::default()
+        };
+        let table = read_table(csv, mapping.delimiter, mapping.has_header).unwrap();
+        let (length, _) = parse_entries(&table, &mapping);
+
+        let entries = overnight.into_iter().chain(length).collect();
+        let plan = plan_import(&State::default(), &HashMap::new(), entries, vec![], now());
+        let day = &plan.days[&NaiveDate::from_ymd_opt(2026, 5, 11).unwrap()];
+        let added = &day.day.durations[1];
+        assert_eq!(added.start_clock().total_minutes(), 9 * 60);
+        assert_eq!(added.end_clock().unwrap().total_minutes(), 9 * 60 + 30);
+        assert_eq!(added.effective_end_day_offset(), 0);
+        assert_eq!(day.after, time::Duration::minutes(240));
+    }
+}
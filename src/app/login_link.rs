@@ -0,0 +1,44 @@
+// Magic-link login in the browser. The link in the login email goes through
+// Supabase and back to the page that requested it, with the session tokens in
+// the URL fragment. Native builds cannot receive that redirect and log in with
+// the code from the same email instead.
+
+use crate::supabase::AuthRedirect;
+use anyhow::Result;
+
+/// Where the magic link should lead back to, if this build can receive it.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn redirect_url() -> Option<String> {
+    let location = web_sys::window()?.location();
+    Some(format!("{}{}", location.origin().ok()?, location.pathname().ok()?))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn redirect_url() -> Option<String> {
+    None
+}
+
+/// Takes a magic-link redirect from the page URL and removes the fragment,
+/// so the tokens neither stay visible nor log in again on reload.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn take_redirect() -> Option<Result<AuthRedirect>> {
+    let window = web_sys::window()?;
+    let location = window.location();
+    let redirect = AuthRedirect::from_fragment(&location.hash().ok()?)?;
+    let url = format!(
+        "{}{}",
+        location.pathname().unwrap_or_default(),
+        location.search().unwrap_or_default()
+    );
+    if let Ok(history) = window.history() {
+        if let Err(err) = history.replace_state_with_url(&web_sys::wasm_bindgen::JsValue::NULL, "", Some(&url)) {
+            tracing::warn!(target = "auth", error = ?err, "failed to clear login redirect from the page URL");
+        }
+    }
+    Some(redirect)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn take_redirect() -> Option<Result<AuthRedirect>> {
+    None
+}
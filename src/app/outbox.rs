@@ -0,0 +1,346 @@
+// Persisted queue of day changes that still have to reach the server. Edits
+// to the visible week are picked up every frame and queued per date, so the
+// latest version of a day replaces any older queued one. The queue survives
+// restarts and network failures and is flushed in the background by
+// `SyncState`, which retries with a growing delay while the server cannot be
+// reached. Queued days also win over server data when their week is loaded
+// again, so navigating away and back never drops an edit.
+//
+// Each queued day remembers the server version it was edited on top of. A
+// save the server rejects as stale moves the day into `conflicts` until the
+// user merged it with the server version.
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use tracing::warn;
+
+use super::conflict::DayConflict;
+use super::state::{State, WeekKey};
+use crate::supabase::WorkDayDraft;
+use crate::ui;
+
+/// Quiet time after the last edit before a flush, so typing is saved once.
+pub(crate) const SAVE_DELAY_SECONDS: f64 = 2.0;
+const FIRST_RETRY_SECONDS: f64 = 5.0;
+const MAX_RETRY_SECONDS: f64 = 300.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub(crate) struct Outbox {
+    /// Account the queued days belong to.
+    user_id: Option<String>,
+    /// Queued days; `updated_at` is the server version each one builds on.
+    days: BTreeMap<NaiveDate, WorkDayDraft>,
+    /// The server version of each queued day, as the base of a merge.
+    bases: BTreeMap<NaiveDate, WorkDayDraft>,
+    conflicts: BTreeMap<NaiveDate, DayConflict>,
+    /// The visible week as last observed; changes are measured against it.
+    #[serde(skip)]
+    baseline: Option<(WeekKey, Vec<ui::Day>)>,
+    #[serde(skip)]
+    last_change: Option<f64>,
+    #[serde(skip)]
+    failures: u32,
+    #[serde(skip)]
+    retry_at: Option<f64>,
+}
+
+impl Outbox {
+    /// Queues the visible days that changed since the last call. A different
+    /// visible week only moves the baseline. `server_day` looks up the server
+    /// version of a date the first time it is queued.
+    pub(crate) fn observe(&mut self, state: &State, time: f64, server_day: impl Fn(NaiveDate) -> Option<WorkDayDraft>) {
+        let week = state.current_week_key();
+        let Some((baseline_week, baseline)) = &self.baseline else {
+            return self.rebase(state);
+        };
+        if *baseline_week != week {
+            return self.rebase(state);
+        }
+        if baseline.as_slice() == state.days() {
+            return;
+        }
+        let changed: Vec<&ui::Day> = state.days().iter().filter(|day| !baseline.iter().any(|old| old == *day)).collect();
+        for day in changed {
+            match WorkDayDraft::from_ui_day(day) {
+                Ok(draft) => {
+                    self.enqueue(draft, &server_day);
+                    self.last_change = Some(time);
+                }
+                Err(err) => warn!(target = "sync", error = %err, date = %day.date, "cannot queue day for saving"),
+            }
+        }
+        self.baseline = Some((week, state.days().to_vec()));
+    }
+
+    /// Takes the visible week as it is without queueing it, e.g. after it was
+    /// loaded from the server.
+    pub(crate) fn rebase(&mut self, state: &State) {
+        self.baseline = Some((state.current_week_key(), state.days().to_vec()));
+    }
+
+    /// Queues days changed outside the visible week, e.g. by an import.
+    pub(crate) fn queue(
+        &mut self,
+        drafts: impl IntoIterator<Item = WorkDayDraft>,
+        time: f64,
+        server_day: impl Fn(NaiveDate) -> Option<WorkDayDraft>,
+    ) {
+        for draft in drafts {
+            self.enqueue(draft, &server_day);
+        }
+        self.last_change = Some(time);
+    }
+
+    /// Edits of a conflicting day go to its local side, so the merge sees
+    /// them; other days keep the base they were first queued with.
+    fn enqueue(&mut self, mut draft: WorkDayDraft, server_day: &impl Fn(NaiveDate) -> Option<WorkDayDraft>) {
+        let date = draft.work_date;
+        if let Some(conflict) = self.conflicts.get_mut(&date) {
+            conflict.local = draft;
+            return;
+        }
+        if !self.days.contains_key(&date) {
+            match server_day(date) {
+                Some(base) => self.bases.insert(date, base),
+                None => self.bases.remove(&date),
+            };
+        }
+        draft.updated_at = self.bases.get(&date).and_then(|base| base.updated_at);
+        self.days.insert(date, draft);
+    }
+
+    /// Ties the queue to the logged in account. Days queued for another
+    /// account are dropped rather than saved into the wrong one; returns how
+    /// many were dropped.
+    pub(crate) fn adopt_user(&mut self, user_id: &str) -> usize {
+        if self.user_id.as_deref() == Some(user_id) {
+            return 0;
+        }
+        let dropped = if self.user_id.is_some() { self.len() } else { 0 };
+        if dropped > 0 {
+            self.days.clear();
+            self.bases.clear();
+            self.conflicts.clear();
+        }
+        self.user_id = Some(user_id.to_string());
+        dropped
+    }
+
+    /// Queued and conflicting days.
+    pub(crate) fn len(&self) -> usize {
+        self.days.len() + self.conflicts.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.days.is_empty() && self.conflicts.is_empty()
+    }
+
+    /// The local version of `date` while it is not saved yet.
+    pub(crate) fn pending(&self, date: NaiveDate) -> Option<&WorkDayDraft> {
+        self.days
+            .get(&date)
+            .or_else(|| self.conflicts.get(&date).map(|conflict| &conflict.local))
+    }
+
+    /// Dates with queued or conflicting days.
+    pub(crate) fn pending_dates(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.days.keys().chain(self.conflicts.keys()).copied()
+    }
+
+    /// Weeks with queued or conflicting days, in order.
+    pub(crate) fn pending_weeks(&self) -> Vec<WeekKey> {
+        let mut dates: Vec<NaiveDate> = self.pending_dates().collect();
+        dates.sort();
+        let mut weeks: Vec<WeekKey> = dates.into_iter().map(WeekKey::containing).collect();
+        weeks.dedup();
+        weeks
+    }
+
+    pub(crate) fn conflicts(&self) -> &BTreeMap<NaiveDate, DayConflict> {
+        &self.conflicts
+    }
+
+    /// Sets a queued day aside after the server rejected `sent` as stale.
+    pub(crate) fn set_conflict(&mut self, sent: &WorkDayDraft, server: WorkDayDraft) {
+        let date = sent.work_date;
+        let local = self.days.remove(&date).unwrap_or_else(|| sent.clone());
+        let base = self.bases.remove(&date);
+        self.conflicts.insert(date, DayConflict { base, local, server });
+    }
+
+    /// Queues the merge of a conflicting day on top of the server version.
+    /// Returns the server version, or `None` if `date` had no conflict.
+    pub(crate) fn resolve(&mut self, merged: WorkDayDraft, time: f64) -> Option<WorkDayDraft> {
+        let conflict = self.conflicts.remove(&merged.work_date)?;
+        self.bases.insert(merged.work_date, conflict.server.clone());
+        self.days.insert(
+            merged.work_date,
+            WorkDayDraft {
+                updated_at: conflict.server.updated_at,
+                ..merged
+            },
+        );
+        self.last_change = Some(time);
+        Some(conflict.server)
+    }
+
+    /// Whether a flush should start now: something is queued, the user
+    /// stopped typing and no retry delay is running.
+    pub(crate) fn is_due(&self, time: f64) -> bool {
+        !self.days.is_empty() && self.is_ready(time)
+    }
+
+    /// Like `is_due`, for a flush that has something to save besides days.
+    pub(crate) fn is_ready(&self, time: f64) -> bool {
+        self.next_flush_in(time, true).is_none()
+    }
+
+    /// Seconds until a flush may start, while something is waiting.
+    /// `has_other_changes` counts changes that are not queued days.
+    pub(crate) fn next_flush_in(&self, time: f64, has_other_changes: bool) -> Option<f64> {
+        if self.days.is_empty() && !has_other_changes {
+            return None;
+        }
+        let typing = self.last_change.map_or(0.0, |last| last + SAVE_DELAY_SECONDS - time);
+        let retry = self.retry_in(time).unwrap_or(0.0);
+        Some(typing.max(retry)).filter(|delay| *delay > 0.0)
+    }
+
+    /// Seconds until the next retry, while one is scheduled.
+    pub(crate) fn retry_in(&self, time: f64) -> Option<f64> {
+        self.retry_at.map(|at| at - time).filter(|delay| *delay > 0.0)
+    }
+
+    /// The queued days, oldest date first.
+    pub(crate) fn batch(&self) -> Vec<WorkDayDraft> {
+        self.days.values().cloned().collect()
+    }
+
+    /// Removes a saved day unless it was edited again after `sent` was taken;
+    /// such a day is rebased on `saved`, the version the server now has.
+    pub(crate) fn confirm(&mut self, sent: &WorkDayDraft, saved: &WorkDayDraft) {
+        let date = sent.work_date;
+        match self.days.get_mut(&date) {
+            Some(queued) if queued == sent => {
+                self.days.remove(&date);
+                self.bases.remove(&date);
+            }
+            Some(queued) => {
+                queued.updated_at = saved.updated_at;
+                self.bases.insert(date, saved.clone());
+            }
+            None => {}
+        }
+    }
+
+    pub(crate) fn record_success(&mut self) {
+        self.failures = 0;
+        self.retry_at = None;
+    }
+
+    /// Schedules the next attempt with a delay that doubles per failure, and
+    /// returns that delay.
+    pub(crate) fn record_failure(&mut self, time: f64) -> f64 {
+        let delay = (FIRST_RETRY_SECONDS * 2f64.powi(self.failures.min(16) as i32)).min(MAX_RETRY_SECONDS);
+        self.failures += 1;
+        self.retry_at = Some(time + delay);
+        delay
+    }
+
+    /// Lets the next flush start right away, e.g. for "Save now".
+    pub(crate) fn retry_now(&mut self) {
+        self.last_change = None;
+        self.retry_at = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outbox, MAX_RETRY_SECONDS, SAVE_DELAY_SECONDS};
+    use crate::app::state::State;
+    use crate::supabase::WorkDayDraft;
+    use crate::ui;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn edits_are_queued_per_day_until_confirmed() {
+        let mut state = State::default();
+        let mut outbox = Outbox::default();
+        outbox.observe(&state, 0.0, |_| None);
+        assert!(outbox.is_empty());
+
+        state.days_mut()[0].durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        outbox.observe(&state, 1.0, |_| None);
+        assert_eq!(outbox.len(), 1);
+        assert!(!outbox.is_due(1.5));
+        assert!(outbox.is_due(1.0 + SAVE_DELAY_SECONDS));
+
+        let sent = outbox.batch();
+        state.days_mut()[0].enabled = false;
+        outbox.observe(&state, 2.0, |_| None);
+        // The day changed again while it was being saved, so it stays queued.
+        outbox.confirm(&sent[0], &sent[0]);
+        assert_eq!(outbox.len(), 1);
+        let sent = outbox.batch();
+        outbox.confirm(&sent[0], &sent[0]);
+        assert!(outbox.is_empty());
+    }
+
+    #[test]
+    fn failures_back_off_up_to_a_limit() {
+        let mut outbox = Outbox::default();
+        let delays: Vec<f64> = (0..10).map(|_| outbox.record_failure(0.0)).collect();
+        assert_eq!(delays[..3], [5.0, 10.0, 20.0]);
+        assert_eq!(delays[9], MAX_RETRY_SECONDS);
+        assert_eq!(outbox.retry_in(100.0), Some(MAX_RETRY_SECONDS - 100.0));
+        outbox.record_success();
+        assert_eq!(outbox.retry_in(100.0), None);
+    }
+
+    #[test]
+    fn another_account_drops_the_queue() {
+        let state = State::default();
+        let mut outbox = Outbox::default();
+        assert_eq!(outbox.adopt_user("a"), 0);
+        let drafts = state
+            .days()
+            .iter()
+            .map(|day| crate::supabase::WorkDayDraft::from_ui_day(day).unwrap());
+        outbox.queue(drafts, 0.0, |_| None);
+        assert_eq!(outbox.adopt_user("a"), 0);
+        assert_eq!(outbox.adopt_user("b"), state.days().len());
+        assert!(outbox.is_empty());
+    }
+
+    #[test]
+    fn stale_days_wait_in_conflicts_until_resolved() {
+        let state = State::default();
+        let mut server = WorkDayDraft::from_ui_day(&state.days()[0]).unwrap();
+        server.updated_at = Some(Utc.with_ymd_and_hms(2026, 5, 4, 8, 0, 0).unwrap());
+        let mut outbox = Outbox::default();
+        let mut local = server.clone();
+        local.enabled = !server.enabled;
+        outbox.queue([local], 0.0, |_| Some(server.clone()));
+        let sent = outbox.batch();
+        assert_eq!(sent[0].updated_at, server.updated_at);
+
+        let mut newer = server.clone();
+        newer.updated_at = Some(Utc.with_ymd_and_hms(2026, 5, 4, 9, 0, 0).unwrap());
+        outbox.set_conflict(&sent[0], newer.clone());
+        assert!(outbox.batch().is_empty());
+        assert_eq!(outbox.conflicts()[&server.work_date].base.as_ref(), Some(&server));
+        // The day is still unsaved, so it keeps winning over server data.
+        assert_eq!(outbox.pending(server.work_date), Some(&sent[0]));
+
+        let resolved = outbox.resolve(sent[0].clone(), 1.0);
+        assert_eq!(resolved, Some(newer.clone()));
+        assert!(outbox.conflicts().is_empty());
+        assert_eq!(outbox.batch()[0].updated_at, newer.updated_at);
+    }
+}
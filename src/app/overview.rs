@@ -0,0 +1,227 @@
+// Read-only summaries for the month and year overviews. These combine the
+// visible week, locally stored days and cached server history through
+// `State::recorded_day`, so the overviews and the flex balance agree on what
+// counts for a date.
+
+use std::collections::HashMap;
+
+use crate::ui;
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use super::state::{last_iso_week_of_year, State, WeekKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum OverviewView {
+    #[default]
+    Week,
+    Month,
+    Year,
+    Report,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DayStatus {
+    /// Disabled day or a day without target.
+    Off,
+    /// Target reached or exceeded.
+    Done,
+    /// Some time recorded, but less than the target.
+    Partial,
+    /// Past day with target but nothing recorded.
+    Missed,
+    /// Today or later, nothing recorded yet.
+    Upcoming,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DaySummary {
+    pub date: NaiveDate,
+    pub done: time::Duration,
+    pub target: time::Duration,
+    pub status: DayStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WeekSummary {
+    pub week: WeekKey,
+    pub monday: NaiveDate,
+    pub done: time::Duration,
+    pub target: time::Duration,
+    /// Running balance since the first week of the year, or `None` for weeks
+    /// that have not started yet.
+    pub balance: Option<time::Duration>,
+}
+
+/// Summarizes one date, or returns `None` for hidden weekdays without data.
+pub(crate) fn day_summary(state: &State, history: &HashMap<NaiveDate, ui::Day>, date: NaiveDate, today: NaiveDate) -> Option<DaySummary> {
+    let default_day;
+    let day = match state.recorded_day(date, history) {
+        Some(day) => day,
+        None if state.schedule().weekday(date.weekday()).shown => {
+            default_day = state.default_day_for_date(date);
+            &default_day
+        }
+        None => return None,
+    };
+
+    let done = day.duration(state.break_rules());
+    let target = day.target();
+    let status = if target.is_zero() {
+        DayStatus::Off
+    } else if done >= target {
+        DayStatus::Done
+    } else if done.is_positive() {
+        DayStatus::Partial
+    } else if date < today {
+        DayStatus::Missed
+    } else {
+        DayStatus::Upcoming
+    };
+    Some(DaySummary {
+        date,
+        done,
+        target,
+        status,
+    })
+}
+
+/// Calendar rows for a month: each row is one Monday-first week, with `None`
+/// for dates outside the month or hidden weekdays without data.
+pub(crate) fn month_rows(
+    state: &State,
+    history: &HashMap<NaiveDate, ui::Day>,
+    year: i32,
+    month: u32,
+    today: NaiveDate,
+) -> Vec<(WeekKey, [Option<DaySummary>; 7])> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return vec![];
+    };
+    let mut rows = vec![];
+    let mut monday = first.week(Weekday::Mon).first_day();
+    while monday.year() < year || (monday.year() == year && monday.month() <= month) {
+        let iso_week = monday.iso_week();
+        let mut cells: [Option<DaySummary>; 7] = Default::default();
+        for (offset, cell) in cells.iter_mut().enumerate() {
+            let date = monday + chrono::Duration::days(offset as i64);
+            if date.month() == month && date.year() == year {
+                *cell = day_summary(state, history, date, today);
+            }
+        }
+        rows.push((
+            WeekKey {
+                year: iso_week.year(),
+                week_nr: iso_week.week(),
+            },
+            cells,
+        ));
+        monday += chrono::Duration::weeks(1);
+    }
+    rows
+}
+
+/// Per-week totals for every ISO week of `year`, with a running balance up to
+/// the week that contains `today`.
+pub(crate) fn year_weeks(state: &State, history: &HashMap<NaiveDate, ui::Day>, year: i32, today: NaiveDate) -> Vec<WeekSummary> {
+    let mut balance = time::Duration::ZERO;
+    (1..=last_iso_week_of_year(year))
+        .filter_map(|week_nr| NaiveDate::from_isoywd_opt(year, week_nr, Weekday::Mon).map(|monday| (week_nr, monday)))
+        .map(|(week_nr, monday)| {
+            let (done, target) = monday
+                .iter_days()
+                .take(7)
+                .filter_map(|date| day_summary(state, history, date, today))
+                .fold((time::Duration::ZERO, time::Duration::ZERO), |(done, target), day| {
+                    (done + day.done, target + day.target)
+                });
+            let balance = if monday <= today {
+                // Only count days up to today so the current week does not show
+                // its remaining target as a deficit yet.
+                balance += monday
+                    .iter_days()
+                    .take(7)
+                    .take_while(|date| *date <= today)
+                    .filter_map(|date| day_summary(state, history, date, today))
+                    .fold(time::Duration::ZERO, |sum, day| sum + day.done - day.target);
+                Some(balance)
+            } else {
+                None
+            };
+            WeekSummary {
+                week: WeekKey { year, week_nr },
+                monday,
+                done,
+                target,
+                balance,
+            }
+        })
+        .collect()
+}
+
+/// Date range covered by a month, for history loading.
+pub(crate) fn month_range(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    Some((first, next.pred_opt()?))
+}
+
+/// Date range covered by the ISO weeks of a year, for history loading.
+pub(crate) fn year_range(year: i32) -> Option<(NaiveDate, NaiveDate)> {
+    let first = NaiveDate::from_isoywd_opt(year, 1, Weekday::Mon)?;
+    let last = NaiveDate::from_isoywd_opt(year, last_iso_week_of_year(year), Weekday::Sun)?;
+    Some((first, last))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{month_range, month_rows, year_weeks, DayStatus};
+    use crate::app::state::State;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    #[test]
+    fn month_rows_cover_partial_weeks() {
+        let state = State::default();
+        let today = NaiveDate::from_ymd_opt(2026, 6, 15).unwrap();
+        // June 2026 starts on a Monday and ends on a Tuesday.
+        let rows = month_rows(&state, &HashMap::new(), 2026, 6, today);
+
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].0.week_nr, 23);
+        assert_eq!(rows[0].1[0].as_ref().unwrap().date, NaiveDate::from_ymd_opt(2026, 6, 1).unwrap());
+        assert!(rows[4].1[2].is_none());
+        // Weekends are hidden by default and have no data.
+        assert!(rows[0].1[5].is_none());
+        assert_eq!(rows[0].1[0].as_ref().unwrap().status, DayStatus::Missed);
+        assert_eq!(rows[2].1[4].as_ref().unwrap().status, DayStatus::Upcoming);
+    }
+
+    #[test]
+    fn year_balance_stops_at_today() {
+        let state = State::default();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 8).unwrap();
+        let weeks = year_weeks(&state, &HashMap::new(), 2025, today);
+        let full_day = time::Duration::hours(7) + time::Duration::minutes(36);
+
+        // Week 1 of 2025 starts on Monday December 30, 2024.
+        assert_eq!(weeks[0].balance, Some(-full_day * 5));
+        assert_eq!(weeks[1].balance, Some(-full_day * 8));
+        assert_eq!(weeks[2].balance, None);
+        assert_eq!(weeks[2].target, full_day * 5);
+    }
+
+    #[test]
+    fn month_range_handles_december() {
+        assert_eq!(
+            month_range(2026, 12),
+            Some((
+                NaiveDate::from_ymd_opt(2026, 12, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 12, 31).unwrap()
+            ))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Per-project hour reports. Like the overviews, reports read days through
+// `State::recorded_day`, so local edits win over cached server history. Only
+// work entries count; breaks and automatic break deductions are not spread
+// over projects.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ui;
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use super::state::State;
+
+pub(crate) type DateRange = (NaiveDate, NaiveDate);
+
+/// Label used for entries without a project tag.
+pub(crate) const UNTAGGED: &str = "(untagged)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ReportPeriod {
+    #[default]
+    Week,
+    Month,
+    Custom,
+}
+
+impl ReportPeriod {
+    /// The week or month containing `date`. A custom period starts out as the
+    /// week containing `date`.
+    pub(crate) fn range_containing(self, date: NaiveDate) -> DateRange {
+        match self {
+            ReportPeriod::Week | ReportPeriod::Custom => {
+                let monday = date.week(Weekday::Mon).first_day();
+                (monday, monday + chrono::Duration::days(6))
+            }
+            ReportPeriod::Month => {
+                let first = date.with_day(1).unwrap_or(date);
+                let next = if first.month() == 12 {
+                    NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(first.year(), first.month() + 1, 1)
+                };
+                (first, next.and_then(|next| next.pred_opt()).unwrap_or(first))
+            }
+        }
+    }
+
+    /// The period right before `range`, used for comparisons. Months step by
+    /// calendar month; weeks and custom ranges keep their length.
+    pub(crate) fn previous(self, range: DateRange) -> DateRange {
+        match self {
+            ReportPeriod::Month => self.range_containing(range.0.pred_opt().unwrap_or(range.0)),
+            ReportPeriod::Week | ReportPeriod::Custom => {
+                let len = range.1 - range.0 + chrono::Duration::days(1);
+                (range.0 - len, range.1 - len)
+            }
+        }
+    }
+
+    /// The period right after `range`.
+    pub(crate) fn next(self, range: DateRange) -> DateRange {
+        match self {
+            ReportPeriod::Month => self.range_containing(range.1.succ_opt().unwrap_or(range.1)),
+            ReportPeriod::Week | ReportPeriod::Custom => {
+                let len = range.1 - range.0 + chrono::Duration::days(1);
+                (range.0 + len, range.1 + len)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProjectRow {
+    pub project: String,
+    pub current: time::Duration,
+    pub previous: time::Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct ProjectReport {
+    /// Projects sorted by time in the current period, largest first.
+    pub rows: Vec<ProjectRow>,
+    pub total: time::Duration,
+    pub previous_total: time::Duration,
+}
+
+impl ProjectReport {
+    /// Share of the current period spent on `row`, between 0 and 100.
+    pub(crate) fn share_percent(&self, row: &ProjectRow) -> f64 {
+        if self.total.is_positive() {
+            row.current.as_seconds_f64() / self.total.as_seconds_f64() * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Worked time per project in `range`, compared against `previous`.
+pub(crate) fn project_report(
+    state: &State,
+    history: &HashMap<NaiveDate, ui::Day>,
+    range: DateRange,
+    previous: DateRange,
+    now: time::OffsetDateTime,
+) -> ProjectReport {
+    let current_totals = project_totals(state, history, range, now);
+    let previous_totals = project_totals(state, history, previous, now);
+
+    let mut rows: Vec<_> = current_totals
+        .keys()
+        .chain(previous_totals.keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .map(|project| ProjectRow {
+            project: project.clone(),
+            current: current_totals.get(project).copied().unwrap_or_default(),
+            previous: previous_totals.get(project).copied().unwrap_or_default(),
+        })
+        .collect();
+    rows.sort_by(|a, b| b.current.cmp(&a.current).then_with(|| a.project.cmp(&b.project)));
+
+    ProjectReport {
+        total: current_totals.values().fold(time::Duration::ZERO, |sum, value| sum + *value),
+        previous_total: previous_totals.values().fold(time::Duration::ZERO, |sum, value| sum + *value),
+        rows,
+    }
+}
+
+fn project_totals(
+    state: &State,
+    history: &HashMap<NaiveDate, ui::Day>,
+    (start, end): DateRange,
+    now: time::OffsetDateTime,
+) -> BTreeMap<String, time::Duration> {
+    let mut totals = BTreeMap::new();
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let Some(day) = state.recorded_day(date, history).filter(|day| day.enabled) else {
+            continue;
+        };
+        for entry in day.durations.iter().filter(|entry| !entry.is_break()) {
+            let project = match entry.project().trim() {
+                "" => UNTAGGED.to_string(),
+                project => project.to_string(),
+            };
+            *totals.entry(project).or_insert(time::Duration::ZERO) += entry.duration_at(date, now);
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project_report, ReportPeriod, UNTAGGED};
+    use crate::app::state::State;
+    use crate::ui;
+    use chrono::NaiveDate;
+    use std::collections::HashMap;
+
+    fn entry(date: NaiveDate, start: time::OffsetDateTime, hours: i64, project: &str) -> ui::Duration {
+        let mut entry = ui::Duration::new(date, start, start + time::Duration::hours(hours));
+        *entry.project_mut() = project.to_string();
+        entry
+    }
+
+    #[test]
+    fn previous_period_follows_period_kind() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 31).unwrap();
+        let march = ReportPeriod::Month.range_containing(date);
+        assert_eq!(march.1, date);
+        assert_eq!(
+            ReportPeriod::Month.previous(march),
+            (
+                NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+            )
+        );
+        assert_eq!(ReportPeriod::Month.next(ReportPeriod::Month.previous(march)), march);
+
+        let custom = (
+            NaiveDate::from_ymd_opt(2026, 3, 10).unwrap(),
+            NaiveDate::from_ymd_opt(2026, 3, 12).unwrap(),
+        );
+        assert_eq!(
+            ReportPeriod::Custom.previous(custom),
+            (
+                NaiveDate::from_ymd_opt(2026, 3, 7).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 3, 9).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn report_groups_work_entries_by_project() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 12);
+        let monday = NaiveDate::from_ymd_opt(2026, 3, 16).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_773_648_000).unwrap();
+        state.days_mut()[0].durations = vec![entry(monday, start, 3, "website"), entry(monday, start, 1, "")];
+        let mut pause = entry(monday, start, 1, "website");
+        pause.set_kind(ui::EntryKind::Break);
+        state.days_mut()[0].durations.push(pause);
+
+        // Previous week only exists on the server.
+        let previous_monday = NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let mut server_day = state.default_day_for_date(previous_monday);
+        let previous_start = start - time::Duration::weeks(1);
+        server_day.durations = vec![entry(previous_monday, previous_start, 2, "support")];
+        let history = HashMap::from([(previous_monday, server_day)]);
+
+        let range = ReportPeriod::Week.range_containing(monday);
+        let report = project_report(&state, &history, range, ReportPeriod::Week.previous(range), start);
+
+        assert_eq!(report.total, time::Duration::hours(4));
+        assert_eq!(report.previous_total, time::Duration::hours(2));
+        let projects: Vec<_> = report.rows.iter().map(|row| row.project.as_str()).collect();
+        assert_eq!(projects, vec!["website", UNTAGGED, "support"]);
+        assert_eq!(report.share_percent(&report.rows[0]), 75.0);
+        assert_eq!(report.rows[2].previous, time::Duration::hours(2));
+    }
+}
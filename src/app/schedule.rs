@@ -0,0 +1,133 @@
+// Per-user week layout. The schedule decides which weekdays are part of the
+// visible week and whether newly created days start enabled. Targets come from
+// the contract profile. It is pure data so both the state and sync layers can
+// use it.
+
+use crate::ui;
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use super::contract::ContractProfile;
+
+pub(crate) const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct WeekdaySchedule {
+    /// Whether this weekday is part of the visible week at all.
+    pub shown: bool,
+    /// Whether a newly created day for this weekday counts toward totals.
+    pub enabled: bool,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct WeekSchedule {
+    weekdays: [WeekdaySchedule; 7],
+}
+
+impl Default for WeekSchedule {
+    fn default() -> Self {
+        let workday = WeekdaySchedule {
+            shown: true,
+            enabled: true,
+        };
+        let weekend = WeekdaySchedule {
+            shown: false,
+            enabled: false,
+        };
+        Self {
+            weekdays: [
+                workday.clone(),
+                workday.clone(),
+                workday.clone(),
+                workday.clone(),
+                workday,
+                weekend.clone(),
+                weekend,
+            ],
+        }
+    }
+}
+
+impl WeekSchedule {
+    pub(crate) fn weekday(&self, weekday: Weekday) -> &WeekdaySchedule {
+        &self.weekdays[weekday.num_days_from_monday() as usize]
+    }
+
+    pub(crate) fn weekday_mut(&mut self, weekday: Weekday) -> &mut WeekdaySchedule {
+        &mut self.weekdays[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Returns the shown weekdays in Monday-first order.
+    pub(crate) fn shown_weekdays(&self) -> impl Iterator<Item = Weekday> + '_ {
+        WEEKDAYS.into_iter().filter(|weekday| self.weekday(*weekday).shown)
+    }
+
+    pub(crate) fn shown_count(&self) -> usize {
+        self.shown_weekdays().count()
+    }
+
+    /// Returns the dates of the shown weekdays for the given ISO week.
+    pub(crate) fn week_dates(&self, year: i32, week_nr: u32) -> Vec<NaiveDate> {
+        self.shown_weekdays()
+            .filter_map(|weekday| NaiveDate::from_isoywd_opt(year, week_nr, weekday))
+            .collect()
+    }
+
+    /// Builds the day used when no stored data exists yet for `date`.
+    pub(crate) fn default_day_for_date(&self, date: NaiveDate, contract: &ContractProfile) -> ui::Day {
+        let weekday = self.weekday(date.weekday());
+        let mut day = ui::Day::new(date.format("%A").to_string(), contract.target_for(date.weekday()));
+        day.date = date;
+        day.enabled = weekday.enabled;
+        day
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeekSchedule;
+    use crate::app::contract::ContractProfile;
+    use chrono::{NaiveDate, Weekday};
+
+    #[test]
+    fn default_schedule_shows_monday_to_friday() {
+        let schedule = WeekSchedule::default();
+        let shown: Vec<_> = schedule.shown_weekdays().collect();
+        assert_eq!(shown, vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]);
+    }
+
+    #[test]
+    fn week_dates_follow_shown_weekdays() {
+        let mut schedule = WeekSchedule::default();
+        schedule.weekday_mut(Weekday::Sat).shown = true;
+        schedule.weekday_mut(Weekday::Wed).shown = false;
+
+        let dates = schedule.week_dates(2026, 18);
+
+        assert_eq!(dates.len(), 5);
+        assert_eq!(dates.first(), NaiveDate::from_ymd_opt(2026, 4, 27).as_ref());
+        assert_eq!(dates.last(), NaiveDate::from_ymd_opt(2026, 5, 2).as_ref());
+        assert!(!dates.contains(&NaiveDate::from_ymd_opt(2026, 4, 29).unwrap()));
+    }
+
+    #[test]
+    fn default_day_uses_weekday_defaults() {
+        let mut schedule = WeekSchedule::default();
+        schedule.weekday_mut(Weekday::Sun).shown = true;
+        let mut contract = ContractProfile::full_time();
+        contract.set_target(Weekday::Sun, time::Duration::hours(4));
+
+        let day = schedule.default_day_for_date(NaiveDate::from_ymd_opt(2026, 5, 3).unwrap(), &contract);
+
+        assert_eq!(day.name, "Sunday");
+        assert!(!day.enabled);
+        assert_eq!(day.configured_target(), time::Duration::hours(4));
+    }
+}
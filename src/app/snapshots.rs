@@ -0,0 +1,215 @@
+// Local copies of days, taken automatically before changes that discard or
+// overwrite data: imports, restores, "Reset state", a week load replacing
+// local entries and a day's "Clear". A snapshot holds only the days the change
+// touched; changes that replace all local data keep every day plus the
+// settings. Snapshots are persisted with the app so a change can still be
+// rolled back after a restart, independent of the server, and are browsed in
+// the History window. Week load snapshots only live until the app closes.
+
+use super::backup::BackupSettings;
+use super::state::{State, WeekKey};
+use crate::ui;
+
+/// Oldest snapshots are dropped beyond this count to bound storage size.
+const MAX_SNAPSHOTS: usize = 20;
+/// Oldest snapshots are also dropped while all of them together hold more
+/// days than this; the newest one is always kept.
+const MAX_SNAPSHOT_DAYS: usize = 1000;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub(crate) struct Snapshot {
+    pub id: u64,
+    pub taken_at: time::OffsetDateTime,
+    pub reason: String,
+    /// The touched days as they were before the change.
+    pub days: Vec<ui::Day>,
+    /// Set when the change replaced all local data; restoring then replaces
+    /// all days and settings again.
+    pub settings: Option<BackupSettings>,
+    /// Whether the snapshot is saved with the app. Only persisted snapshots
+    /// are ever loaded, so loading sets it.
+    #[serde(skip, default = "loaded_snapshot_is_persisted")]
+    persisted: bool,
+}
+
+fn loaded_snapshot_is_persisted() -> bool {
+    true
+}
+
+impl Snapshot {
+    pub(crate) fn taken_at_label(&self) -> String {
+        self.taken_at
+            .format(time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]"))
+            .unwrap_or_default()
+    }
+
+    /// What the snapshot covers, e.g. "week 20 of 2026" or "all data".
+    pub(crate) fn scope_label(&self) -> String {
+        if self.settings.is_some() {
+            return "all data".to_string();
+        }
+        match self.days.as_slice() {
+            [] => "no days".to_string(),
+            [day] => day.date.format("%a %Y-%m-%d").to_string(),
+            [first, rest @ ..] => {
+                let week = WeekKey::containing(first.date);
+                let last = rest.iter().map(|day| day.date).max().unwrap_or(first.date);
+                if WeekKey::containing(last) == week && rest.iter().all(|day| WeekKey::containing(day.date) == week) {
+                    format!("week {} of {}", week.week_nr, week.year)
+                } else {
+                    format!("{} days, {} to {last}", self.days.len(), first.date)
+                }
+            }
+        }
+    }
+
+    /// Applies the snapshot to `state`: its days replace their current
+    /// version, and a snapshot of all data replaces everything.
+    pub(crate) fn restore_into(&self, state: &mut State) {
+        match &self.settings {
+            Some(settings) => state.restore_backup(self.days.clone(), settings.clone()),
+            None => state.merge_days(self.days.clone()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub(crate) struct Snapshots {
+    #[serde(serialize_with = "serialize_persisted")]
+    entries: Vec<Snapshot>,
+    next_id: u64,
+}
+
+impl Snapshots {
+    /// Stores the touched `days` as they are and returns the snapshot id.
+    pub(crate) fn take_days(&mut self, reason: impl Into<String>, days: Vec<ui::Day>, now: time::OffsetDateTime) -> u64 {
+        self.push(reason.into(), days, None, true, now)
+    }
+
+    /// Like `take_days`, but the snapshot is not persisted with the app.
+    pub(crate) fn take_unpersisted_days(&mut self, reason: impl Into<String>, days: Vec<ui::Day>, now: time::OffsetDateTime) -> u64 {
+        self.push(reason.into(), days, None, false, now)
+    }
+
+    /// Stores all local days and settings of `state` and returns the id.
+    pub(crate) fn take_all(&mut self, reason: impl Into<String>, state: &State, now: time::OffsetDateTime) -> u64 {
+        self.push(reason.into(), state.stored_days(), Some(state.backup_settings()), true, now)
+    }
+
+    fn push(
+        &mut self,
+        reason: String,
+        days: Vec<ui::Day>,
+        settings: Option<BackupSettings>,
+        persisted: bool,
+        now: time::OffsetDateTime,
+    ) -> u64 {
+        self.next_id += 1;
+        self.entries.push(Snapshot {
+            id: self.next_id,
+            taken_at: now,
+            reason,
+            days,
+            settings,
+            persisted,
+        });
+        while self.entries.len() > MAX_SNAPSHOTS
+            || (self.entries.len() > 1 && self.entries.iter().map(|snapshot| snapshot.days.len()).sum::<usize>() > MAX_SNAPSHOT_DAYS)
+        {
+            self.entries.remove(0);
+        }
+        self.next_id
+    }
+
+    pub(crate) fn get(&self, id: u64) -> Option<&Snapshot> {
+        self.entries.iter().find(|snapshot| snapshot.id == id)
+    }
+
+    /// Removes and returns a snapshot, e.g. once it has been restored.
+    pub(crate) fn remove(&mut self, id: u64) -> Option<Snapshot> {
+        let ix = self.entries.iter().position(|snapshot| snapshot.id == id)?;
+        Some(self.entries.remove(ix))
+    }
+
+    /// Snapshots from newest to oldest.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Snapshot> {
+        self.entries.iter().rev()
+    }
+}
+
+fn serialize_persisted<S>(entries: &[Snapshot], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.collect_seq(entries.iter().filter(|snapshot| snapshot.persisted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Snapshots, MAX_SNAPSHOTS, MAX_SNAPSHOT_DAYS};
+    use crate::app::state::State;
+
+    #[test]
+    fn oldest_snapshots_are_dropped() {
+        let state = State::default();
+        let now = time::OffsetDateTime::UNIX_EPOCH;
+        let mut snapshots = Snapshots::default();
+        let first = snapshots.take_days("first", state.days().to_vec(), now);
+        for _ in 0..MAX_SNAPSHOTS {
+            snapshots.take_days("later", state.days().to_vec(), now);
+        }
+
+        assert!(snapshots.get(first).is_none());
+        assert_eq!(snapshots.iter().count(), MAX_SNAPSHOTS);
+        let newest = snapshots.iter().next().unwrap().id;
+        assert!(snapshots.remove(newest).is_some());
+        assert!(snapshots.get(newest).is_none());
+    }
+
+    #[test]
+    fn snapshots_are_bounded_by_days_and_loads_are_not_persisted() {
+        let state = State::default();
+        let now = time::OffsetDateTime::UNIX_EPOCH;
+        let mut snapshots = Snapshots::default();
+        let week = state.days().to_vec();
+        let cleared = snapshots.take_days("Before clearing", week[..1].to_vec(), now);
+        let loaded = snapshots.take_unpersisted_days("Before loading", week.clone(), now);
+        assert_eq!(
+            snapshots.get(cleared).unwrap().scope_label(),
+            week[0].date.format("%a %Y-%m-%d").to_string()
+        );
+        let week_key = state.current_week_key();
+        assert_eq!(
+            snapshots.get(loaded).unwrap().scope_label(),
+            format!("week {} of {}", week_key.week_nr, week_key.year)
+        );
+
+        let persisted: Snapshots = ron::from_str(&ron::to_string(&snapshots).unwrap()).unwrap();
+        assert!(persisted.get(cleared).is_some());
+        assert!(persisted.get(loaded).is_none());
+
+        let many = vec![week[0].clone(); MAX_SNAPSHOT_DAYS];
+        let large = snapshots.take_days("Before importing", many, now);
+        assert_eq!(snapshots.iter().map(|snapshot| snapshot.id).collect::<Vec<_>>(), [large]);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_puts_back_its_days_only() {
+        let mut state = State::default();
+        let now = time::OffsetDateTime::UNIX_EPOCH;
+        let mut snapshots = Snapshots::default();
+        let id = snapshots.take_days("Before clearing", state.days()[..1].to_vec(), now);
+        state.days_mut()[0].enabled = false;
+        state.days_mut()[1].enabled = false;
+
+        snapshots.get(id).unwrap().restore_into(&mut state);
+        assert!(state.days()[0].enabled);
+        assert!(!state.days()[1].enabled);
+
+        let all = snapshots.take_all("Before resetting", &state, now);
+        let mut reset = State::default();
+        snapshots.get(all).unwrap().restore_into(&mut reset);
+        assert!(!reset.days()[1].enabled);
+    }
+}
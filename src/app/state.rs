@@ -8,18 +8,56 @@ use crate::ui;
 use anyhow::{anyhow, Result};
 use chrono::{Datelike, NaiveDate, Weekday};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use super::backup::BackupSettings;
+use super::contract::{self, ContractPeriod, ContractProfile};
+use super::schedule::WeekSchedule;
+use super::timer::{self, RunningTimer};
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct WeekKey {
     pub(crate) year: i32,
     pub(crate) week_nr: u32,
 }
 
+impl WeekKey {
+    /// Monday of this ISO week. Keys are always normalized, so this is valid.
+    pub(crate) fn monday(&self) -> NaiveDate {
+        NaiveDate::from_isoywd_opt(self.year, self.week_nr, Weekday::Mon).unwrap_or_default()
+    }
+
+    /// The ISO week `date` falls in.
+    pub(crate) fn containing(date: NaiveDate) -> Self {
+        let week = date.iso_week();
+        Self {
+            year: week.year(),
+            week_nr: week.week(),
+        }
+    }
+
+    /// The week `nr_weeks` before or after this one.
+    pub(crate) fn offset(&self, nr_weeks: i64) -> Self {
+        Self::containing(self.monday() + chrono::Duration::weeks(nr_weeks))
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
 pub struct State {
     days: Vec<ui::Day>,
     all_days: HashMap<NaiveDate, ui::Day>,
     cur_week_nr: u32,
     cur_year: i32,
+    #[serde(default)]
+    schedule: WeekSchedule,
+    #[serde(default)]
+    contract: ContractProfile,
+    #[serde(default)]
+    contract_periods: Vec<ContractPeriod>,
+    #[serde(default)]
+    balance_start: Option<NaiveDate>,
+    #[serde(default)]
+    timer: Option<RunningTimer>,
+    #[serde(default)]
+    break_rules: ui::BreakRules,
 }
 
 fn current_work_week_monday() -> NaiveDate {
@@ -56,12 +94,14 @@ impl State {
         self.save_current_week();
         self.cur_week_nr = week_nr;
         self.cur_year = year;
-        let cur_monday = NaiveDate::from_isoywd_opt(year, week_nr, Weekday::Mon).ok_or(anyhow!("invalid date"))?;
-        self.days = (0..5)
-            .map(|day_ix| {
-                let date = cur_monday + chrono::Duration::days(day_ix);
-                let mut day = ui::Day::new(date.format("%A").to_string());
-                day.date = date;
+        NaiveDate::from_isoywd_opt(year, week_nr, Weekday::Mon).ok_or(anyhow!("invalid date"))?;
+        self.days = self
+            .schedule
+            .week_dates(year, week_nr)
+            .into_iter()
+            .map(|date| {
+                let profile = contract::profile_for_date(&self.contract_periods, &self.contract, date);
+                let day = self.schedule.default_day_for_date(date, profile);
                 self.all_days.entry(date).or_insert(day).clone()
             })
             .collect();
@@ -74,8 +114,56 @@ impl State {
         }
     }
 
+    /// Stores whole days from outside the app, such as an import. Days in the
+    /// visible week replace their visible copy as well.
+    pub(crate) fn merge_days(&mut self, days: Vec<ui::Day>) {
+        for day in days {
+            if let Some(visible) = self.days.iter_mut().find(|visible| visible.date == day.date) {
+                *visible = day.clone();
+            }
+            self.all_days.insert(day.date, day);
+        }
+    }
+
+    /// Every stored day sorted by date, with the visible week taking
+    /// precedence over its stored copy.
+    pub(crate) fn stored_days(&self) -> Vec<ui::Day> {
+        let mut days = self.all_days.clone();
+        for day in &self.days {
+            days.insert(day.date, day.clone());
+        }
+        let mut days: Vec<ui::Day> = days.into_values().collect();
+        days.sort_by_key(|day| day.date);
+        days
+    }
+
+    pub(crate) fn backup_settings(&self) -> BackupSettings {
+        BackupSettings {
+            schedule: self.schedule.clone(),
+            contract: self.contract.clone(),
+            contract_periods: self.contract_periods.clone(),
+            balance_start: self.balance_start,
+            break_rules: self.break_rules.clone(),
+        }
+    }
+
+    /// Replaces all days and settings with those of a backup. The visible week
+    /// stays the same and a running timer keeps running.
+    pub(crate) fn restore_backup(&mut self, days: Vec<ui::Day>, settings: BackupSettings) {
+        self.schedule = settings.schedule;
+        self.contract = settings.contract;
+        self.contract_periods = settings.contract_periods;
+        self.balance_start = settings.balance_start;
+        self.break_rules = settings.break_rules;
+        self.all_days = days.into_iter().map(|day| (day.date, day)).collect();
+        self.days.clear();
+        let _ = self.set_current_week(self.cur_week_nr, self.cur_year);
+        self.restore_timer_entry();
+    }
+
     pub(crate) fn replace_current_week_days(&mut self, days: Vec<ui::Day>) {
         self.days = days;
+        self.restore_timer_entry();
         self.save_current_week();
     }
 
@@ -125,23 +213,267 @@ impl State {
     }
 
     pub(crate) fn current_week_dates(&self) -> Vec<NaiveDate> {
-        let monday = NaiveDate::from_isoywd_opt(self.cur_year, self.cur_week_nr, Weekday::Mon).unwrap();
-        (0..5).map(|offset| monday + chrono::Duration::days(offset)).collect()
+        self.schedule.week_dates(self.cur_year, self.cur_week_nr)
     }
 
     pub(crate) fn current_week_range(&self) -> (NaiveDate, NaiveDate) {
-        let dates = self.current_week_dates();
+        self.week_range(self.current_week_key())
+    }
+
+    /// First and last shown date of `week`.
+    pub(crate) fn week_range(&self, week: WeekKey) -> (NaiveDate, NaiveDate) {
+        let dates = self.schedule.week_dates(week.year, week.week_nr);
         (*dates.first().unwrap(), *dates.last().unwrap())
     }
 
     pub(crate) fn duration(&self) -> time::Duration {
-        self.days.iter().fold(time::Duration::ZERO, |sum, day| sum + day.duration())
+        self.days
+            .iter()
+            .fold(time::Duration::ZERO, |sum, day| sum + day.duration(&self.break_rules))
     }
 
     pub(crate) fn total_target(&self) -> time::Duration {
         self.days.iter().fold(time::Duration::ZERO, |sum, day| sum + day.target())
     }
 
+    /// Builds the day used for `date` when neither local nor server data exists.
+    pub(crate) fn default_day_for_date(&self, date: NaiveDate) -> ui::Day {
+        self.schedule.default_day_for_date(date, self.contract_for_date(date))
+    }
+
+    pub(crate) fn schedule(&self) -> &WeekSchedule {
+        &self.schedule
+    }
+
+    /// Replaces the week schedule and rebuilds the visible week from it.
+    ///
+    /// Days that become hidden stay in `all_days`, so showing the weekday again
+    /// later brings back whatever was recorded for it.
+    pub(crate) fn set_schedule(&mut self, schedule: WeekSchedule) {
+        if schedule.shown_count() == 0 {
+            return;
+        }
+        self.save_current_week();
+        self.schedule = schedule;
+        let _ = self.set_current_week(self.cur_week_nr, self.cur_year);
+    }
+
+    pub(crate) fn contract(&self) -> &ContractProfile {
+        &self.contract
+    }
+
+    /// Replaces the fallback contract profile used for newly created days that
+    /// are not covered by any contract period.
+    ///
+    /// Days that already exist keep their stored target; use
+    /// `apply_contract_to_current_week` to retarget the visible week.
+    pub(crate) fn set_contract(&mut self, contract: ContractProfile) {
+        self.contract = contract;
+    }
+
+    pub(crate) fn contract_periods(&self) -> &[ContractPeriod] {
+        &self.contract_periods
+    }
+
+    /// Replaces the contract periods, kept sorted by start date.
+    ///
+    /// The server allows one period per start date, so a list where two
+    /// periods start on the same day is refused and the current one kept.
+    pub(crate) fn set_contract_periods(&mut self, mut periods: Vec<ContractPeriod>) {
+        periods.sort_by_key(|period| period.start);
+        if periods.windows(2).any(|pair| pair[0].start == pair[1].start) {
+            return;
+        }
+        self.contract_periods = periods;
+    }
+
+    /// Returns the contract profile that was valid on `date`.
+    pub(crate) fn contract_for_date(&self, date: NaiveDate) -> &ContractProfile {
+        contract::profile_for_date(&self.contract_periods, &self.contract, date)
+    }
+
+    /// Resets the targets of the visible week to the contract valid on each day.
+    pub(crate) fn apply_contract_to_current_week(&mut self) {
+        for day in &mut self.days {
+            day.set_target(contract::target_for_date(&self.contract_periods, &self.contract, day.date));
+        }
+        self.save_current_week();
+    }
+
+    pub(crate) fn break_rules(&self) -> &ui::BreakRules {
+        &self.break_rules
+    }
+
+    /// Replaces the break rules. Days only hold their entries, so totals and
+    /// balances follow the new rules everywhere.
+    pub(crate) fn set_break_rules(&mut self, rules: ui::BreakRules) {
+        self.break_rules = rules;
+    }
+
+    pub(crate) fn balance_start(&self) -> Option<NaiveDate> {
+        self.balance_start
+    }
+
+    pub(crate) fn set_balance_start(&mut self, start: Option<NaiveDate>) {
+        self.balance_start = start;
+    }
+
+    /// Looks up the best known data for `date`: an edited local day first,
+    /// then days fetched from the server, then the untouched default day the
+    /// app created when the week was visited.
+    pub(crate) fn recorded_day<'a>(&'a self, date: NaiveDate, history: &'a HashMap<NaiveDate, ui::Day>) -> Option<&'a ui::Day> {
+        match self.local_day(date) {
+            Some(day) if !self.is_untouched(day) => Some(day),
+            local => history.get(&date).or(local),
+        }
+    }
+
+    /// Whether `day` still is the default day for its date, i.e. nothing was
+    /// recorded on it. Queued edits that turned a day back into its default
+    /// are kept out of the history cache by the sync layer.
+    pub(crate) fn is_untouched(&self, day: &ui::Day) -> bool {
+        let default_day = self.default_day_for_date(day.date);
+        day.durations.is_empty() && day.enabled == default_day.enabled && day.configured_target() == default_day.configured_target()
+    }
+
+    /// Whether the local day for `date` holds something recorded on this
+    /// device rather than an untouched default day.
+    pub(crate) fn has_local_edit(&self, date: NaiveDate) -> bool {
+        self.local_day(date).is_some_and(|day| !self.is_untouched(day))
+    }
+
+    /// The local day for `date`, or the default day when none is stored.
+    pub(crate) fn local_day_or_default(&self, date: NaiveDate) -> ui::Day {
+        self.local_day(date).cloned().unwrap_or_else(|| self.default_day_for_date(date))
+    }
+
+    /// Looks up `date` in the visible week first, then in locally stored days.
+    pub(crate) fn local_day(&self, date: NaiveDate) -> Option<&ui::Day> {
+        self.days.iter().find(|day| day.date == date).or_else(|| self.all_days.get(&date))
+    }
+
+    /// Cumulative worked time minus target from `start` through `until`, both
+    /// inclusive.
+    ///
+    /// Shown weekdays without any recorded data still count with their default
+    /// day, so a skipped workday costs its contract target instead of silently
+    /// disappearing from the balance.
+    pub(crate) fn flex_balance(&self, start: NaiveDate, until: NaiveDate, history: &HashMap<NaiveDate, ui::Day>) -> time::Duration {
+        let mut balance = time::Duration::ZERO;
+        for date in start.iter_days().take_while(|date| *date <= until) {
+            match self.recorded_day(date, history) {
+                Some(day) => balance += day.duration(&self.break_rules) - day.target(),
+                None if self.schedule.weekday(date.weekday()).shown => {
+                    let day = self.default_day_for_date(date);
+                    balance += day.duration(&self.break_rules) - day.target();
+                }
+                None => {}
+            }
+        }
+        balance
+    }
+
+    /// Returns the day for `date` wherever it lives, creating a default day in
+    /// the local store when nothing was recorded yet.
+    fn day_mut(&mut self, date: NaiveDate) -> &mut ui::Day {
+        if let Some(ix) = self.days.iter().position(|day| day.date == date) {
+            return &mut self.days[ix];
+        }
+        let default_day = self.default_day_for_date(date);
+        self.all_days.entry(date).or_insert(default_day)
+    }
+
+    pub(crate) fn running_timer(&self) -> Option<&RunningTimer> {
+        self.timer.as_ref()
+    }
+
+    /// Starts a timer with an open entry on the day of `now`. Returns false
+    /// when a timer is already running.
+    pub(crate) fn start_timer(&mut self, now: time::OffsetDateTime) -> bool {
+        self.refresh_timer(now);
+        if self.timer.is_some() {
+            return false;
+        }
+        let date = ui::duration::local_date(now);
+        let entry = ui::Duration::new_open(now);
+        let row_id = entry.row_id();
+        self.day_mut(date).durations.push(entry);
+        self.timer = Some(RunningTimer {
+            date,
+            row_id,
+            started_at: now,
+        });
+        true
+    }
+
+    /// Keeps the timer in line with the entries. A timer whose entry was
+    /// closed or removed, possibly on another device, stops; an open entry in
+    /// the visible week without a timer, e.g. one started elsewhere, is
+    /// adopted.
+    pub(crate) fn refresh_timer(&mut self, now: time::OffsetDateTime) {
+        if let Some(running) = &self.timer {
+            let still_open = self.local_day(running.date).is_some_and(|day| {
+                day.durations
+                    .iter()
+                    .any(|duration| duration.row_id() == running.row_id && duration.is_open())
+            });
+            if !still_open {
+                self.timer = None;
+            }
+        }
+        if self.timer.is_none() {
+            self.timer = self.days.iter().find_map(|day| {
+                day.durations
+                    .iter()
+                    .find(|duration| duration.is_open())
+                    .map(|duration| RunningTimer {
+                        date: day.date,
+                        row_id: duration.row_id(),
+                        started_at: timer::started_at(day.date, duration.start_clock(), now),
+                    })
+            });
+        }
+    }
+
+    /// Closes the open entry at `now` and returns the stopped timer.
+    pub(crate) fn stop_timer(&mut self, now: time::OffsetDateTime) -> Option<RunningTimer> {
+        let running = self.timer.take()?;
+        if let Some(entry) = self
+            .day_mut(running.date)
+            .durations
+            .iter_mut()
+            .find(|duration| duration.row_id() == running.row_id)
+        {
+            entry.set_end(running.date, now);
+        }
+        Some(running)
+    }
+
+    /// Re-attaches the running timer after the visible days were replaced,
+    /// e.g. by a week load. A row starting at the timer's start time is adopted
+    /// as its entry, even when it was closed elsewhere; otherwise the open
+    /// entry is recreated.
+    fn restore_timer_entry(&mut self) {
+        let Some(running) = self.timer.as_mut() else {
+            return;
+        };
+        let Some(day) = self.days.iter_mut().find(|day| day.date == running.date) else {
+            return;
+        };
+        if day.durations.iter().any(|duration| duration.row_id() == running.row_id) {
+            return;
+        }
+        let start = ui::TimePoint::from_offset_datetime(running.started_at);
+        match day.durations.iter().find(|duration| *duration.start_clock() == start) {
+            Some(existing) => running.row_id = existing.row_id(),
+            None => {
+                let entry = ui::Duration::new_open(running.started_at);
+                running.row_id = entry.row_id();
+                day.durations.push(entry);
+            }
+        }
+    }
+
     pub(crate) fn days_mut(&mut self) -> &mut [ui::Day] {
         &mut self.days
     }
@@ -166,6 +498,12 @@ impl Default for State {
             all_days: HashMap::new(),
             cur_week_nr: 0,
             cur_year: 0,
+            schedule: WeekSchedule::default(),
+            contract: ContractProfile::default(),
+            contract_periods: vec![],
+            balance_start: None,
+            timer: None,
+            break_rules: ui::BreakRules::default(),
         };
         let (cur_week_nr, cur_year) = current_iso_week_and_year();
         let _ = res.set_current_week(cur_week_nr, cur_year);
@@ -176,11 +514,289 @@ impl Default for State {
 #[cfg(test)]
 mod tests {
     use super::State;
+    use crate::app::contract::{ContractPeriod, ContractProfile};
+    use chrono::{Datelike, NaiveDate, Weekday};
+    use std::collections::HashMap;
+
+    #[test]
+    fn week_keys_step_across_year_boundaries() {
+        let week = super::WeekKey { year: 2026, week_nr: 53 };
+        assert_eq!(week.offset(1), super::WeekKey { year: 2027, week_nr: 1 });
+        assert_eq!(week.offset(1).offset(-1), week);
+        assert_eq!(super::WeekKey::containing(NaiveDate::from_ymd_opt(2027, 1, 1).unwrap()), week);
+    }
 
     #[test]
     fn default_state_has_week_target() {
         let state = State::default();
+        assert_eq!(state.total_target(), ContractProfile::full_time().weekly_target());
+    }
+
+    #[test]
+    fn contract_profile_targets_new_weeks() {
+        let mut state = State::default();
+        let mut contract = ContractProfile::full_time();
+        for weekday in [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu] {
+            contract.set_target(weekday, time::Duration::hours(8));
+        }
+        contract.set_target(Weekday::Fri, time::Duration::hours(4));
+        state.set_contract(contract);
+
+        // The visible week already exists, so it keeps its stored targets until
+        // explicitly retargeted; a week that was never visited uses the profile.
         assert_eq!(state.total_target(), time::Duration::hours(38));
+        state.shift_weeks(10);
+        assert_eq!(state.total_target(), time::Duration::hours(36));
+        state.shift_weeks(-10);
+        state.apply_contract_to_current_week();
+        assert_eq!(state.total_target(), time::Duration::hours(36));
+    }
+
+    #[test]
+    fn contract_periods_with_the_same_start_are_refused() {
+        let mut state = State::default();
+        let period = |weekly: i64| ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            end: None,
+            profile: ContractProfile::from_weekly_target("custom", time::Duration::hours(weekly), &crate::app::schedule::WEEKDAYS[..5]),
+        };
+        state.set_contract_periods(vec![period(32)]);
+
+        state.set_contract_periods(vec![period(32), period(36)]);
+        assert_eq!(state.contract_periods(), [period(32)]);
+    }
+
+    #[test]
+    fn past_weeks_use_contract_period_valid_on_that_date() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        state.set_contract_periods(vec![ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            end: None,
+            profile: ContractProfile::from_weekly_target("32h", time::Duration::hours(32), &crate::app::schedule::WEEKDAYS[..4]),
+        }]);
+
+        // Week 27 of 2026 runs Monday June 29 to Friday July 3, so the contract
+        // changes halfway through the week.
+        state.set_current_week_normalized(2026, 27);
+        let targets: Vec<_> = state
+            .days()
+            .iter()
+            .map(|day| (day.date.weekday(), day.configured_target()))
+            .collect();
+        let full_day = time::Duration::hours(7) + time::Duration::minutes(36);
+        assert_eq!(
+            targets,
+            vec![
+                (Weekday::Mon, full_day),
+                (Weekday::Tue, full_day),
+                (Weekday::Wed, time::Duration::hours(8)),
+                (Weekday::Thu, time::Duration::hours(8)),
+                (Weekday::Fri, time::Duration::ZERO),
+            ]
+        );
+    }
+
+    #[test]
+    fn flex_balance_combines_local_server_and_missing_days() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let monday = NaiveDate::from_ymd_opt(2026, 5, 11).unwrap();
+        let full_day = time::Duration::hours(7) + time::Duration::minutes(36);
+
+        // Monday: one hour of overtime recorded locally.
+        let start = time::OffsetDateTime::from_unix_timestamp(1_778_482_800).unwrap();
+        state.days_mut()[0]
+            .durations
+            .push(crate::ui::Duration::new(monday, start, start + full_day + time::Duration::hours(1)));
+
+        // Previous Friday only exists on the server, with half a day worked.
+        let friday = NaiveDate::from_ymd_opt(2026, 5, 8).unwrap();
+        let mut server_day = state.default_day_for_date(friday);
+        let friday_start = time::OffsetDateTime::from_unix_timestamp(1_778_223_600).unwrap();
+        server_day.durations.push(crate::ui::Duration::new(
+            friday,
+            friday_start,
+            friday_start + time::Duration::hours(4),
+        ));
+        let history = HashMap::from([(friday, server_day)]);
+
+        // Thursday has no data anywhere and counts as a full missed day; the
+        // weekend is hidden and does not count.
+        let thursday = NaiveDate::from_ymd_opt(2026, 5, 7).unwrap();
+        let balance = state.flex_balance(thursday, monday, &history);
+
+        let expected = -full_day + (time::Duration::hours(4) - full_day) + time::Duration::hours(1);
+        assert_eq!(balance, expected);
+    }
+
+    #[test]
+    fn server_history_wins_over_untouched_visited_days() {
+        let mut state = State::default();
+        // Visiting week 19 creates default days for it without recording any.
+        state.set_current_week_normalized(2026, 19);
+        state.set_current_week_normalized(2026, 20);
+        let monday = NaiveDate::from_ymd_opt(2026, 5, 4).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2026, 5, 5).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_777_878_000).unwrap();
+        let worked = |date: NaiveDate| {
+            let mut day = state.default_day_for_date(date);
+            day.durations
+                .push(crate::ui::Duration::new(date, start, start + time::Duration::hours(3)));
+            day
+        };
+        let history = HashMap::from([(monday, worked(monday)), (tuesday, worked(tuesday))]);
+
+        assert_eq!(state.recorded_day(monday, &history).unwrap().durations.len(), 1);
+
+        // A local edit of the day still wins over the server copy.
+        state.set_current_week_normalized(2026, 19);
+        state.days_mut()[1].enabled = false;
+        assert!(!state.recorded_day(tuesday, &history).unwrap().enabled);
+
+        let full_day = time::Duration::hours(7) + time::Duration::minutes(36);
+        assert_eq!(state.flex_balance(monday, monday, &history), time::Duration::hours(3) - full_day);
+    }
+
+    #[test]
+    fn schedule_drives_visible_week_days() {
+        let mut state = State::default();
+        let mut schedule = state.schedule().clone();
+        let saturday = schedule.weekday_mut(Weekday::Sat);
+        saturday.shown = true;
+        saturday.enabled = true;
+        let mut contract = state.contract().clone();
+        contract.set_target(Weekday::Sat, time::Duration::hours(4));
+        state.set_contract(contract);
+        state.set_schedule(schedule);
+
+        assert_eq!(state.days().len(), 6);
+        assert_eq!(state.current_week_dates().len(), 6);
+        assert_eq!(state.total_target(), time::Duration::hours(42));
+    }
+
+    #[test]
+    fn hiding_a_weekday_keeps_its_stored_day() {
+        let mut state = State::default();
+        let friday = state.days()[4].date;
+        state.days_mut()[4].enabled = false;
+
+        let mut schedule = state.schedule().clone();
+        schedule.weekday_mut(Weekday::Fri).shown = false;
+        state.set_schedule(schedule.clone());
+        assert!(state.days().iter().all(|day| day.date != friday));
+
+        schedule.weekday_mut(Weekday::Fri).shown = true;
+        state.set_schedule(schedule);
+        let restored = state.days().iter().find(|day| day.date == friday).unwrap();
+        assert!(!restored.enabled);
+    }
+
+    #[test]
+    fn break_rules_apply_to_stored_and_loaded_days() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let start = time::OffsetDateTime::from_unix_timestamp(1_778_482_800).unwrap();
+        let monday = state.days()[0].date;
+        state.days_mut()[0]
+            .durations
+            .push(crate::ui::Duration::new(monday, start, start + time::Duration::hours(8)));
+        state.save_current_week();
+        let balance = state.flex_balance(monday, monday, &HashMap::new());
+
+        state.set_break_rules(crate::ui::BreakRules {
+            rules: vec![crate::ui::BreakRules::six_hour_rule()],
+        });
+        assert_eq!(
+            state.days()[0].duration_at(start, state.break_rules()),
+            time::Duration::hours(7) + time::Duration::minutes(30)
+        );
+        assert_eq!(
+            state.flex_balance(monday, monday, &HashMap::new()),
+            balance - time::Duration::minutes(30)
+        );
+
+        // Days loaded from elsewhere are counted with the same rules.
+        let days: Vec<_> = state.days().to_vec();
+        state.replace_current_week_days(days);
+        assert_eq!(state.duration(), time::Duration::hours(7) + time::Duration::minutes(30));
+    }
+
+    #[test]
+    fn timer_counts_open_entry_until_stopped() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let start = time::OffsetDateTime::from_unix_timestamp(1_778_490_000).unwrap();
+
+        assert!(state.start_timer(start));
+        assert!(!state.start_timer(start));
+        assert!(state.days()[0].durations[0].is_open());
+        assert_eq!(
+            state.days()[0].duration_at(start + time::Duration::minutes(90), state.break_rules()),
+            time::Duration::minutes(90)
+        );
+
+        let stopped = state.stop_timer(start + time::Duration::hours(2)).unwrap();
+        assert_eq!(stopped.date, NaiveDate::from_ymd_opt(2026, 5, 11).unwrap());
+        assert!(state.running_timer().is_none());
+        assert_eq!(
+            state.days()[0].duration_at(start + time::Duration::hours(3), state.break_rules()),
+            time::Duration::hours(2)
+        );
+    }
+
+    #[test]
+    fn timer_survives_week_reload_and_stops_when_row_is_removed() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let start = time::OffsetDateTime::from_unix_timestamp(1_778_490_000).unwrap();
+        state.start_timer(start);
+
+        // A week load without the open entry brings it back.
+        let days = state
+            .current_week_dates()
+            .into_iter()
+            .map(|date| state.default_day_for_date(date))
+            .collect();
+        state.replace_current_week_days(days);
+        state.refresh_timer(start + time::Duration::hours(1));
+        assert_eq!(state.days()[0].durations.len(), 1);
+        assert!(state.running_timer().is_some());
+
+        state.days_mut()[0].durations.clear();
+        state.refresh_timer(start + time::Duration::hours(2));
+        assert!(state.running_timer().is_none());
+    }
+
+    #[test]
+    fn timer_follows_entries_started_and_stopped_elsewhere() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let start = time::OffsetDateTime::from_unix_timestamp(1_778_490_000).unwrap();
+        let monday = state.days()[0].date;
+
+        // Another device started a timer and saved the open entry.
+        let mut days: Vec<_> = state
+            .current_week_dates()
+            .into_iter()
+            .map(|date| state.default_day_for_date(date))
+            .collect();
+        days[0].durations.push(crate::ui::Duration::new_open(start));
+        state.replace_current_week_days(days.clone());
+        state.refresh_timer(start + time::Duration::hours(1));
+        let running = state.running_timer().unwrap();
+        assert_eq!(running.date, monday);
+        assert_eq!(running.elapsed(start + time::Duration::hours(1)), time::Duration::hours(1));
+
+        // ... and stopped it again.
+        days[0].durations[0].set_end(monday, start + time::Duration::hours(2));
+        state.replace_current_week_days(days);
+        state.refresh_timer(start + time::Duration::hours(3));
+        assert!(state.running_timer().is_none());
+        assert_eq!(
+            state.days()[0].duration_at(start + time::Duration::hours(3), state.break_rules()),
+            time::Duration::hours(2)
+        );
     }
 
     #[test]
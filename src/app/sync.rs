@@ -1,39 +1,110 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 // Supabase session and synchronization state. This file owns login/session
-// lifecycle, dirty tracking, background load/save orchestration, and the
-// translation between async results and app state updates.
+// lifecycle, the per-week cache of server data, dirty tracking, background
+// load and prefetch orchestration, flushing the outbox of pending day changes,
+// and the translation between async results and app state updates.
 
 use crate::config::AppConfig;
-use crate::supabase::{StoredSession, SupabaseClient, WorkDayDraft};
+use crate::realtime::{realtime_url, DayChange, RealtimeClient, RealtimeEvent};
+use crate::supabase::{AuthRedirect, ContractPeriodDraft, StoredSession, SupabaseClient, WorkDayDraft, WorkWeekRejected};
 use crate::ui;
-use anyhow::{Context, Error, Result};
+use anyhow::{Error, Result};
+use chrono::NaiveDate;
 use tracing::{debug, info, warn};
 
+use super::conflict::{ConflictMerge, DayConflict};
+use super::contract::ContractPeriod;
+use super::login_link;
+use super::outbox::Outbox;
+use super::snapshots::Snapshots;
 use super::state::{State, WeekKey};
 use super::tasks::{spawn_async_task, AsyncResults};
 use super::ui_state::AppUiState;
+use super::undo::UndoLog;
 
 pub(crate) struct ProcessAsyncContext<'a> {
     pub state: &'a mut State,
-    pub undoer: &'a mut egui::util::undoer::Undoer<State>,
+    pub undo_log: &'a mut UndoLog,
     pub ui_state: &'a mut AppUiState,
+    pub snapshots: &'a mut Snapshots,
     pub config: Option<&'a AppConfig>,
     pub async_results: &'a AsyncResults<AsyncResult>,
     pub ctx: &'a egui::Context,
 }
 
+/// A cached week younger than this is not prefetched again.
+const PREFETCH_MAX_AGE_SECONDS: f64 = 300.0;
+/// Wait before reconnecting after the realtime connection dropped.
+const REALTIME_RETRY_SECONDS: f64 = 15.0;
+
+/// Server rows of one week as last loaded or saved. Visible weeks are shown
+/// from here right away and revalidated in the background.
 #[derive(Debug, Clone)]
-struct WeekSyncSnapshot {
-    week: WeekKey,
-    drafts: Vec<WorkDayDraft>,
+struct CachedWeek {
+    days: Vec<WorkDayDraft>,
+    fetched_at: f64,
+}
+
+/// Server data fetched together with a week. Contract periods ride along so
+/// missing days can be created with the target that was valid on their date.
+#[derive(Debug)]
+pub(crate) struct LoadedWeek {
+    days: Vec<WorkDayDraft>,
+    contract_periods: Vec<ContractPeriodDraft>,
+}
+
+/// Outcome of an outbox flush. Each week is saved in one transaction, so a
+/// rejected day keeps the rest of its week unsaved too, but not other weeks.
+/// Days of a rejected week that had no error of their own are in none of the
+/// lists and stay queued.
+#[derive(Debug)]
+pub(crate) struct SavedDays {
+    saved: Vec<WorkDayDraft>,
+    failed: Vec<(NaiveDate, String)>,
+    /// Server versions of days that were changed elsewhere since their base.
+    conflicts: Vec<WorkDayDraft>,
+    /// Outcome of saving the contract periods, if they were sent. Kept apart
+    /// from the days, so a failure here does not hide the days that saved.
+    contract_periods: Option<Result<Vec<ContractPeriodDraft>, String>>,
+}
+
+/// Server days outside the visible week, fetched for history views such as the
+/// flex balance. Kept apart from `State` so it never ends up in undo history or
+/// overrides local edits.
+#[derive(Debug, Default)]
+struct HistoryCache {
+    days: HashMap<NaiveDate, ui::Day>,
+    loaded_ranges: Vec<(NaiveDate, NaiveDate)>,
+}
+
+/// The realtime subscription of the logged in account.
+#[derive(Default)]
+struct RealtimeLink {
+    client: Option<RealtimeClient>,
+    retry_at: Option<f64>,
+    /// Changes may have been missed while the connection was down.
+    missed_changes: bool,
+}
+
+/// What a change made on another device means for the local copy.
+#[derive(Debug, PartialEq)]
+enum RemoteChange {
+    /// The visible week has no local edits, so it takes the server version.
+    ReloadVisible,
+    /// The visible week has local edits; the user decides when to reload.
+    FlagVisible,
+    RefetchCached(WeekKey),
 }
 
 #[derive(Debug, Default)]
 struct InFlightOps {
     auth: bool,
     load_week: Option<WeekKey>,
-    save_week: Option<WeekKey>,
+    prefetch: Vec<WeekKey>,
+    save_days: bool,
+    load_history: Option<(NaiveDate, NaiveDate)>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,9 +115,9 @@ pub(crate) enum PendingAuthedOp {
         end_date: chrono::NaiveDate,
         refreshed_after_failure: bool,
     },
-    SaveWeek {
-        week: WeekKey,
+    SaveDays {
         drafts: Vec<WorkDayDraft>,
+        contract_periods: Option<Vec<ContractPeriodDraft>>,
         refreshed_after_failure: bool,
     },
 }
@@ -54,13 +125,25 @@ pub(crate) enum PendingAuthedOp {
 #[derive(Debug)]
 pub(crate) enum AsyncResult {
     Login(Result<StoredSession, Error>),
+    LoginEmailSent {
+        email: String,
+        result: Result<(), Error>,
+    },
     RefreshSession(Result<StoredSession, Error>),
     LoadWeek {
         op: PendingAuthedOp,
+        result: Result<LoadedWeek, String>,
+    },
+    PrefetchWeek {
+        week: WeekKey,
         result: Result<Vec<WorkDayDraft>, String>,
     },
-    SaveWeek {
+    SaveDays {
         op: PendingAuthedOp,
+        result: Result<SavedDays, String>,
+    },
+    LoadHistory {
+        range: (NaiveDate, NaiveDate),
         result: Result<Vec<WorkDayDraft>, String>,
     },
 }
@@ -69,12 +152,23 @@ pub(crate) enum AsyncResult {
 #[serde(default)]
 pub struct SyncState {
     pub stored_session: Option<StoredSession>,
+    /// Day changes that still have to be saved; kept across restarts.
+    outbox: Outbox,
+    #[serde(skip)]
+    weeks: HashMap<WeekKey, CachedWeek>,
+    #[serde(skip)]
+    synced_contract_periods: Option<Vec<ContractPeriodDraft>>,
     #[serde(skip)]
-    synced_week: Option<WeekSyncSnapshot>,
+    history: HistoryCache,
     #[serde(skip)]
     in_flight: InFlightOps,
     #[serde(skip)]
     pending_authed_op: Option<PendingAuthedOp>,
+    #[serde(skip)]
+    realtime: RealtimeLink,
+    /// A week that was changed on another device while it had local edits.
+    #[serde(skip)]
+    changed_elsewhere: Option<WeekKey>,
 }
 
 impl SyncState {
@@ -98,6 +192,7 @@ impl SyncState {
         }
 
         let session = self.stored_session.clone().unwrap();
+        self.adopt_outbox_user(&session.user_id, ui_state);
         info!(
             target = "sync",
             user_id = %session.user_id,
@@ -121,16 +216,12 @@ impl SyncState {
         }
     }
 
-    pub(crate) fn clear_synced_week(&mut self) {
-        self.synced_week = None;
-    }
-
     pub(crate) fn is_logged_in(&self) -> bool {
         self.stored_session.is_some()
     }
 
     pub(crate) fn is_busy(&self) -> bool {
-        self.in_flight.auth || self.in_flight.load_week.is_some() || self.in_flight.save_week.is_some()
+        self.in_flight.auth || self.in_flight.load_week.is_some()
     }
 
     pub(crate) fn session_label(&self) -> String {
@@ -150,42 +241,111 @@ impl SyncState {
     }
 
     pub(crate) fn is_week_dirty(&self, state: &State) -> bool {
-        if !self.is_logged_in() || self.in_flight.load_week.is_some() || self.in_flight.save_week.is_some() {
+        if !self.is_logged_in() || self.in_flight.load_week.is_some() || self.in_flight.save_days {
             return false;
         }
 
-        match self.current_week_drafts(state) {
-            // Compare against the last successful server snapshot rather than
-            // trying to infer dirtiness from UI events alone.
-            Ok(current) => match &self.synced_week {
-                Some(snapshot) if snapshot.week == state.current_week_key() => current != snapshot.drafts,
-                _ => true,
+        // Compare against the cached server copy of this week rather than
+        // trying to infer dirtiness from UI events alone.
+        let Some(cached) = self.weeks.get(&state.current_week_key()) else {
+            return true;
+        };
+        let server = server_week_days(state, cached.days.clone())
+            .and_then(|days| days.iter().map(WorkDayDraft::from_ui_day).collect::<Result<Vec<_>>>());
+        match (self.current_week_drafts(state), server) {
+            (Ok(current), Ok(server)) => current != server,
+            _ => false,
+        }
+    }
+
+    fn current_contract_drafts(&self, state: &State) -> Result<Vec<ContractPeriodDraft>> {
+        state.contract_periods().iter().map(ContractPeriod::to_draft).collect()
+    }
+
+    /// Contract periods are not tied to a week, so they are tracked against
+    /// their own server snapshot and saved together with the next week save.
+    pub(crate) fn is_contracts_dirty(&self, state: &State) -> bool {
+        if !self.is_logged_in() || self.in_flight.load_week.is_some() || self.in_flight.save_days {
+            return false;
+        }
+
+        match self.current_contract_drafts(state) {
+            Ok(current) => match &self.synced_contract_periods {
+                Some(snapshot) => &current != snapshot,
+                None => !current.is_empty(),
             },
             Err(_) => false,
         }
     }
 
-    pub(crate) fn can_change_week(&self, state: &State) -> bool {
+    pub(crate) fn has_unsaved_changes(&self, state: &State) -> bool {
+        !self.outbox.is_empty() || self.is_contracts_dirty(state)
+    }
+
+    /// Week changes only wait for auth; edits are already queued in the
+    /// outbox, and loads of weeks that are no longer visible just fill the
+    /// cache.
+    pub(crate) fn can_change_week(&self) -> bool {
         !self.in_flight.auth
-            && self.in_flight.load_week.is_none()
-            && self.in_flight.save_week.is_none()
-            && !(self.is_logged_in() && self.is_week_dirty(state))
     }
 
-    pub(crate) fn can_refresh_week(&self, state: &State) -> bool {
-        self.is_logged_in() && self.in_flight.load_week.is_none() && self.in_flight.save_week.is_none() && !self.is_week_dirty(state)
+    pub(crate) fn can_refresh_week(&self) -> bool {
+        self.is_logged_in() && self.in_flight.load_week.is_none()
     }
 
     pub(crate) fn config_available(&self, config: Option<&AppConfig>) -> bool {
         config.is_some()
     }
 
+    pub(crate) fn history_days(&self) -> &HashMap<NaiveDate, ui::Day> {
+        &self.history.days
+    }
+
+    pub(crate) fn is_loading_history(&self) -> bool {
+        self.in_flight.load_history.is_some()
+    }
+
     pub(crate) fn in_flight_auth(&self) -> bool {
         self.in_flight.auth
     }
 
-    pub(crate) fn in_flight_save_week(&self) -> Option<WeekKey> {
-        self.in_flight.save_week
+    pub(crate) fn is_saving(&self) -> bool {
+        self.in_flight.save_days
+    }
+
+    /// Describes what is still waiting to be saved, if anything.
+    pub(crate) fn save_status(&self, state: &State, time: f64) -> Option<String> {
+        let days = match self.outbox.len() {
+            1 => "1 day".to_string(),
+            count => format!("{count} days"),
+        };
+        if !self.is_logged_in() {
+            return (!self.outbox.is_empty()).then(|| format!("{days} not saved yet; log in to save"));
+        }
+        match self.outbox.conflicts().len() {
+            0 => {}
+            1 => return Some("1 day conflicts with another device".to_string()),
+            count => return Some(format!("{count} days conflict with another device")),
+        }
+        if self.in_flight.save_days {
+            return Some("Saving...".to_string());
+        }
+        if let Some(delay) = self.outbox.retry_in(time).filter(|_| self.has_unsaved_changes(state)) {
+            return Some(format!("Offline? Retrying in {delay:.0} s"));
+        }
+        let visible = state.current_week_key();
+        let other_weeks: Vec<String> = self
+            .outbox
+            .pending_weeks()
+            .into_iter()
+            .filter(|week| *week != visible)
+            .map(|week| week.week_nr.to_string())
+            .collect();
+        match other_weeks.as_slice() {
+            [] => self.has_unsaved_changes(state).then(|| "Unsaved changes".to_string()),
+            [week] => Some(format!("Unsaved changes in week {week}")),
+            weeks => Some(format!("Unsaved changes in weeks {}", weeks.join(", "))),
+        }
     }
 
     pub(crate) fn start_login(
@@ -222,6 +382,105 @@ impl SyncState {
         });
     }
 
+    /// Emails a login code and magic link to the address in the login form.
+    pub(crate) fn start_send_login_email(
+        &mut self,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: egui::Context,
+    ) {
+        if self.in_flight.auth {
+            debug!(target = "auth", "ignoring login email request while auth is already in flight");
+            return;
+        }
+
+        let Some(config) = self.require_config(ui_state, config, "Supabase config missing; login is unavailable.") else {
+            return;
+        };
+
+        let email = ui_state.login_email().trim().to_string();
+        if email.is_empty() {
+            ui_state.set_error_message("Enter your email address.".to_string());
+            return;
+        }
+
+        info!(target = "auth", %email, "requesting login email");
+        ui_state.clear_error();
+        self.in_flight.auth = true;
+        ui_state.set_status_message("Sending login email...".to_string());
+        let redirect_to = login_link::redirect_url();
+        let results = async_results.clone();
+        spawn_async_task(ctx, results, async move {
+            let client = supabase_client(&config);
+            let result = client.send_otp(&email, redirect_to.as_deref()).await;
+            AsyncResult::LoginEmailSent { email, result }
+        });
+    }
+
+    /// Logs in with the one-time code from the login email.
+    pub(crate) fn start_verify_login_code(
+        &mut self,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: egui::Context,
+    ) {
+        if self.in_flight.auth {
+            debug!(target = "auth", "ignoring login code while auth is already in flight");
+            return;
+        }
+
+        let Some(config) = self.require_config(ui_state, config, "Supabase config missing; login is unavailable.") else {
+            return;
+        };
+
+        let Some(email) = ui_state.login_code_sent_to().map(str::to_string) else {
+            ui_state.set_error_message("Send a login email first.".to_string());
+            return;
+        };
+        let code: String = ui_state.login_code().chars().filter(|c| !c.is_whitespace()).collect();
+        if code.is_empty() {
+            ui_state.set_error_message("Enter the code from the login email.".to_string());
+            return;
+        }
+
+        info!(target = "auth", %email, "starting login code verification");
+        ui_state.clear_error();
+        self.in_flight.auth = true;
+        ui_state.set_status_message("Logging in...".to_string());
+        let results = async_results.clone();
+        spawn_async_task(ctx, results, async move {
+            let client = supabase_client(&config);
+            AsyncResult::Login(client.verify_otp(&email, &code).await.map(StoredSession::from))
+        });
+    }
+
+    /// Completes a login from the tokens of an opened magic link. These
+    /// replace any stored session, since the user just asked to log in.
+    pub(crate) fn start_link_login(
+        &mut self,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: egui::Context,
+        redirect: AuthRedirect,
+    ) {
+        let Some(config) = self.require_config(ui_state, config, "Supabase config missing; login is unavailable.") else {
+            return;
+        };
+
+        info!(target = "auth", "completing login from magic link");
+        self.in_flight.auth = true;
+        ui_state.set_status_message("Logging in...".to_string());
+        let results = async_results.clone();
+        spawn_async_task(ctx, results, async move {
+            let client = supabase_client(&config);
+            let session = client.get_user(&redirect.access_token).await;
+            AsyncResult::Login(session.map(|user| redirect.into_stored_session(user)))
+        });
+    }
+
     fn start_refresh_session(
         &mut self,
         ui_state: &mut AppUiState,
@@ -257,12 +516,11 @@ impl SyncState {
         async_results: &AsyncResults<AsyncResult>,
         ctx: egui::Context,
     ) {
-        if !self.is_logged_in() || self.in_flight.load_week.is_some() || self.in_flight.save_week.is_some() {
+        if !self.is_logged_in() || self.in_flight.load_week == Some(state.current_week_key()) {
             debug!(
                 target = "sync",
                 is_logged_in = self.is_logged_in(),
                 has_in_flight_load = self.in_flight.load_week.is_some(),
-                has_in_flight_save = self.in_flight.save_week.is_some(),
                 "skipping week load request"
             );
             return;
@@ -291,7 +549,223 @@ impl SyncState {
         self.spawn_load_week(ui_state, async_results, ctx, config, session.access_token, op);
     }
 
-    pub(crate) fn save_visible_week(
+    /// Shows the cached server copy of the visible week right away, keeping
+    /// days that wait in the outbox. The caller revalidates it with a load.
+    pub(crate) fn show_cached_week(&mut self, state: &mut State, snapshots: &mut Snapshots) -> bool {
+        let week = state.current_week_key();
+        let Some(cached) = self.weeks.get(&week).filter(|_| self.is_logged_in()).cloned() else {
+            return false;
+        };
+        match self.replace_visible_week(state, snapshots, cached.days) {
+            Ok(_) => {
+                debug!(target = "sync", year = week.year, week = week.week_nr, "showing cached week");
+                self.outbox.rebase(state);
+                true
+            }
+            Err(err) => {
+                warn!(target = "sync", error = %err, year = week.year, week = week.week_nr, "failed to show cached week");
+                false
+            }
+        }
+    }
+
+    /// Fetches the weeks around the visible one into the cache, so flipping to
+    /// them shows data without waiting.
+    fn prefetch_adjacent_weeks(
+        &mut self,
+        state: &State,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: &egui::Context,
+    ) {
+        let time = ctx.input(|input| input.time);
+        let visible = state.current_week_key();
+        for week in [visible.offset(-1), visible.offset(1)] {
+            let fresh = self
+                .weeks
+                .get(&week)
+                .is_some_and(|cached| time - cached.fetched_at < PREFETCH_MAX_AGE_SECONDS);
+            if !fresh {
+                self.spawn_prefetch(state, week, config, async_results, ctx);
+            }
+        }
+    }
+
+    /// Fetches `week` into the cache in the background. Prefetching never
+    /// refreshes the session; the next visible load does that.
+    fn spawn_prefetch(
+        &mut self,
+        state: &State,
+        week: WeekKey,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: &egui::Context,
+    ) {
+        let (Some(config), Some(session)) = (config.cloned(), self.stored_session.clone()) else {
+            return;
+        };
+        if session.is_expired_or_near_expiry(chrono::Utc::now().timestamp()) || self.in_flight.prefetch.contains(&week) {
+            return;
+        }
+        let (start_date, end_date) = state.week_range(week);
+        debug!(target = "sync", year = week.year, week = week.week_nr, "prefetching week");
+        self.in_flight.prefetch.push(week);
+        let access_token = session.access_token;
+        spawn_async_task(ctx.clone(), async_results.clone(), async move {
+            let client = supabase_client(&config);
+            let result = client
+                .get_work_days_range(&access_token, start_date, end_date)
+                .await
+                .map(|days| days.into_iter().map(WorkDayDraft::from).collect())
+                .map_err(|err| err.to_string());
+            AsyncResult::PrefetchWeek { week, result }
+        });
+    }
+
+    /// Keeps the realtime subscription of the logged in account running and
+    /// applies the changes it reports. Runs every frame.
+    pub(crate) fn maintain_realtime(
+        &mut self,
+        state: &State,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: &egui::Context,
+        time: f64,
+    ) {
+        let (Some(app_config), Some(session)) = (config, self.stored_session.clone()) else {
+            self.realtime = RealtimeLink::default();
+            return;
+        };
+        // An expired token cannot join; the next session refresh brings one.
+        if session.is_expired_or_near_expiry(chrono::Utc::now().timestamp()) {
+            return;
+        }
+        if self
+            .realtime
+            .client
+            .as_ref()
+            .is_some_and(|client| client.user_id() != session.user_id)
+        {
+            self.realtime = RealtimeLink::default();
+        }
+        if self.realtime.client.is_none() {
+            if let Some(delay) = self.realtime.retry_at.map(|at| at - time).filter(|delay| *delay > 0.0) {
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(delay));
+                return;
+            }
+            let url = realtime_url(&app_config.supabase_url, &app_config.supabase_anon_key);
+            let repaint = ctx.clone();
+            let wake = Arc::new(move || repaint.request_repaint());
+            self.realtime.client = Some(RealtimeClient::connect(&url, &session.user_id, &session.access_token, wake));
+            self.realtime.retry_at = None;
+        }
+        let Some(client) = self.realtime.client.as_mut() else {
+            return;
+        };
+        if client.access_token() != session.access_token {
+            client.set_access_token(&session.access_token);
+        }
+        let events = client.poll(time);
+        if let Some(delay) = client.next_heartbeat_in(time) {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(delay));
+        }
+
+        for event in events {
+            match event {
+                RealtimeEvent::Joined => {
+                    info!(target = "realtime", "subscribed to day changes");
+                    if std::mem::take(&mut self.realtime.missed_changes) {
+                        let unknown = DayChange {
+                            work_date: None,
+                            updated_at: None,
+                        };
+                        self.apply_remote_change(state, ui_state, config, async_results, ctx, &unknown);
+                    }
+                }
+                RealtimeEvent::DayChanged(change) => {
+                    debug!(target = "realtime", work_date = ?change.work_date, "day changed elsewhere");
+                    self.apply_remote_change(state, ui_state, config, async_results, ctx, &change);
+                }
+                RealtimeEvent::Failed(reason) | RealtimeEvent::Closed(reason) => {
+                    warn!(target = "realtime", %reason, retry_in = REALTIME_RETRY_SECONDS, "realtime connection lost");
+                    self.realtime = RealtimeLink {
+                        client: None,
+                        retry_at: Some(time + REALTIME_RETRY_SECONDS),
+                        missed_changes: true,
+                    };
+                    ctx.request_repaint_after(std::time::Duration::from_secs_f64(REALTIME_RETRY_SECONDS));
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Decides how a day changed on another device reaches the local copy.
+    fn remote_change(&self, state: &State, change: &DayChange) -> Option<RemoteChange> {
+        let visible = state.current_week_key();
+        let week = change.work_date.map_or(visible, WeekKey::containing);
+        if let Some(date) = change.work_date {
+            // The cache already has this version, e.g. after saving it here.
+            let cached = cached_server_day(&self.weeks, date).and_then(|day| day.updated_at);
+            if change.updated_at.is_some() && cached == change.updated_at {
+                return None;
+            }
+            // Most likely the echo of the save in flight, whose response
+            // updates the cache anyway.
+            if self.in_flight.save_days && self.outbox.pending(date).is_some() {
+                return None;
+            }
+        }
+        if week != visible {
+            return self.weeks.contains_key(&week).then_some(RemoteChange::RefetchCached(week));
+        }
+        if self.outbox.pending_weeks().contains(&visible) || self.is_week_dirty(state) {
+            Some(RemoteChange::FlagVisible)
+        } else {
+            Some(RemoteChange::ReloadVisible)
+        }
+    }
+
+    fn apply_remote_change(
+        &mut self,
+        state: &State,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: &egui::Context,
+        change: &DayChange,
+    ) {
+        match self.remote_change(state, change) {
+            Some(RemoteChange::ReloadVisible) => self.request_visible_week_load(state, ui_state, config, async_results, ctx.clone()),
+            Some(RemoteChange::FlagVisible) => {
+                let week = state.current_week_key();
+                info!(
+                    target = "realtime",
+                    year = week.year,
+                    week = week.week_nr,
+                    "visible week with local edits changed elsewhere"
+                );
+                self.changed_elsewhere = Some(week);
+            }
+            Some(RemoteChange::RefetchCached(week)) => self.spawn_prefetch(state, week, config, async_results, ctx),
+            None => {}
+        }
+    }
+
+    /// Whether the visible week was changed on another device while it had
+    /// local edits, and has not been reloaded since.
+    pub(crate) fn is_changed_elsewhere(&self, state: &State) -> bool {
+        self.changed_elsewhere == Some(state.current_week_key())
+    }
+
+    pub(crate) fn dismiss_changed_elsewhere(&mut self) {
+        self.changed_elsewhere = None;
+    }
+
+    /// Fetches server days from the flex balance start through today so the
+    /// balance also covers weeks this device never loaded.
+    pub(crate) fn request_balance_history_load(
         &mut self,
         state: &State,
         ui_state: &mut AppUiState,
@@ -299,14 +773,201 @@ impl SyncState {
         async_results: &AsyncResults<AsyncResult>,
         ctx: egui::Context,
     ) {
-        if !self.is_logged_in() || self.in_flight.save_week.is_some() || self.in_flight.load_week.is_some() {
-            debug!(
-                target = "sync",
-                is_logged_in = self.is_logged_in(),
-                has_in_flight_save = self.in_flight.save_week.is_some(),
-                has_in_flight_load = self.in_flight.load_week.is_some(),
-                "skipping week save request"
-            );
+        let Some(start_date) = state.balance_start() else {
+            return;
+        };
+        let end_date = chrono::Local::now().date_naive();
+        if start_date <= end_date {
+            self.request_history_range(ui_state, config, async_results, ctx, (start_date, end_date));
+        }
+    }
+
+    /// Loads `range` into the history cache unless it is already covered.
+    ///
+    /// History loads only piggyback on a session that is known to be fresh;
+    /// they never trigger a refresh themselves, so a failed load just leaves
+    /// the cache as it was.
+    pub(crate) fn request_history_range(
+        &mut self,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: egui::Context,
+        range: (NaiveDate, NaiveDate),
+    ) {
+        if !self.is_logged_in() || self.in_flight.load_history.is_some() || self.history_covers(range) {
+            return;
+        }
+        let Some(config) = self.require_config(ui_state, config, "Supabase config missing; loading is unavailable.") else {
+            return;
+        };
+        let Some(session) = self.stored_session.clone() else {
+            return;
+        };
+        if session.is_expired_or_near_expiry(chrono::Utc::now().timestamp()) {
+            debug!(target = "sync", "skipping history load until the session is refreshed");
+            return;
+        }
+
+        let (start_date, end_date) = range;
+        info!(target = "sync", %start_date, %end_date, "requesting history range load");
+        self.in_flight.load_history = Some(range);
+        let results = async_results.clone();
+        spawn_async_task(ctx, results, async move {
+            let client = supabase_client(&config);
+            let result = client
+                .get_work_days_range(&session.access_token, start_date, end_date)
+                .await
+                .map(|days| days.into_iter().map(WorkDayDraft::from).collect())
+                .map_err(|err| err.to_string());
+            AsyncResult::LoadHistory { range, result }
+        });
+    }
+
+    pub(crate) fn history_covers(&self, (start, end): (NaiveDate, NaiveDate)) -> bool {
+        self.history
+            .loaded_ranges
+            .iter()
+            .any(|(loaded_start, loaded_end)| *loaded_start <= start && end <= *loaded_end)
+    }
+
+    fn apply_loaded_history(&mut self, range: (NaiveDate, NaiveDate), drafts: Vec<WorkDayDraft>) -> Result<()> {
+        for draft in drafts {
+            let day = draft.into_ui_day()?;
+            self.history.days.insert(day.date, day);
+        }
+        self.history.loaded_ranges.push(range);
+        self.forget_pending_history();
+        Ok(())
+    }
+
+    /// Drops the server copy of days with unsaved edits, so reports and the
+    /// balance show the local version even where it looks like a default day.
+    fn forget_pending_history(&mut self) {
+        for date in self.outbox.pending_dates() {
+            self.history.days.remove(&date);
+        }
+    }
+
+    /// Queues edits to the visible week. While logged out edits stay local,
+    /// so only the baseline follows them.
+    pub(crate) fn observe_edits(&mut self, state: &State, time: f64) {
+        if self.is_logged_in() {
+            let weeks = &self.weeks;
+            self.outbox.observe(state, time, |date| cached_server_day(weeks, date));
+            self.forget_pending_history();
+        } else {
+            self.outbox.rebase(state);
+        }
+    }
+
+    /// Queues the day a timer was started or stopped on. Edits are otherwise
+    /// only noticed in the visible week, and the timer's day can lie outside
+    /// it, e.g. while another week is shown.
+    pub(crate) fn queue_timer_day(&mut self, state: &State, date: NaiveDate, ui_state: &mut AppUiState, time: f64) {
+        if !self.is_logged_in() {
+            return;
+        }
+        if let Some(day) = state.local_day(date) {
+            self.queue_days(std::slice::from_ref(day), ui_state, time);
+        }
+    }
+
+    /// Takes the visible week as saved, e.g. after local data was reset.
+    pub(crate) fn rebase_outbox(&mut self, state: &State) {
+        self.outbox.rebase(state);
+    }
+
+    /// Queues days changed outside the visible week, e.g. by an import.
+    pub(crate) fn queue_days(&mut self, days: &[ui::Day], ui_state: &mut AppUiState, time: f64) {
+        match days.iter().map(WorkDayDraft::from_ui_day).collect::<Result<Vec<_>>>() {
+            Ok(drafts) => {
+                let weeks = &self.weeks;
+                self.outbox.queue(drafts, time, |date| cached_server_day(weeks, date));
+                self.forget_pending_history();
+            }
+            Err(err) => {
+                warn!(target = "sync", error = %err, "failed to queue days for saving");
+                ui_state.set_error_message(err.to_string());
+            }
+        }
+    }
+
+    /// Queues the days a restore from a snapshot or backup changed, so the
+    /// server does not keep data the user rolled back.
+    pub(crate) fn queue_restored_days(&mut self, before: &State, restored: &State, ui_state: &mut AppUiState, time: f64) {
+        if self.is_logged_in() {
+            let days = restored_days(before, restored, &self.history.days);
+            if !days.is_empty() {
+                info!(target = "sync", day_count = days.len(), "queueing restored days");
+                self.queue_days(&days, ui_state, time);
+            }
+        }
+        self.outbox.rebase(restored);
+    }
+
+    pub(crate) fn conflicts(&self) -> &BTreeMap<NaiveDate, DayConflict> {
+        self.outbox.conflicts()
+    }
+
+    /// Queues the user's merge of a conflicting day and shows it if its week
+    /// is visible.
+    pub(crate) fn resolve_conflict(&mut self, state: &mut State, merge: &ConflictMerge, time: f64) -> Result<()> {
+        let Some(conflict) = self.outbox.conflicts().get(&merge.date).cloned() else {
+            return Ok(());
+        };
+        let merged = merge.merged(&conflict);
+        let day = merged.clone().into_ui_day()?;
+        info!(target = "sync", date = %merge.date, entry_count = merged.work_entries.len(), "resolved save conflict");
+        if let Some(server) = self.outbox.resolve(merged, time) {
+            self.apply_saved_days(vec![server])?;
+        }
+        state.merge_days(vec![day]);
+        self.outbox.rebase(state);
+        Ok(())
+    }
+
+    /// Skips the remaining save delay or retry wait and flushes right away.
+    pub(crate) fn save_now(
+        &mut self,
+        state: &State,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: egui::Context,
+    ) {
+        self.outbox.retry_now();
+        let time = ctx.input(|input| input.time);
+        self.flush_outbox(state, ui_state, config, async_results, ctx, time);
+    }
+
+    /// Saves the queued days, together with edited contract periods, once the
+    /// outbox is due. Runs every frame; schedules a repaint for the next due
+    /// time while changes are waiting.
+    pub(crate) fn flush_outbox(
+        &mut self,
+        state: &State,
+        ui_state: &mut AppUiState,
+        config: Option<&AppConfig>,
+        async_results: &AsyncResults<AsyncResult>,
+        ctx: egui::Context,
+        time: f64,
+    ) {
+        if !self.is_logged_in() || self.in_flight.auth || self.in_flight.save_days || self.pending_authed_op.is_some() {
+            return;
+        }
+        // Before the first load there is nothing to compare local contract
+        // periods with, so they wait for it.
+        let save_contracts = self.synced_contract_periods.is_some() && self.is_contracts_dirty(state);
+        let due = if save_contracts {
+            self.outbox.is_ready(time)
+        } else {
+            self.outbox.is_due(time)
+        };
+        if !due {
+            if let Some(delay) = self.outbox.next_flush_in(time, save_contracts) {
+                ctx.request_repaint_after(std::time::Duration::from_secs_f64(delay));
+            }
             return;
         }
 
@@ -316,19 +977,22 @@ impl SyncState {
         let Some(session) = self.stored_session.clone() else {
             return;
         };
-
-        let week = state.current_week_key();
-        let drafts = match self.current_week_drafts(state) {
-            Ok(drafts) => drafts,
-            Err(err) => {
-                warn!(target = "sync", error = %err, year = week.year, week = week.week_nr, "failed to build save drafts");
-                ui_state.set_error_message(err.to_string());
-                return;
+        let contract_periods = if save_contracts {
+            match self.current_contract_drafts(state) {
+                Ok(periods) => Some(periods),
+                Err(err) => {
+                    warn!(target = "sync", error = %err, "failed to build contract period drafts");
+                    ui_state.set_error_message(err.to_string());
+                    self.outbox.record_failure(time);
+                    return;
+                }
             }
+        } else {
+            None
         };
-        let op = PendingAuthedOp::SaveWeek {
-            week,
-            drafts,
+        let op = PendingAuthedOp::SaveDays {
+            drafts: self.outbox.batch(),
+            contract_periods,
             refreshed_after_failure: false,
         };
         if session.is_expired_or_near_expiry(chrono::Utc::now().timestamp()) {
@@ -337,51 +1001,56 @@ impl SyncState {
             return;
         }
 
-        self.spawn_save_week(ui_state, async_results, ctx, config, session.access_token, op);
+        self.spawn_save_days(async_results, ctx, config, session.access_token, op);
     }
 
-    fn spawn_load_week(
+    fn spawn_save_days(
         &mut self,
-        ui_state: &mut AppUiState,
         async_results: &AsyncResults<AsyncResult>,
         ctx: egui::Context,
         config: AppConfig,
         access_token: String,
         op: PendingAuthedOp,
     ) {
-        let PendingAuthedOp::LoadWeek {
-            week,
-            start_date,
-            end_date,
-            ..
+        let PendingAuthedOp::SaveDays {
+            drafts, contract_periods, ..
         } = op.clone()
         else {
             return;
         };
+        let total_entries: usize = drafts.iter().map(|draft| draft.work_entries.len()).sum();
         info!(
             target = "sync",
-            year = week.year,
-            week = week.week_nr,
-            %start_date,
-            %end_date,
-            "requesting visible week load"
+            day_count = drafts.len(),
+            total_entries,
+            saves_contract_periods = contract_periods.is_some(),
+            "flushing outbox"
         );
-        ui_state.clear_error();
-        self.in_flight.load_week = Some(week);
-        ui_state.set_status_message(format!("Loading week {}...", week.week_nr));
+        self.in_flight.save_days = true;
         let results = async_results.clone();
         spawn_async_task(ctx, results, async move {
             let client = supabase_client(&config);
-            let result = client
-                .get_work_days_range(&access_token, start_date, end_date)
-                .await
-                .map(|days| days.into_iter().map(WorkDayDraft::from).collect())
-                .map_err(|err| err.to_string());
-            AsyncResult::LoadWeek { op, result }
+            let result = save_days(&client, &access_token, drafts, contract_periods).await;
+            AsyncResult::SaveDays { op, result }
         });
     }
 
-    fn spawn_save_week(
+    /// Records saved days: the history cache gets the server version, and
+    /// cached weeks count them as synced.
+    fn apply_saved_days(&mut self, saved: Vec<WorkDayDraft>) -> Result<()> {
+        for draft in saved {
+            if let Some(cached) = self.weeks.get_mut(&WeekKey::containing(draft.work_date)) {
+                cached.days.retain(|day| day.work_date != draft.work_date);
+                cached.days.push(draft.clone());
+                cached.days.sort_by_key(|day| day.work_date);
+            }
+            let day = draft.into_ui_day()?;
+            self.history.days.insert(day.date, day);
+        }
+        Ok(())
+    }
+
+    fn spawn_load_week(
         &mut self,
         ui_state: &mut AppUiState,
         async_results: &AsyncResults<AsyncResult>,
@@ -390,28 +1059,33 @@ impl SyncState {
         access_token: String,
         op: PendingAuthedOp,
     ) {
-        let PendingAuthedOp::SaveWeek { week, drafts, .. } = op.clone() else {
+        let PendingAuthedOp::LoadWeek {
+            week,
+            start_date,
+            end_date,
+            ..
+        } = op.clone()
+        else {
             return;
         };
-        let total_entries: usize = drafts.iter().map(|draft| draft.work_entries.len()).sum();
         info!(
             target = "sync",
             year = week.year,
             week = week.week_nr,
-            day_count = drafts.len(),
-            total_entries,
-            "saving visible week"
+            %start_date,
+            %end_date,
+            "requesting visible week load"
         );
         ui_state.clear_error();
-        self.in_flight.save_week = Some(week);
-        ui_state.set_status_message(format!("Saving week {}...", week.week_nr));
+        self.in_flight.load_week = Some(week);
+        ui_state.set_status_message(format!("Loading week {}...", week.week_nr));
         let results = async_results.clone();
         spawn_async_task(ctx, results, async move {
             let client = supabase_client(&config);
-            let result = save_week_drafts(&client, &access_token, drafts)
+            let result = load_week(&client, &access_token, start_date, end_date)
                 .await
                 .map_err(|err| err.to_string());
-            AsyncResult::SaveWeek { op, result }
+            AsyncResult::LoadWeek { op, result }
         });
     }
 
@@ -422,40 +1096,95 @@ impl SyncState {
             "logging out and clearing stored session"
         );
         self.stored_session = None;
-        self.synced_week = None;
+        self.weeks.clear();
+        self.synced_contract_periods = None;
+        self.history = HistoryCache::default();
+        self.realtime = RealtimeLink::default();
+        self.changed_elsewhere = None;
         ui_state.clear_login_password();
         ui_state.set_status_message("Logged out.".to_string());
         ui_state.clear_error();
         ui_state.set_show_login_window(false);
     }
 
+    /// Adopts the server's contract periods unless the local list has edits
+    /// that were not saved yet. A server without any periods never wipes a
+    /// local list, so periods created before the first login get uploaded.
+    fn apply_loaded_contract_periods(&mut self, state: &mut State, drafts: Vec<ContractPeriodDraft>) -> Result<()> {
+        let keep_local = match &self.synced_contract_periods {
+            Some(_) => self.is_contracts_dirty(state),
+            None => drafts.is_empty(),
+        };
+        if !keep_local {
+            let periods = drafts.iter().cloned().map(ContractPeriod::from_draft).collect::<Result<Vec<_>>>()?;
+            state.set_contract_periods(periods);
+        }
+        debug!(
+            target = "sync",
+            server_period_count = drafts.len(),
+            keep_local,
+            "applied loaded contract periods"
+        );
+        self.synced_contract_periods = Some(drafts);
+        Ok(())
+    }
+
+    fn apply_loaded_week(&mut self, state: &mut State, snapshots: &mut Snapshots, loaded: LoadedWeek) -> Result<Vec<WorkDayDraft>> {
+        self.apply_loaded_contract_periods(state, loaded.contract_periods)?;
+        self.replace_visible_week(state, snapshots, loaded.days)
+    }
+
+    /// Applies server days to the visible week. The local week is kept as a
+    /// snapshot when the server copy replaces recorded entries with something
+    /// different.
+    fn replace_visible_week(
+        &mut self,
+        state: &mut State,
+        snapshots: &mut Snapshots,
+        drafts: Vec<WorkDayDraft>,
+    ) -> Result<Vec<WorkDayDraft>> {
+        let previous = state.days().iter().any(|day| !day.durations.is_empty()).then(|| state.clone());
+        let server_drafts = self.apply_loaded_drafts(state, drafts)?;
+        if let Some(previous) = previous.filter(|previous| self.current_week_drafts(previous).ok() != self.current_week_drafts(state).ok())
+        {
+            let week = state.current_week_key();
+            let reason = format!("Before loading week {} of {} from the server", week.week_nr, week.year);
+            snapshots.take_unpersisted_days(reason, previous.days().to_vec(), ui::duration::local_now());
+        }
+        Ok(server_drafts)
+    }
+
+    /// Replaces the visible week with the server days, keeping days that still
+    /// wait in the outbox. Returns the server version of the week.
     fn apply_loaded_drafts(&mut self, state: &mut State, drafts: Vec<WorkDayDraft>) -> Result<Vec<WorkDayDraft>> {
         debug!(target = "sync", server_day_count = drafts.len(), "applying loaded week drafts");
-        let mut by_date = HashMap::new();
-        for draft in drafts {
-            by_date.insert(draft.work_date, draft);
-        }
+        let mut days = server_week_days(state, drafts)?;
+        let server_drafts = days.iter().map(WorkDayDraft::from_ui_day).collect::<Result<Vec<_>>>()?;
 
-        let mut days = Vec::with_capacity(5);
-        for date in state.current_week_dates() {
-            if let Some(draft) = by_date.remove(&date) {
-                days.push(draft.into_ui_day()?);
-            } else {
-                // Missing server rows mean "no data for this date", not "drop
-                // the date from the visible work week".
-                days.push(default_day_for_date(date));
+        // Days still waiting in the outbox are newer than the server copy.
+        for day in &mut days {
+            if let Some(pending) = self.outbox.pending(day.date) {
+                *day = pending.clone().into_ui_day()?;
             }
         }
-
         state.replace_current_week_days(days);
-        self.current_week_drafts(state)
+        Ok(server_drafts)
+    }
+
+    fn adopt_outbox_user(&mut self, user_id: &str, ui_state: &mut AppUiState) {
+        let dropped = self.outbox.adopt_user(user_id);
+        if dropped > 0 {
+            warn!(target = "sync", dropped, "dropped unsaved days queued for another account");
+            ui_state.set_error_message(format!("Dropped {dropped} unsaved days that belonged to another account."));
+        }
     }
 
     pub(crate) fn process_async_results(&mut self, results: Vec<AsyncResult>, runtime: ProcessAsyncContext<'_>) {
         let ProcessAsyncContext {
             state,
-            undoer,
+            undo_log,
             ui_state,
+            snapshots,
             config,
             async_results,
             ctx,
@@ -467,6 +1196,7 @@ impl SyncState {
                     ui_state.clear_login_password();
                     match result {
                         Ok(session) => {
+                            ui_state.set_login_code_sent_to(None);
                             info!(
                                 target = "auth",
                                 user_id = %session.user_id,
@@ -476,11 +1206,13 @@ impl SyncState {
                             if let Some(email) = &session.email {
                                 ui_state.set_login_email(email.clone());
                             }
-                            self.stored_session = Some(session);
-                            ui_state.set_status_message(format!("Logged in as {}", self.session_label()));
+                            ui_state.set_status_message(format!("Logged in as {}", session.email.as_deref().unwrap_or(&session.user_id)));
                             ui_state.clear_error();
+                            self.adopt_outbox_user(&session.user_id, ui_state);
+                            self.stored_session = Some(session);
                             ui_state.set_show_login_window(false);
-                            self.synced_week = None;
+                            self.weeks.clear();
+                            self.history = HistoryCache::default();
                             self.request_visible_week_load(state, ui_state, config, async_results, ctx.clone());
                         }
                         Err(err) => {
@@ -490,6 +1222,22 @@ impl SyncState {
                         }
                     }
                 }
+                AsyncResult::LoginEmailSent { email, result } => {
+                    self.in_flight.auth = false;
+                    match result {
+                        Ok(()) => {
+                            info!(target = "auth", %email, "login email sent");
+                            ui_state.set_status_message(format!("Login email sent to {email}."));
+                            ui_state.clear_error();
+                            ui_state.set_login_code_sent_to(Some(email));
+                        }
+                        Err(err) => {
+                            warn!(target = "auth", error = %err, "sending login email failed");
+                            ui_state.set_error_message(format!("Sending login email failed: {}", describe_auth_error(&err)));
+                            ui_state.set_status_message("Login failed.".to_string());
+                        }
+                    }
+                }
                 AsyncResult::RefreshSession(result) => {
                     self.in_flight.auth = false;
                     match result {
@@ -515,47 +1263,148 @@ impl SyncState {
                         Err(err) => {
                             warn!(target = "auth", error = %err, "session refresh failed");
                             self.stored_session = None;
-                            self.synced_week = None;
+                            self.weeks.clear();
+                            self.synced_contract_periods = None;
+                            self.history = HistoryCache::default();
                             self.pending_authed_op = None;
                             ui_state.set_error_message(format!("Session refresh failed: {}", describe_auth_error(&err)));
                             ui_state.set_status_message("Session expired. Please log in again.".to_string());
                         }
                     }
                 }
+                AsyncResult::SaveDays { op, result } => {
+                    self.in_flight.save_days = false;
+                    if !self.is_logged_in() {
+                        continue;
+                    }
+                    let time = ctx.input(|input| input.time);
+                    match result {
+                        Ok(SavedDays {
+                            saved,
+                            failed,
+                            conflicts,
+                            contract_periods,
+                        }) => {
+                            // Failed contract periods stay dirty and go out
+                            // again with the next flush.
+                            let contract_error = match contract_periods {
+                                Some(Ok(periods)) => {
+                                    self.synced_contract_periods = Some(periods);
+                                    None
+                                }
+                                Some(Err(err)) => {
+                                    warn!(target = "sync", error = %err, "failed to save contract periods");
+                                    Some(err)
+                                }
+                                None => None,
+                            };
+                            if let PendingAuthedOp::SaveDays { drafts, .. } = &op {
+                                for sent in drafts {
+                                    if let Some(saved) = saved.iter().find(|day| day.work_date == sent.work_date) {
+                                        self.outbox.confirm(sent, saved);
+                                    } else if let Some(server) = conflicts.iter().find(|day| day.work_date == sent.work_date) {
+                                        self.outbox.set_conflict(sent, server.clone());
+                                    }
+                                }
+                            }
+                            let (saved_count, conflict_count) = (saved.len(), conflicts.len());
+                            // Rejected days are refreshed to the server version
+                            // as well, so the cache reflects what is stored.
+                            if let Err(err) = self.apply_saved_days(saved.into_iter().chain(conflicts).collect()) {
+                                warn!(target = "sync", error = %err, "failed to apply saved days");
+                            }
+                            info!(
+                                target = "sync",
+                                saved_count,
+                                failed_count = failed.len(),
+                                conflict_count,
+                                contract_periods_failed = contract_error.is_some(),
+                                "outbox flush finished"
+                            );
+                            if conflict_count > 0 {
+                                ui_state.set_error_message(format!(
+                                    "{conflict_count} days were changed on another device. Merge them in the Conflicts window."
+                                ));
+                                ui_state.set_show_conflicts_window(true);
+                            }
+                            if failed.is_empty() && contract_error.is_none() {
+                                self.outbox.record_success();
+                                if self.outbox.is_empty() && conflict_count == 0 {
+                                    ui_state.set_status_message("All changes saved.".to_string());
+                                }
+                            } else {
+                                let delay = self.outbox.record_failure(time);
+                                let what = match (failed.len(), contract_error.is_some()) {
+                                    (0, _) => "contract periods".to_string(),
+                                    (days, false) => format!("{days} days"),
+                                    (days, true) => format!("{days} days and contract periods"),
+                                };
+                                let mut failures: Vec<_> = failed.iter().map(|(date, err)| format!("{date}: {err}")).collect();
+                                failures.extend(contract_error.map(|err| format!("contract periods: {err}")));
+                                ui_state
+                                    .set_error_message(format!("Failed to save {what}, retrying in {delay:.0} s: {}", failures.join("; ")));
+                            }
+                        }
+                        Err(err) => {
+                            if self.retry_authed_op_after_failure(ui_state, config, async_results, ctx.clone(), &op, &err) {
+                                continue;
+                            }
+                            let delay = self.outbox.record_failure(time);
+                            warn!(target = "sync", error = %err, delay, "outbox flush failed");
+                            ui_state.set_error_message(format!(
+                                "Failed to save changes, retrying in {delay:.0} s: {}",
+                                summarize_save_error(&err)
+                            ));
+                        }
+                    }
+                }
                 AsyncResult::LoadWeek { op, result } => {
-                    let week = op.week();
+                    let Some(week) = op.week() else {
+                        continue;
+                    };
                     if self.in_flight.load_week == Some(week) {
                         self.in_flight.load_week = None;
                     }
+                    let time = ctx.input(|input| input.time);
+                    // Queue pending edits against the cache as it was before
+                    // the load replaces it, so they keep their base.
+                    let weeks = &self.weeks;
+                    self.outbox.observe(state, time, |date| cached_server_day(weeks, date));
+                    if let Ok(loaded) = &result {
+                        let days = loaded.days.clone();
+                        self.weeks.insert(week, CachedWeek { days, fetched_at: time });
+                    }
                     if state.current_week_key() != week {
                         // The user navigated away while the request was in
-                        // flight, so ignore the stale payload.
+                        // flight; the payload only refreshes the cache.
                         debug!(
                             target = "sync",
                             year = week.year,
                             week = week.week_nr,
-                            "ignoring stale loaded week result"
+                            "cached loaded week that is no longer visible"
                         );
                         continue;
                     }
 
                     match result {
-                        Ok(drafts) => match self.apply_loaded_drafts(state, drafts) {
-                            Ok(snapshot_drafts) => {
+                        Ok(loaded) => match self.apply_loaded_week(state, snapshots, loaded) {
+                            Ok(server_drafts) => {
                                 info!(
                                     target = "sync",
                                     year = week.year,
                                     week = week.week_nr,
-                                    day_count = snapshot_drafts.len(),
+                                    day_count = server_drafts.len(),
                                     "loaded visible week successfully"
                                 );
-                                self.synced_week = Some(WeekSyncSnapshot {
-                                    week,
-                                    drafts: snapshot_drafts,
-                                });
-                                *undoer = Default::default();
+                                undo_log.rebase(state);
+                                self.outbox.rebase(state);
+                                if self.changed_elsewhere == Some(week) {
+                                    self.changed_elsewhere = None;
+                                }
                                 ui_state.set_status_message(format!("Loaded week {}", week.week_nr));
                                 ui_state.clear_error();
+                                self.request_balance_history_load(state, ui_state, config, async_results, ctx.clone());
+                                self.prefetch_adjacent_weeks(state, config, async_results, ctx);
                             }
                             Err(err) => {
                                 warn!(target = "sync", error = %err, year = week.year, week = week.week_nr, "failed to apply loaded week");
@@ -572,55 +1421,50 @@ impl SyncState {
                         }
                     }
                 }
-                AsyncResult::SaveWeek { op, result } => {
-                    let week = op.week();
-                    if self.in_flight.save_week == Some(week) {
-                        self.in_flight.save_week = None;
-                    }
-                    if state.current_week_key() != week {
-                        debug!(
-                            target = "sync",
-                            year = week.year,
-                            week = week.week_nr,
-                            "ignoring stale saved week result"
-                        );
+                AsyncResult::PrefetchWeek { week, result } => {
+                    self.in_flight.prefetch.retain(|pending| *pending != week);
+                    if !self.is_logged_in() {
                         continue;
                     }
-
                     match result {
-                        Ok(saved_drafts) => {
-                            let snapshot_drafts = if saved_drafts.is_empty() {
-                                match self.current_week_drafts(state) {
-                                    Ok(drafts) => drafts,
-                                    Err(err) => {
-                                        warn!(target = "sync", error = %err, year = week.year, week = week.week_nr, "save succeeded but snapshot rebuild failed");
-                                        ui_state.set_error_message(format!("Saved, but local snapshot failed: {err}"));
-                                        continue;
-                                    }
-                                }
-                            } else {
-                                saved_drafts
-                            };
-                            self.synced_week = Some(WeekSyncSnapshot {
-                                week,
-                                drafts: snapshot_drafts,
-                            });
-                            info!(
+                        Ok(days) => {
+                            debug!(
                                 target = "sync",
                                 year = week.year,
                                 week = week.week_nr,
-                                "saved visible week successfully"
+                                day_count = days.len(),
+                                "prefetched week"
                             );
-                            ui_state.set_status_message(format!("Saved week {}", week.week_nr));
-                            ui_state.clear_error();
+                            let fetched_at = ctx.input(|input| input.time);
+                            self.weeks.insert(week, CachedWeek { days, fetched_at });
+                        }
+                        // The visible load reports errors; a failed prefetch
+                        // only means the week loads when it is opened.
+                        Err(err) => debug!(target = "sync", error = %err, year = week.year, week = week.week_nr, "failed to prefetch week"),
+                    }
+                }
+                AsyncResult::LoadHistory { range, result } => {
+                    if self.in_flight.load_history == Some(range) {
+                        self.in_flight.load_history = None;
+                    }
+                    if !self.is_logged_in() {
+                        debug!(target = "sync", "ignoring history result after logout");
+                        continue;
+                    }
+
+                    match result
+                        .map_err(Error::msg)
+                        .and_then(|drafts| self.apply_loaded_history(range, drafts))
+                    {
+                        Ok(()) => {
+                            info!(target = "sync", start_date = %range.0, end_date = %range.1, "loaded history range");
+                            // The balance start may have moved while this load was
+                            // in flight; this is a no-op once the range is covered.
+                            self.request_balance_history_load(state, ui_state, config, async_results, ctx.clone());
                         }
                         Err(err) => {
-                            if self.retry_authed_op_after_failure(ui_state, config, async_results, ctx.clone(), &op, &err) {
-                                continue;
-                            }
-                            warn!(target = "sync", error = %err, year = week.year, week = week.week_nr, "failed to save visible week");
-                            ui_state.set_error_message(format!("Failed to save week: {}", summarize_save_error(&err)));
-                            ui_state.set_status_message("Week save failed.".to_string());
+                            warn!(target = "sync", error = %err, start_date = %range.0, end_date = %range.1, "failed to load history range");
+                            ui_state.set_error_message(format!("Failed to load history: {err}"));
                         }
                     }
                 }
@@ -658,8 +1502,8 @@ impl SyncState {
             PendingAuthedOp::LoadWeek { .. } => {
                 self.spawn_load_week(ui_state, async_results, ctx, config, session.access_token, op);
             }
-            PendingAuthedOp::SaveWeek { .. } => {
-                self.spawn_save_week(ui_state, async_results, ctx, config, session.access_token, op);
+            PendingAuthedOp::SaveDays { .. } => {
+                self.spawn_save_days(async_results, ctx, config, session.access_token, op);
             }
         }
     }
@@ -691,9 +1535,11 @@ impl SyncState {
 }
 
 impl PendingAuthedOp {
-    fn week(&self) -> WeekKey {
+    /// The week a load belongs to; saved days can span many weeks.
+    fn week(&self) -> Option<WeekKey> {
         match self {
-            Self::LoadWeek { week, .. } | Self::SaveWeek { week, .. } => *week,
+            Self::LoadWeek { week, .. } => Some(*week),
+            Self::SaveDays { .. } => None,
         }
     }
 
@@ -703,7 +1549,7 @@ impl PendingAuthedOp {
                 refreshed_after_failure,
                 ..
             }
-            | Self::SaveWeek {
+            | Self::SaveDays {
                 refreshed_after_failure,
                 ..
             } => *refreshed_after_failure,
@@ -716,7 +1562,7 @@ impl PendingAuthedOp {
                 refreshed_after_failure,
                 ..
             }
-            | Self::SaveWeek {
+            | Self::SaveDays {
                 refreshed_after_failure,
                 ..
             } => *refreshed_after_failure = true,
@@ -725,16 +1571,60 @@ impl PendingAuthedOp {
     }
 }
 
-fn default_day_for_date(date: chrono::NaiveDate) -> ui::Day {
-    let mut day = ui::Day::new(date.format("%A").to_string());
-    day.date = date;
-    day
+/// The server version of `date` if its week is cached. A cached week without
+/// a row for `date` means the server has no such day yet.
+fn cached_server_day(weeks: &HashMap<WeekKey, CachedWeek>, date: NaiveDate) -> Option<WorkDayDraft> {
+    weeks
+        .get(&WeekKey::containing(date))?
+        .days
+        .iter()
+        .find(|day| day.work_date == date)
+        .cloned()
+}
+
+/// The visible week's days as the server has them: server rows where they
+/// exist and default days for the other shown dates.
+fn server_week_days(state: &State, drafts: Vec<WorkDayDraft>) -> Result<Vec<ui::Day>> {
+    let mut by_date = HashMap::new();
+    for draft in drafts {
+        by_date.insert(draft.work_date, draft);
+    }
+
+    let dates = state.current_week_dates();
+    let mut days = Vec::with_capacity(dates.len());
+    for date in dates {
+        if let Some(draft) = by_date.remove(&date) {
+            days.push(draft.into_ui_day()?);
+        } else {
+            // Missing server rows mean "no data for this date", not "drop
+            // the date from the visible work week".
+            days.push(state.default_day_for_date(date));
+        }
+    }
+    Ok(days)
 }
 
 fn supabase_client(config: &AppConfig) -> SupabaseClient {
     SupabaseClient::new(config.supabase_url.clone(), config.supabase_anon_key.clone())
 }
 
+/// Days whose restored version differs from what was shown before. An
+/// untouched default day only counts when it undoes a local edit, otherwise
+/// restoring it would wipe the server's copy of a day this device never had.
+fn restored_days(before: &State, restored: &State, history: &HashMap<NaiveDate, ui::Day>) -> Vec<ui::Day> {
+    let draft = |day: &ui::Day| WorkDayDraft::from_ui_day(day).ok();
+    restored
+        .stored_days()
+        .into_iter()
+        .filter(|day| !restored.is_untouched(day) || before.has_local_edit(day.date))
+        .filter(|day| {
+            before
+                .recorded_day(day.date, history)
+                .map_or(true, |previous| draft(previous) != draft(day))
+        })
+        .collect()
+}
+
 fn describe_auth_error(err: &Error) -> String {
     let message = err.to_string();
     if message.contains("failed before response") {
@@ -756,58 +1646,120 @@ fn is_auth_error(error: &str) -> bool {
         || error.contains("not authenticated")
 }
 
-async fn save_week_drafts(client: &SupabaseClient, access_token: &str, drafts: Vec<WorkDayDraft>) -> Result<Vec<WorkDayDraft>> {
-    let mut saved = Vec::with_capacity(drafts.len());
-    for draft in drafts {
+async fn load_week(
+    client: &SupabaseClient,
+    access_token: &str,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+) -> Result<LoadedWeek> {
+    let days = client
+        .get_work_days_range(access_token, start_date, end_date)
+        .await?
+        .into_iter()
+        .map(WorkDayDraft::from)
+        .collect();
+    let contract_periods = client
+        .get_contract_periods(access_token)
+        .await?
+        .into_iter()
+        .map(ContractPeriodDraft::from)
+        .collect();
+    Ok(LoadedWeek { days, contract_periods })
+}
+
+/// Saves the drafts one ISO week at a time, then the contract periods, whose
+/// outcome is reported on its own. Each week is a single RPC call that stores
+/// all of its days or none of them. An auth error fails the whole flush so it
+/// can be retried after a session refresh. When the server rejects a week, the
+/// days it names are reported as failed or conflicting and the week's other
+/// days stay queued, since none of them were stored; any other error fails
+/// every day of the week.
+async fn save_days(
+    client: &SupabaseClient,
+    access_token: &str,
+    drafts: Vec<WorkDayDraft>,
+    contract_periods: Option<Vec<ContractPeriodDraft>>,
+) -> Result<SavedDays, String> {
+    let mut result = SavedDays {
+        saved: Vec::with_capacity(drafts.len()),
+        failed: vec![],
+        conflicts: vec![],
+        contract_periods: None,
+    };
+    for week in drafts.chunk_by(|a, b| WeekKey::containing(a.work_date) == WeekKey::containing(b.work_date)) {
         debug!(
             target = "sync",
-            work_date = %draft.work_date,
-            entry_count = draft.work_entries.len(),
-            enabled = draft.enabled,
-            target_minutes = draft.target_minutes,
-            "saving work day draft"
+            first_date = %week[0].work_date,
+            day_count = week.len(),
+            entry_count = week.iter().map(|draft| draft.work_entries.len()).sum::<usize>(),
+            "saving week drafts"
         );
-        let saved_day = client
-            .save_work_day(access_token, &draft)
-            .await
-            .with_context(|| format!("failed to save {}", draft.work_date))?;
-        saved.push(WorkDayDraft::from(saved_day));
-    }
-    saved.sort_by_key(|draft| draft.work_date);
-    Ok(saved)
+        match client.save_work_week(access_token, week).await {
+            Ok(saved_days) => result.saved.extend(saved_days.into_iter().map(WorkDayDraft::from)),
+            Err(err) if is_auth_error(&err.to_string()) => return Err(err.to_string()),
+            Err(err) => match err.downcast::<WorkWeekRejected>() {
+                Ok(rejected) => {
+                    for day in rejected.days {
+                        match day.conflict {
+                            Some(server) => result.conflicts.push(server),
+                            None => result.failed.push((day.work_date, day.reason)),
+                        }
+                    }
+                }
+                Err(err) => result.failed.extend(week.iter().map(|draft| (draft.work_date, err.to_string()))),
+            },
+        }
+    }
+    if let Some(periods) = contract_periods {
+        let saved = client.save_contract_periods(access_token, &periods).await;
+        result.contract_periods = Some(
+            saved
+                .map(|saved| saved.into_iter().map(ContractPeriodDraft::from).collect())
+                .map_err(|err| format!("{err:#}")),
+        );
+    }
+    Ok(result)
 }
 
 fn summarize_save_error(error: &str) -> String {
-    if error.contains("save_work_day_with_entries") && error.contains("function") {
+    if (error.contains("save_work_week_with_entries") || error.contains("save_work_day_with_entries")) && error.contains("function") {
         return "database save RPC is missing or mismatched".to_string();
     }
-    if let Some(date) = extract_failed_work_date(error) {
-        if error.contains("Supabase request failed with status") {
-            return format!("server rejected {}", date);
-        }
-        if error.contains("local date/time") || error.contains("minute precision") {
-            return format!("could not convert local time for {}", date);
-        }
-    }
     error.to_string()
 }
 
-fn extract_failed_work_date(error: &str) -> Option<&str> {
-    error
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .windows(2)
-        .find_map(|window| if window[0] == "save" { Some(window[1]) } else { None })
-}
-
 #[cfg(test)]
 mod tests {
-    use super::{is_auth_error, AsyncResult, PendingAuthedOp, SyncState, WeekSyncSnapshot};
+    use super::{
+        is_auth_error, summarize_save_error, AsyncResult, CachedWeek, LoadedWeek, PendingAuthedOp, ProcessAsyncContext, RemoteChange,
+        SavedDays, SyncState,
+    };
+    use crate::app::contract::{ContractPeriod, ContractProfile};
+    use crate::app::schedule::WeekSchedule;
+    use crate::app::snapshots::Snapshots;
     use crate::app::state::{State, WeekKey};
+    use crate::app::tasks::new_async_results;
+    use crate::app::ui_state::AppUiState;
+    use crate::realtime::DayChange;
     use crate::supabase::WorkDayDraft;
-    use chrono::NaiveDate;
+    use crate::ui;
+    use chrono::{Datelike, NaiveDate, TimeZone, Utc, Weekday};
+
+    fn logged_in_sync() -> SyncState {
+        SyncState {
+            stored_session: Some(crate::supabase::StoredSession {
+                access_token: "a".to_string(),
+                refresh_token: "r".to_string(),
+                expires_at: None,
+                user_id: "u".to_string(),
+                email: None,
+            }),
+            ..Default::default()
+        }
+    }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn dirty_state_is_false_when_snapshot_matches() {
         let state = State::default();
         let mut sync = SyncState::default();
@@ -824,16 +1776,20 @@ mod tests {
             .map(WorkDayDraft::from_ui_day)
             .collect::<Result<_, _>>()
             .unwrap();
-        sync.synced_week = Some(WeekSyncSnapshot {
-            week: state.current_week_key(),
-            drafts,
-        });
+        sync.weeks.insert(
+            state.current_week_key(),
+            CachedWeek {
+                days: drafts,
+                fetched_at: 0.0,
+            },
+        );
 
         assert!(!sync.is_week_dirty(&state));
         let _ = AsyncResult::Login(Ok(sync.stored_session.clone().unwrap()));
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn dirty_state_is_true_when_snapshot_week_differs() {
         let state = State::default();
         let mut sync = SyncState::default();
@@ -844,17 +1800,382 @@ mod tests {
             user_id: "u".to_string(),
             email: None,
         });
-        sync.synced_week = Some(WeekSyncSnapshot {
-            week: WeekKey { year: 2020, week_nr: 1 },
-            drafts: vec![WorkDayDraft {
-                work_date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
-                target_minutes: 1,
-                enabled: true,
-                work_entries: vec![],
+        sync.weeks.insert(
+            WeekKey { year: 2020, week_nr: 1 },
+            CachedWeek {
+                days: vec![WorkDayDraft {
+                    work_date: NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+                    target_minutes: 1,
+                    enabled: true,
+                    work_entries: vec![],
+                    updated_at: None,
+                }],
+                fetched_at: 0.0,
+            },
+        );
+
+        assert!(sync.is_week_dirty(&state));
+    }
+
+    fn weekend_schedule() -> WeekSchedule {
+        let mut schedule = WeekSchedule::default();
+        schedule.weekday_mut(Weekday::Sat).shown = true;
+        schedule.weekday_mut(Weekday::Sat).enabled = true;
+        schedule.weekday_mut(Weekday::Sun).shown = true;
+        schedule
+    }
+
+    #[test]
+    fn loaded_week_follows_the_schedule() {
+        let mut state = State::default();
+        state.set_schedule(weekend_schedule());
+        state.set_current_week_normalized(2026, 20);
+        let saturday = NaiveDate::from_ymd_opt(2026, 5, 16).unwrap();
+        let mut server = state.default_day_for_date(saturday);
+        server.durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(8, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        let mut sync = logged_in_sync();
+
+        sync.apply_loaded_drafts(&mut state, vec![WorkDayDraft::from_ui_day(&server).unwrap()])
+            .unwrap();
+        let dates: Vec<_> = state.days().iter().map(|day| day.date.weekday()).collect();
+        assert_eq!(dates.len(), 7);
+        assert_eq!(dates[5..], [Weekday::Sat, Weekday::Sun]);
+        assert_eq!(state.days()[5].durations.len(), 1);
+        // Days without a server row get the schedule's defaults.
+        assert!(!state.days()[6].enabled);
+        assert!(state.days()[0].enabled);
+    }
+
+    #[test]
+    fn dirty_state_covers_shown_weekend_days() {
+        let mut state = State::default();
+        state.set_schedule(weekend_schedule());
+        let mut sync = logged_in_sync();
+        let drafts: Vec<WorkDayDraft> = state
+            .days()
+            .iter()
+            .map(WorkDayDraft::from_ui_day)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        sync.weeks.insert(
+            state.current_week_key(),
+            CachedWeek {
+                days: drafts,
+                fetched_at: 0.0,
+            },
+        );
+        assert!(!sync.is_week_dirty(&state));
+
+        state.days_mut()[5].durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(8, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        assert!(sync.is_week_dirty(&state));
+    }
+
+    #[test]
+    fn loaded_contract_periods_keep_local_list_when_server_has_none() {
+        let mut state = State::default();
+        state.set_contract_periods(vec![ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            end: None,
+            profile: ContractProfile::full_time(),
+        }]);
+        let mut sync = logged_in_sync();
+
+        sync.apply_loaded_contract_periods(&mut state, vec![]).unwrap();
+        assert_eq!(state.contract_periods().len(), 1);
+        assert!(sync.is_contracts_dirty(&state));
+
+        let server = ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            end: None,
+            profile: ContractProfile::full_time(),
+        };
+        sync.synced_contract_periods = None;
+        sync.apply_loaded_contract_periods(&mut state, vec![server.to_draft().unwrap()])
+            .unwrap();
+        assert_eq!(state.contract_periods(), &[server]);
+        assert!(!sync.is_contracts_dirty(&state));
+    }
+
+    #[test]
+    fn loading_over_local_entries_takes_a_snapshot() {
+        let mut state = State::default();
+        state.days_mut()[0].durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        let (start_date, end_date) = state.current_week_range();
+        let week = state.current_week_key();
+        let mut sync = logged_in_sync();
+        let mut snapshots = Snapshots::default();
+        let load = |state: &mut State, sync: &mut SyncState, snapshots: &mut Snapshots| {
+            let result = AsyncResult::LoadWeek {
+                op: PendingAuthedOp::LoadWeek {
+                    week,
+                    start_date,
+                    end_date,
+                    refreshed_after_failure: false,
+                },
+                result: Ok(LoadedWeek {
+                    days: vec![],
+                    contract_periods: vec![],
+                }),
+            };
+            sync.process_async_results(
+                vec![result],
+                ProcessAsyncContext {
+                    state,
+                    undo_log: &mut Default::default(),
+                    ui_state: &mut Default::default(),
+                    snapshots,
+                    config: None,
+                    async_results: &new_async_results(),
+                    ctx: &egui::Context::default(),
+                },
+            );
+        };
+
+        load(&mut state, &mut sync, &mut snapshots);
+        assert_eq!(state.duration(), time::Duration::ZERO);
+        let snapshot = snapshots.iter().next().unwrap();
+        assert_eq!(
+            snapshot
+                .days
+                .iter()
+                .map(|day| day.duration(state.break_rules()))
+                .sum::<time::Duration>(),
+            time::Duration::hours(3)
+        );
+
+        // Loading again changes nothing, so no new snapshot is taken.
+        load(&mut state, &mut sync, &mut snapshots);
+        assert_eq!(snapshots.iter().count(), 1);
+    }
+
+    #[test]
+    fn queued_days_survive_loading_their_week() {
+        let mut state = State::default();
+        let mut sync = logged_in_sync();
+        sync.observe_edits(&state, 0.0);
+        state.days_mut()[0].durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        sync.observe_edits(&state, 1.0);
+        assert!(sync.has_unsaved_changes(&state));
+
+        let server = sync.apply_loaded_drafts(&mut state, vec![]).unwrap();
+        assert!(server.iter().all(|draft| draft.work_entries.is_empty()));
+        assert_eq!(state.duration(), time::Duration::hours(3));
+        assert!(sync.can_change_week());
+    }
+
+    #[test]
+    fn timer_days_outside_the_visible_week_are_queued() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let mut sync = logged_in_sync();
+        sync.observe_edits(&state, 0.0);
+        let now = time::OffsetDateTime::from_unix_timestamp(1_780_300_800).unwrap();
+
+        assert!(state.start_timer(now));
+        let date = state.running_timer().unwrap().date;
+        assert!(!state.current_week_dates().contains(&date));
+        sync.observe_edits(&state, 1.0);
+        assert!(sync.outbox.pending(date).is_none());
+
+        let mut ui_state = AppUiState::default();
+        sync.queue_timer_day(&state, date, &mut ui_state, 1.0);
+        let queued = sync.outbox.pending(date).unwrap();
+        assert_eq!(queued.work_entries.len(), 1);
+        assert!(queued.work_entries[0].ends_at.is_none());
+
+        state.stop_timer(now + time::Duration::hours(1));
+        sync.queue_timer_day(&state, date, &mut ui_state, 2.0);
+        assert!(sync.outbox.pending(date).unwrap().work_entries[0].ends_at.is_some());
+    }
+
+    #[test]
+    fn failed_contract_periods_do_not_hide_saved_days() {
+        let mut state = State::default();
+        let mut sync = logged_in_sync();
+        sync.synced_contract_periods = Some(vec![]);
+        sync.observe_edits(&state, 0.0);
+        state.days_mut()[0].durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        state.set_contract_periods(vec![ContractPeriod {
+            start: NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+            end: None,
+            profile: ContractProfile::full_time(),
+        }]);
+        sync.observe_edits(&state, 1.0);
+        let drafts = sync.outbox.batch();
+        let contract_periods = Some(sync.current_contract_drafts(&state).unwrap());
+
+        let mut ui_state = AppUiState::default();
+        sync.process_async_results(
+            vec![AsyncResult::SaveDays {
+                op: PendingAuthedOp::SaveDays {
+                    drafts: drafts.clone(),
+                    contract_periods,
+                    refreshed_after_failure: false,
+                },
+                result: Ok(SavedDays {
+                    saved: drafts,
+                    failed: vec![],
+                    conflicts: vec![],
+                    contract_periods: Some(Err("permission denied".to_string())),
+                }),
             }],
-        });
+            ProcessAsyncContext {
+                state: &mut state,
+                undo_log: &mut Default::default(),
+                ui_state: &mut ui_state,
+                snapshots: &mut Default::default(),
+                config: None,
+                async_results: &new_async_results(),
+                ctx: &egui::Context::default(),
+            },
+        );
+
+        assert!(sync.outbox.is_empty());
+        assert!(sync.is_contracts_dirty(&state));
+        let error = ui_state.error_message().unwrap();
+        assert!(error.starts_with("Failed to save contract periods"), "{error}");
+    }
+
+    #[test]
+    fn restores_queue_the_days_they_change() {
+        let mut before = State::default();
+        before.set_current_week_normalized(2026, 20);
+        let mut sync = logged_in_sync();
+        sync.observe_edits(&before, 0.0);
+        let entry = || ui::Duration::from_clocks(ui::TimePoint::new(9, 0), Some(ui::TimePoint::new(12, 0)), 0);
+
+        // An earlier week whose only copy is on the server, and one this
+        // device never saw at all.
+        let server_date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let mut server_day = before.default_day_for_date(server_date);
+        server_day.durations.push(entry());
+        sync.history.days.insert(server_date, server_day.clone());
+        let unseen_date = NaiveDate::from_ymd_opt(2026, 3, 3).unwrap();
+
+        // The snapshot an import takes holds the previous version of each
+        // imported date; the import then adds an entry to both dates.
+        let mut snapshot = before.clone();
+        snapshot.merge_days(vec![server_day.clone(), before.default_day_for_date(unseen_date)]);
+        let mut imported = server_day.clone();
+        imported.durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(13, 0),
+            Some(ui::TimePoint::new(14, 0)),
+            0,
+        ));
+        let mut imported_unseen = before.default_day_for_date(unseen_date);
+        imported_unseen.durations.push(entry());
+        let mut after_import = before.clone();
+        after_import.merge_days(vec![imported, imported_unseen]);
+
+        sync.queue_restored_days(&after_import, &snapshot, &mut Default::default(), 1.0);
+        let queued = |sync: &SyncState, date| sync.outbox.pending(date).map(|draft| draft.work_entries.len());
+        assert_eq!(queued(&sync, server_date), Some(1));
+        assert_eq!(queued(&sync, unseen_date), Some(0));
+
+        // A visited but empty week in a snapshot leaves its server copy alone.
+        let history_date = NaiveDate::from_ymd_opt(2026, 5, 12).unwrap();
+        let mut history_day = before.default_day_for_date(history_date);
+        history_day.durations.push(entry());
+        sync.history.days.insert(history_date, history_day);
+        sync.queue_restored_days(&after_import, &before, &mut Default::default(), 2.0);
+        assert_eq!(queued(&sync, history_date), None);
+    }
+
+    #[test]
+    fn cached_weeks_show_instantly_and_track_dirtiness_per_week() {
+        let mut state = State::default();
+        let mut sync = logged_in_sync();
+        let mut snapshots = Snapshots::default();
+        let visible = state.current_week_key();
+        let mut day = state.days()[0].clone();
+        day.durations.push(ui::Duration::from_clocks(
+            ui::TimePoint::new(9, 0),
+            Some(ui::TimePoint::new(12, 0)),
+            0,
+        ));
+        let server_day = WorkDayDraft::from_ui_day(&day).unwrap();
+        sync.weeks.insert(
+            visible,
+            CachedWeek {
+                days: vec![server_day],
+                fetched_at: 0.0,
+            },
+        );
 
+        state.shift_weeks(1);
+        assert!(!sync.show_cached_week(&mut state, &mut snapshots));
         assert!(sync.is_week_dirty(&state));
+
+        state.shift_weeks(-1);
+        assert!(sync.show_cached_week(&mut state, &mut snapshots));
+        assert_eq!(state.duration(), time::Duration::hours(3));
+        assert!(!sync.is_week_dirty(&state));
+    }
+
+    #[test]
+    fn remote_changes_reload_clean_weeks_and_flag_edited_ones() {
+        let mut state = State::default();
+        let mut sync = logged_in_sync();
+        let visible = state.current_week_key();
+        let mut server: Vec<WorkDayDraft> = state.days().iter().map(|day| WorkDayDraft::from_ui_day(day).unwrap()).collect();
+        server[0].updated_at = Some(Utc.with_ymd_and_hms(2026, 5, 4, 8, 0, 0).unwrap());
+        sync.weeks.insert(
+            visible,
+            CachedWeek {
+                days: server.clone(),
+                fetched_at: 0.0,
+            },
+        );
+        let date = server[0].work_date;
+        let newer = Some(Utc.with_ymd_and_hms(2026, 5, 4, 9, 0, 0).unwrap());
+        let change = |updated_at| DayChange {
+            work_date: Some(date),
+            updated_at,
+        };
+
+        // The cache already has this version, e.g. because it was saved here.
+        assert_eq!(sync.remote_change(&state, &change(server[0].updated_at)), None);
+        assert_eq!(sync.remote_change(&state, &change(newer)), Some(RemoteChange::ReloadVisible));
+
+        sync.observe_edits(&state, 0.0);
+        state.days_mut()[0].enabled = !state.days()[0].enabled;
+        sync.observe_edits(&state, 1.0);
+        assert_eq!(sync.remote_change(&state, &change(newer)), Some(RemoteChange::FlagVisible));
+
+        let next = visible.offset(1);
+        let other = DayChange {
+            work_date: Some(state.week_range(next).0),
+            updated_at: newer,
+        };
+        assert_eq!(sync.remote_change(&state, &other), None);
+        sync.weeks.insert(
+            next,
+            CachedWeek {
+                days: vec![],
+                fetched_at: 0.0,
+            },
+        );
+        assert_eq!(sync.remote_change(&state, &other), Some(RemoteChange::RefetchCached(next)));
     }
 
     #[test]
@@ -876,4 +2197,14 @@ mod tests {
         assert!(is_auth_error("Supabase request failed with status 403 during get work day range: not authenticated"));
         assert!(!is_auth_error("failed to save 2026-04-28: local date/time is ambiguous or invalid"));
     }
+
+    #[test]
+    fn week_save_errors_are_shown_as_is() {
+        let error = "Supabase request failed with status 400 during save work week RPC: value too long";
+        assert_eq!(summarize_save_error(error), error);
+        assert_eq!(
+            summarize_save_error("Supabase request failed with status 404 during save work week RPC: Could not find the function public.save_work_week_with_entries"),
+            "database save RPC is missing or mismatched"
+        );
+    }
 }
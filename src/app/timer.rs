@@ -0,0 +1,29 @@
+// Running clock-in timer. The timer owns only the bookkeeping needed to find
+// its open entry again; the entry itself is a normal open `ui::Duration`, so
+// totals, saving and undo treat it like any other row and another device can
+// stop it by closing that entry.
+
+use crate::ui;
+use chrono::NaiveDate;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct RunningTimer {
+    /// Work day the open entry belongs to.
+    pub date: NaiveDate,
+    /// Row id of the open entry, used to find it again after edits.
+    pub row_id: u64,
+    pub started_at: time::OffsetDateTime,
+}
+
+impl RunningTimer {
+    pub fn elapsed(&self, now: time::OffsetDateTime) -> time::Duration {
+        (now - self.started_at).max(time::Duration::ZERO)
+    }
+}
+
+/// Absolute local start of an entry that starts at `clock` on `date`, using
+/// the offset of `now`.
+pub(crate) fn started_at(date: NaiveDate, clock: &ui::TimePoint, now: time::OffsetDateTime) -> time::OffsetDateTime {
+    let days = (date - ui::duration::local_date(now)).num_days();
+    now.replace_time(time::Time::MIDNIGHT) + time::Duration::days(days) + time::Duration::minutes(clock.total_minutes())
+}
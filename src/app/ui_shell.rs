@@ -2,16 +2,37 @@
 // as day/time editors stay in `src/ui/*`; this file only arranges the app-wide
 // panels and delegates actions back into `TemplateApp`.
 
-use crate::ui::duration;
+use crate::ui::{self, duration};
+use chrono::{Datelike, NaiveDate};
 use egui::{Color32, RichText};
 
+use super::conflict::{entry_label, ConflictMerge, Side};
+use super::contract::{ContractPeriod, ContractProfile};
+use super::export::ExportFormat;
+use super::ical::{self, EventStatus};
+use super::import::{self, CsvMapping, ImportFormat, TimeColumns};
+use super::overview::{self, DayStatus, OverviewView};
+use super::report::{self, ReportPeriod};
+use super::schedule::WEEKDAYS;
 use super::state::current_iso_week_and_year;
+use super::ui_state::LoginMethod;
 use super::TemplateApp;
 
+const CUSTOM_CONTRACT_NAME: &str = "Custom";
+
 pub(crate) fn render(app: &mut TemplateApp, ctx: &egui::Context, frame: &mut eframe::Frame) {
     render_menu_bar(app, ctx);
     render_header_bar(app, ctx);
     render_login_window(app, ctx);
+    render_schedule_window(app, ctx);
+    render_balance_window(app, ctx);
+    render_break_rules_window(app, ctx);
+    render_export_window(app, ctx);
+    render_import_window(app, ctx);
+    render_calendar_window(app, ctx);
+    render_restore_window(app, ctx);
+    render_history_window(app, ctx);
+    render_conflicts_window(app, ctx);
     render_main_panel(app, ctx, frame);
 }
 
@@ -19,8 +40,35 @@ fn render_menu_bar(app: &mut TemplateApp, ctx: &egui::Context) {
     egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
         egui::menu::bar(ui, |ui| {
             let is_web = cfg!(target_arch = "wasm32");
-            if !is_web {
-                ui.menu_button("File", |ui| {
+            ui.menu_button("File", |ui| {
+                if ui.button("Export…").clicked() {
+                    let week = app.state.current_week_key().monday();
+                    app.ui_state.set_show_export_window(true, (week, week + chrono::Duration::days(6)));
+                    ui.close_menu();
+                }
+                if ui.button("Import CSV / Clockify…").clicked() {
+                    app.ui_state.set_show_import_window(true);
+                    ui.close_menu();
+                }
+                if ui.button("Import calendar (.ics)…").clicked() {
+                    app.ui_state.set_show_calendar_window(true);
+                    ui.close_menu();
+                }
+                ui.separator();
+                if ui.button("Back up all data…").clicked() {
+                    app.export_backup(ctx.clone());
+                    ui.close_menu();
+                }
+                if ui.button("Restore backup…").clicked() {
+                    app.pick_backup_file(ctx.clone());
+                    ui.close_menu();
+                }
+                if ui.button("History…").clicked() {
+                    app.ui_state.set_show_history_window(true);
+                    ui.close_menu();
+                }
+                if !is_web {
+                    ui.separator();
                     if ui.button("Reset state").clicked() {
                         app.reset_state();
                     }
@@ -28,9 +76,25 @@ fn render_menu_bar(app: &mut TemplateApp, ctx: &egui::Context) {
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
-                });
-                ui.add_space(16.0);
-            }
+                }
+            });
+            ui.add_space(16.0);
+
+            ui.menu_button("Settings", |ui| {
+                if ui.button("Week schedule").clicked() {
+                    app.ui_state.set_show_schedule_window(true);
+                    ui.close_menu();
+                }
+                if ui.button("Flex balance").clicked() {
+                    app.ui_state.set_show_balance_window(true);
+                    ui.close_menu();
+                }
+                if ui.button("Break rules").clicked() {
+                    app.ui_state.set_show_break_rules_window(true);
+                    ui.close_menu();
+                }
+            });
+            ui.add_space(16.0);
 
             egui::widgets::global_theme_preference_buttons(ui);
         });
@@ -39,9 +103,10 @@ fn render_menu_bar(app: &mut TemplateApp, ctx: &egui::Context) {
 
 fn render_header_bar(app: &mut TemplateApp, ctx: &egui::Context) {
     egui::TopBottomPanel::top("top_panel_2").show(ctx, |ui| {
-        let can_undo = app.undoer.has_undo(&app.state);
-        let can_redo = app.undoer.has_redo(&app.state);
-        let can_change_week = app.sync.can_change_week(&app.state);
+        let week = app.state.current_week_key();
+        let can_undo = app.undo_log.can_undo(week);
+        let can_redo = app.undo_log.can_redo(week);
+        let can_change_week = app.sync.can_change_week();
         let logged_in = app.sync.is_logged_in();
         let is_busy = app.sync.is_busy();
 
@@ -49,7 +114,7 @@ fn render_header_bar(app: &mut TemplateApp, ctx: &egui::Context) {
             ui.horizontal_wrapped(|ui| {
                 if ui
                     .button("Reset state")
-                    .on_hover_text("Remove all stored data and start fresh. Can't be undone")
+                    .on_hover_text("Remove all stored data and start fresh. A snapshot is kept in File → History")
                     .clicked()
                 {
                     app.reset_state();
@@ -59,14 +124,25 @@ fn render_header_bar(app: &mut TemplateApp, ctx: &egui::Context) {
                 let redo = ui.add_enabled(can_redo, egui::Button::new("⟳ Redo")).clicked();
 
                 if undo {
-                    if let Some(prev_state) = app.undoer.undo(&app.state) {
-                        app.state = prev_state.clone();
-                    }
+                    app.undo(ctx.input(|input| input.time));
                 }
                 if redo {
-                    if let Some(redo_state) = app.undoer.redo(&app.state) {
-                        app.state = redo_state.clone();
-                    }
+                    app.redo(ctx.input(|input| input.time));
+                }
+
+                ui.separator();
+
+                let mut view = app.ui_state.view();
+                ui.selectable_value(&mut view, OverviewView::Week, "Week");
+                ui.selectable_value(&mut view, OverviewView::Month, "Month");
+                ui.selectable_value(&mut view, OverviewView::Year, "Year");
+                ui.selectable_value(&mut view, OverviewView::Report, "Report");
+                if view != app.ui_state.view() {
+                    // Overviews open on the period around the visible week.
+                    let monday = app.state.current_week_key().monday();
+                    app.ui_state.set_overview_month(monday.year(), monday.month() as i32);
+                    app.ui_state.set_overview_year(app.state.cur_year());
+                    app.ui_state.set_view(view);
                 }
 
                 ui.separator();
@@ -97,6 +173,49 @@ fn render_header_bar(app: &mut TemplateApp, ctx: &egui::Context) {
                     app.navigate_to_week(ctx.clone(), year, week_nr as i32);
                 }
 
+                ui.separator();
+                match app.state.running_timer() {
+                    Some(running) => {
+                        let elapsed = running.elapsed(duration::local_now());
+                        ui.colored_label(Color32::GREEN, format!("⏱ {}", duration::format_duration(elapsed, "%H:%M:%S")))
+                            .on_hover_text(format!(
+                                "Running since {:02}:{:02} on {}",
+                                running.started_at.hour(),
+                                running.started_at.minute(),
+                                running.date
+                            ));
+                        if ui
+                            .button("Stop")
+                            .on_hover_text("Close the running entry at the current time")
+                            .clicked()
+                        {
+                            app.stop_timer(ctx.input(|input| input.time));
+                        }
+                    }
+                    None => {
+                        if ui
+                            .button("Start timer")
+                            .on_hover_text("Add an open entry to today that runs until stopped")
+                            .clicked()
+                        {
+                            app.start_timer(ctx.input(|input| input.time));
+                        }
+                    }
+                }
+
+                if let Some(balance) = app.flex_balance() {
+                    ui.separator();
+                    let color = if balance.is_negative() { Color32::RED } else { Color32::GREEN };
+                    let mut text = format!("Flex: {}", duration::format_signed_duration(balance, duration::DURATION_FORMAT));
+                    if app.sync.is_loading_history() {
+                        text.push_str(" ...");
+                    }
+                    ui.colored_label(color, text).on_hover_text(format!(
+                        "Worked minus target since {}, through today",
+                        app.state.balance_start().unwrap_or_default()
+                    ));
+                }
+
                 ui.separator();
                 let status = if logged_in {
                     format!("Logged in: {}", app.sync.session_label())
@@ -106,20 +225,26 @@ fn render_header_bar(app: &mut TemplateApp, ctx: &egui::Context) {
                 ui.label(RichText::new(status).strong());
 
                 if logged_in {
-                    if ui
-                        .add_enabled(app.sync.can_refresh_week(&app.state), egui::Button::new("Refresh"))
-                        .clicked()
-                    {
+                    if ui.add_enabled(app.sync.can_refresh_week(), egui::Button::new("Refresh")).clicked() {
                         app.request_visible_week_load(ctx.clone());
                     }
                     if ui
                         .add_enabled(
-                            app.sync.is_week_dirty(&app.state) && app.sync.in_flight_save_week().is_none(),
-                            egui::Button::new("Save"),
+                            app.sync.has_unsaved_changes(&app.state) && !app.sync.is_saving(),
+                            egui::Button::new("Save now"),
                         )
+                        .on_hover_text("Changes are saved automatically; this skips the wait")
                         .clicked()
                     {
-                        app.save_visible_week(ctx.clone());
+                        app.save_now(ctx.clone());
+                    }
+                    if !app.sync.conflicts().is_empty()
+                        && ui
+                            .button("Merge conflicts")
+                            .on_hover_text("Days changed on another device while this one had unsaved edits")
+                            .clicked()
+                    {
+                        app.ui_state.set_show_conflicts_window(true);
                     }
                     if ui.add_enabled(!is_busy, egui::Button::new("Log out")).clicked() {
                         app.logout();
@@ -134,13 +259,13 @@ fn render_header_bar(app: &mut TemplateApp, ctx: &egui::Context) {
                     app.ui_state.set_show_login_window(true);
                 }
 
-                if app.sync.is_week_dirty(&app.state) {
-                    ui.colored_label(Color32::YELLOW, "Unsaved changes");
+                if let Some(save_status) = app.sync.save_status(&app.state, ctx.input(|input| input.time)) {
+                    ui.colored_label(Color32::YELLOW, save_status);
                 }
             });
         });
 
-        app.undoer.feed_state(ui.ctx().input(|input| input.time), &app.state);
+        app.undo_log.feed(ui.ctx().input(|input| input.time), &app.state);
     });
 }
 
@@ -155,22 +280,904 @@ fn render_login_window(app: &mut TemplateApp, ctx: &egui::Context) {
         .resizable(false)
         .open(&mut open)
         .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let method = app.ui_state.login_method_mut();
+                ui.selectable_value(method, LoginMethod::Password, "Password");
+                ui.selectable_value(method, LoginMethod::EmailCode, "Email link or code");
+            });
+            ui.separator();
+
             ui.label("Email");
             ui.text_edit_singleline(app.ui_state.login_email_mut());
-            ui.label("Password");
-            ui.add(egui::TextEdit::singleline(app.ui_state.login_password_mut()).password(true));
+            let idle = !app.sync.in_flight_auth();
+            match app.ui_state.login_method() {
+                LoginMethod::Password => {
+                    ui.label("Password");
+                    ui.add(egui::TextEdit::singleline(app.ui_state.login_password_mut()).password(true));
 
-            if let Some(error) = app.ui_state.error_message() {
-                ui.colored_label(Color32::RED, error);
-            }
+                    if let Some(error) = app.ui_state.error_message() {
+                        ui.colored_label(Color32::RED, error);
+                    }
+
+                    if ui.add_enabled(idle, egui::Button::new("Log in")).clicked() {
+                        app.start_login(ctx.clone());
+                    }
+                }
+                LoginMethod::EmailCode => {
+                    let sent_to = app.ui_state.login_code_sent_to().map(str::to_string);
+                    let send_label = if sent_to.is_some() { "Send again" } else { "Send login email" };
+                    if ui.add_enabled(idle, egui::Button::new(send_label)).clicked() {
+                        app.send_login_email(ctx.clone());
+                    }
+
+                    if let Some(sent_to) = sent_to {
+                        if cfg!(target_arch = "wasm32") {
+                            ui.label(format!("Sent to {sent_to}. Open the link in it, or enter its code here."));
+                        } else {
+                            ui.label(format!("Sent to {sent_to}. Enter the code from it here."));
+                        }
+                        ui.label("Code");
+                        ui.text_edit_singleline(app.ui_state.login_code_mut());
+                    }
+
+                    if let Some(error) = app.ui_state.error_message() {
+                        ui.colored_label(Color32::RED, error);
+                    }
 
-            if ui.add_enabled(!app.sync.in_flight_auth(), egui::Button::new("Log in")).clicked() {
-                app.start_login(ctx.clone());
+                    let has_code = !app.ui_state.login_code().trim().is_empty();
+                    if ui.add_enabled(idle && has_code, egui::Button::new("Log in")).clicked() {
+                        app.verify_login_code(ctx.clone());
+                    }
+                }
             }
         });
     app.ui_state.set_show_login_window(open);
 }
 
+fn render_schedule_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_schedule_window() {
+        return;
+    }
+
+    let mut open = app.ui_state.show_schedule_window();
+    let mut schedule = app.state.schedule().clone();
+    let mut contract = app.state.contract().clone();
+    let mut periods = app.state.contract_periods().to_vec();
+    let mut apply_to_week = false;
+    let can_edit = !app.sync.is_busy();
+    egui::Window::new("Week schedule")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.add_enabled_ui(can_edit, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Default contract:");
+                    contract_profile_combo(ui, "contract_profile", &mut contract);
+                });
+
+                egui::Grid::new("schedule_grid").striped(true).show(ui, |ui| {
+                    ui.label("Day");
+                    ui.label("Shown");
+                    ui.label("Enabled");
+                    ui.label("Target");
+                    ui.end_row();
+
+                    let shown_count = schedule.shown_count();
+                    for weekday in WEEKDAYS {
+                        let entry = schedule.weekday_mut(weekday);
+                        ui.label(weekday.to_string());
+                        // Keep at least one weekday visible so the week never
+                        // collapses to an empty layout.
+                        ui.add_enabled_ui(!entry.shown || shown_count > 1, |ui| {
+                            ui.checkbox(&mut entry.shown, "");
+                        });
+                        ui.checkbox(&mut entry.enabled, "");
+                        let mut target = contract.target_for(weekday);
+                        target_editor(ui, &mut target);
+                        if target != contract.target_for(weekday) {
+                            contract.set_target(weekday, target);
+                            contract.name = CUSTOM_CONTRACT_NAME.to_string();
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.label(format!(
+                    "Weekly target: {}",
+                    duration::format_duration(contract.weekly_target(), duration::DURATION_FORMAT)
+                ));
+
+                ui.separator();
+                ui.label(RichText::new("Contract periods").strong());
+                ui.label("A period overrides the default contract between its dates. Each period needs its own start date.");
+                let mut remove_ix = None;
+                for (ix, period) in periods.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label("From");
+                        date_editor(ui, &mut period.start);
+                        let mut open_ended = period.end.is_none();
+                        ui.checkbox(&mut open_ended, "open-ended");
+                        if open_ended {
+                            period.end = None;
+                        } else {
+                            let mut end = period.end.unwrap_or(period.start);
+                            ui.label("until");
+                            date_editor(ui, &mut end);
+                            period.end = Some(end.max(period.start));
+                        }
+                        if ui
+                            .add(egui::Button::new("×").corner_radius(10.0))
+                            .on_hover_text("Remove period")
+                            .clicked()
+                        {
+                            remove_ix = Some(ix);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        contract_profile_combo(ui, ("contract_period", ix), &mut period.profile);
+                        ui.label(format!(
+                            "{} per week",
+                            duration::format_duration(period.profile.weekly_target(), duration::DURATION_FORMAT)
+                        ));
+                    });
+                    egui::CollapsingHeader::new("Weekday targets")
+                        .id_salt(("contract_period_targets", ix))
+                        .show(ui, |ui| {
+                            egui::Grid::new(("contract_period_grid", ix)).show(ui, |ui| {
+                                for weekday in WEEKDAYS {
+                                    ui.label(weekday.to_string());
+                                    let mut target = period.profile.target_for(weekday);
+                                    target_editor(ui, &mut target);
+                                    if target != period.profile.target_for(weekday) {
+                                        period.profile.set_target(weekday, target);
+                                        period.profile.name = CUSTOM_CONTRACT_NAME.to_string();
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    ui.separator();
+                }
+                if let Some(ix) = remove_ix {
+                    periods.remove(ix);
+                }
+                let today = chrono::Local::now().date_naive();
+                let today_taken = periods.iter().any(|period| period.start == today);
+                if ui
+                    .add_enabled(!today_taken, egui::Button::new("Add period"))
+                    .on_disabled_hover_text("A period already starts today; change its start date first")
+                    .clicked()
+                {
+                    periods.push(ContractPeriod {
+                        start: today,
+                        end: None,
+                        profile: contract.clone(),
+                    });
+                }
+
+                ui.separator();
+                ui.label("Changes apply to days that have no stored data yet.");
+                apply_to_week = ui
+                    .button("Apply targets to this week")
+                    .on_hover_text("Reset the targets of the visible week to the contract valid on each day")
+                    .clicked();
+            });
+        });
+    app.ui_state.set_show_schedule_window(open);
+
+    if &contract != app.state.contract() {
+        app.state.set_contract(contract);
+    }
+    if periods != app.state.contract_periods() {
+        app.state.set_contract_periods(periods);
+    }
+    if &schedule != app.state.schedule() {
+        app.set_schedule(ctx.clone(), schedule);
+    }
+    if apply_to_week {
+        app.state.apply_contract_to_current_week();
+    }
+}
+
+fn render_balance_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_balance_window() {
+        return;
+    }
+
+    let mut open = app.ui_state.show_balance_window();
+    let mut start = app.state.balance_start();
+    egui::Window::new("Flex balance")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let mut enabled = start.is_some();
+            ui.checkbox(&mut enabled, "Track a running flex balance");
+            if enabled {
+                let mut date = start.unwrap_or_else(|| chrono::Local::now().date_naive());
+                ui.horizontal(|ui| {
+                    ui.label("Counting from");
+                    date_editor(ui, &mut date);
+                });
+                start = Some(date);
+            } else {
+                start = None;
+            }
+            ui.label("Every shown weekday since the start date counts, including days without entries.");
+        });
+    app.ui_state.set_show_balance_window(open);
+
+    if start != app.state.balance_start() {
+        app.set_balance_start(ctx.clone(), start);
+    }
+}
+
+fn render_break_rules_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_break_rules_window() {
+        return;
+    }
+
+    let mut open = app.ui_state.show_break_rules_window();
+    let mut rules = app.state.break_rules().clone();
+    egui::Window::new("Break rules")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label("When a day's worked time exceeds a threshold and its recorded breaks are shorter than required, the missing part is deducted.");
+            ui.separator();
+
+            let mut remove_ix = None;
+            egui::Grid::new("break_rules_grid").striped(true).show(ui, |ui| {
+                ui.label(RichText::new("Worked over").strong());
+                ui.label(RichText::new("Break of at least").strong());
+                ui.end_row();
+                for (ix, rule) in rules.rules.iter_mut().enumerate() {
+                    target_editor(ui, &mut rule.worked_over);
+                    target_editor(ui, &mut rule.min_break);
+                    if ui.button("×").on_hover_text("Remove rule").clicked() {
+                        remove_ix = Some(ix);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(ix) = remove_ix {
+                rules.rules.remove(ix);
+            }
+
+            if ui.button("Add rule").on_hover_text("Adds the 30 minutes after 6 hours rule").clicked() {
+                rules.rules.push(ui::BreakRules::six_hour_rule());
+            }
+        });
+    app.ui_state.set_show_break_rules_window(open);
+
+    if &rules != app.state.break_rules() {
+        app.set_break_rules(rules);
+    }
+}
+
+fn render_export_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_export_window() {
+        return;
+    }
+
+    let today = chrono::Local::now().date_naive();
+    let (mut period, mut range, mut day_summary) = app.ui_state.export_selection(today);
+    let mut format = app.ui_state.export_format();
+    let mut open = true;
+    let mut export = false;
+    egui::Window::new("Export")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            period_range_selector(ui, &mut period, &mut range);
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut format, ExportFormat::Csv, "CSV");
+                ui.selectable_value(&mut format, ExportFormat::Ical, "iCalendar");
+            });
+            match format {
+                ExportFormat::Csv => {
+                    ui.checkbox(&mut day_summary, "Include a per-day summary");
+                    ui.label("One row per entry with date, times, duration, day target and tags.");
+                }
+                ExportFormat::Ical => {
+                    ui.label("One calendar event per finished entry, titled with its project and note.");
+                }
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                let loading = app.sync.is_loading_history();
+                export = ui
+                    .add_enabled(!loading, egui::Button::new("Export"))
+                    .on_disabled_hover_text("Waiting for server data of this range")
+                    .clicked();
+                if loading {
+                    ui.spinner();
+                }
+            });
+        });
+    app.ui_state.set_export_selection(period, range, day_summary);
+    app.ui_state.set_export_format(format);
+    app.request_history_range(ctx.clone(), range);
+
+    if !open {
+        app.ui_state.set_show_export_window(false, range);
+    } else if export {
+        app.export(ctx.clone(), range, format, day_summary);
+    }
+}
+
+fn render_import_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_import_window() {
+        return;
+    }
+
+    let loading = app.sync.is_loading_history();
+    let logged_in = app.sync.is_logged_in();
+    let mut open = true;
+    let mut pick_file = false;
+    let mut apply = false;
+    let mut roll_back = false;
+    let mut preview_range = None;
+    egui::Window::new("Import").collapsible(false).open(&mut open).show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            pick_file = ui.button("Choose file…").clicked();
+            if app.can_roll_back_import() {
+                roll_back = ui
+                    .button("Roll back last import")
+                    .on_hover_text(
+                        "Restore all days as they were right before the last import. The restored days are saved to the server as well.",
+                    )
+                    .clicked();
+            }
+        });
+
+        let Some(session) = app.ui_state.import_session_mut() else {
+            ui.label("Pick a CSV file with one row per entry, or a Clockify detailed report (CSV or JSON).");
+            return;
+        };
+        ui.label(RichText::new(&session.file_name).strong());
+        let header = match &session.table {
+            Ok(table) => table.header.clone(),
+            Err(err) => {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+                vec![]
+            }
+        };
+        match session.format {
+            ImportFormat::Csv => import_mapping_editor(ui, &mut session.mapping, &header),
+            ImportFormat::Clockify => {
+                ui.label("Clockify detailed report: projects, descriptions and tags are imported as project, note and tags.");
+            }
+        }
+
+        session.refresh_plan(&app.state, app.sync.history_days(), duration::local_now());
+        let Some(plan) = &session.plan else {
+            return;
+        };
+        preview_range = plan
+            .days
+            .keys()
+            .next()
+            .zip(plan.days.keys().next_back())
+            .map(|(first, last)| (*first, *last));
+        // A plan built before the server days of its dates arrived would
+        // replace them, so it is rebuilt until they are loaded.
+        let history_ready = !logged_in || preview_range.map_or(true, |range| app.sync.history_covers(range));
+
+        ui.separator();
+        if !plan.errors.is_empty() {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("{} rows could not be read:", plan.errors.len()),
+            );
+            for error in plan.errors.iter().take(import::MAX_SHOWN_ERRORS) {
+                ui.label(error);
+            }
+        }
+        egui::ScrollArea::vertical().max_height(260.0).show(ui, |ui| {
+            egui::Grid::new("import_preview").striped(true).num_columns(5).show(ui, |ui| {
+                for heading in ["Date", "Existing", "Added", "Skipped", "Total"] {
+                    ui.label(RichText::new(heading).strong());
+                }
+                ui.end_row();
+                for (date, day) in &plan.days {
+                    ui.label(date.format("%a %Y-%m-%d").to_string());
+                    ui.label(day.existing.to_string());
+                    ui.label(day.added.to_string());
+                    ui.label(day.duplicates.to_string())
+                        .on_hover_text("Entries with the same start and end are already recorded");
+                    ui.label(format!(
+                        "{} → {}",
+                        duration::format_duration(day.before, "%H:%M"),
+                        duration::format_duration(day.after, "%H:%M")
+                    ));
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.separator();
+        if logged_in {
+            ui.checkbox(&mut session.push_to_server, "Save imported days to the server");
+        }
+        ui.horizontal(|ui| {
+            apply = ui
+                .add_enabled(
+                    history_ready && plan.added() > 0,
+                    egui::Button::new(format!("Import {} entries", plan.added())),
+                )
+                .on_disabled_hover_text("Nothing new to import, or server data is still loading")
+                .clicked();
+            if loading || !history_ready {
+                ui.spinner();
+            }
+        });
+        if !history_ready {
+            session.invalidate_plan();
+        }
+    });
+
+    if let Some(range) = preview_range {
+        app.request_history_range(ctx.clone(), range);
+    }
+    if pick_file {
+        app.pick_import_file(ctx.clone());
+    }
+    if roll_back {
+        app.roll_back_import(ctx.input(|input| input.time));
+    }
+    if apply {
+        app.apply_import(ctx.input(|input| input.time));
+    }
+    if !open {
+        app.ui_state.set_show_import_window(false);
+    }
+}
+
+/// Lists the automatic snapshots, newest first, and restores one on request.
+fn render_history_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_history_window() {
+        return;
+    }
+
+    let mut open = true;
+    let mut restore = None;
+    egui::Window::new("History").collapsible(false).open(&mut open).show(ctx, |ui| {
+        ui.label("Snapshots of local days, taken before changes that discard or overwrite entries.");
+        ui.separator();
+        if app.snapshots().iter().next().is_none() {
+            ui.label("No snapshots yet.");
+            return;
+        }
+        egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+            egui::Grid::new("history_snapshots").striped(true).num_columns(4).show(ui, |ui| {
+                for snapshot in app.snapshots().iter() {
+                    ui.label(snapshot.taken_at_label());
+                    ui.label(&snapshot.reason);
+                    ui.label(snapshot.scope_label());
+                    if ui
+                        .button("Restore")
+                        .on_hover_text("Put back the days of this snapshot. The days it replaces are kept as a new snapshot.")
+                        .clicked()
+                    {
+                        restore = Some(snapshot.id);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    });
+
+    if let Some(id) = restore {
+        app.restore_snapshot(id, ctx.input(|input| input.time));
+    }
+    if !open {
+        app.ui_state.set_show_history_window(false);
+    }
+}
+
+/// Merges days the server rejected as stale, one day at a time: every entry
+/// of either version is listed with where it appears, and the checked ones
+/// make up the saved day.
+fn render_conflicts_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_conflicts_window() {
+        return;
+    }
+
+    let conflicts = app.sync.conflicts().clone();
+    let merge = app.ui_state.conflict_merge_mut();
+    if merge.as_ref().map_or(true, |merge| !conflicts.contains_key(&merge.date)) {
+        *merge = conflicts.iter().next().map(|(date, conflict)| ConflictMerge::new(*date, conflict));
+    }
+    let mut open = true;
+    let mut resolve = None;
+    egui::Window::new("Conflicts").collapsible(false).open(&mut open).show(ctx, |ui| {
+        let Some(merge) = app.ui_state.conflict_merge_mut() else {
+            ui.label("No conflicts left.");
+            return;
+        };
+        ui.label("These days were saved on another device while this one had unsaved edits of them.");
+        ui.horizontal_wrapped(|ui| {
+            for (date, conflict) in &conflicts {
+                let selected = *date == merge.date;
+                if ui.selectable_label(selected, date.format("%a %Y-%m-%d").to_string()).clicked() && !selected {
+                    *merge = ConflictMerge::new(*date, conflict);
+                }
+            }
+        });
+        ui.separator();
+
+        let conflict = &conflicts[&merge.date];
+        let mark = |present: bool| if present { "✔" } else { "" };
+        let settings = |day: &crate::supabase::WorkDayDraft| {
+            let target = format!("{}:{:02}", day.target_minutes / 60, day.target_minutes % 60);
+            if day.enabled {
+                target
+            } else {
+                format!("{target}, off")
+            }
+        };
+        egui::Grid::new("conflict_entries").striped(true).num_columns(5).show(ui, |ui| {
+            for header in ["Entry", "Base", "This device", "Server", "Keep"] {
+                ui.label(RichText::new(header).strong());
+            }
+            ui.end_row();
+            for entry in &mut merge.entries {
+                ui.label(entry.label());
+                ui.label(mark(entry.in_base));
+                ui.label(mark(entry.in_local));
+                ui.label(mark(entry.in_server));
+                ui.checkbox(&mut entry.keep, "");
+                ui.end_row();
+            }
+            ui.label("Target");
+            ui.label(conflict.base.as_ref().map_or_else(String::new, settings));
+            ui.radio_value(&mut merge.settings_from, Side::Local, settings(&conflict.local));
+            ui.radio_value(&mut merge.settings_from, Side::Server, settings(&conflict.server));
+            ui.end_row();
+        });
+        if conflict.base.is_none() {
+            ui.label("This device never saw a server version of this day, so nothing counts as removed.");
+        }
+        let merged = merge.merged(conflict);
+        let preview: Vec<String> = merged.work_entries.iter().map(entry_label).collect();
+        ui.label(format!(
+            "Saves: {}",
+            if preview.is_empty() {
+                "no entries".to_string()
+            } else {
+                preview.join(", ")
+            }
+        ));
+
+        ui.horizontal(|ui| {
+            if ui.button("Keep this device's day").clicked() {
+                merge.take_side(Side::Local);
+            }
+            if ui.button("Take the server's day").clicked() {
+                merge.take_side(Side::Server);
+            }
+            if ui.button("Save merged day").clicked() {
+                resolve = Some(merge.clone());
+            }
+        });
+    });
+
+    if let Some(merge) = resolve {
+        app.resolve_conflict(&merge, ctx.input(|input| input.time));
+    }
+    if !open {
+        app.ui_state.set_show_conflicts_window(false);
+    }
+}
+
+/// Confirms replacing all local data with a picked backup file.
+fn render_restore_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    let Some(pending) = app.ui_state.pending_restore() else {
+        return;
+    };
+    let current_user = app.sync.stored_session.as_ref().map(|session| session.user_id.as_str());
+    let mut open = true;
+    let mut restore = false;
+    egui::Window::new("Restore backup")
+        .collapsible(false)
+        .resizable(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(RichText::new(&pending.file_name).strong());
+            let backup = match &pending.backup {
+                Ok(backup) => backup,
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                    return;
+                }
+            };
+            egui::Grid::new("restore_summary").num_columns(2).show(ui, |ui| {
+                ui.label("Made:");
+                ui.label(
+                    backup
+                        .created_at
+                        .map_or_else(|| "unknown".to_string(), |created_at| created_at.date().to_string()),
+                );
+                ui.end_row();
+                ui.label("Days:");
+                let range = backup.days.first().zip(backup.days.last());
+                ui.label(match range {
+                    Some((first, last)) => format!("{} ({} – {})", backup.days.len(), first.date, last.date),
+                    None => "none".to_string(),
+                });
+                ui.end_row();
+                ui.label("Account:");
+                ui.label(
+                    backup
+                        .sync
+                        .email
+                        .as_deref()
+                        .or(backup.sync.user_id.as_deref())
+                        .unwrap_or("not synced"),
+                );
+                ui.end_row();
+            });
+            if backup.sync.user_id.is_some() && current_user.is_some() && backup.sync.user_id.as_deref() != current_user {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    "This backup belongs to another account. Saving restored weeks writes them to the current account.",
+                );
+            }
+            ui.separator();
+            ui.label("All local days and settings are replaced, and changed days are saved to the server. The current data is kept as a snapshot.");
+            restore = ui.button("Restore").clicked();
+        });
+
+    if restore {
+        app.restore_backup(ctx.input(|input| input.time));
+    } else if !open {
+        app.ui_state.set_pending_restore(None);
+    }
+}
+
+/// Picks events of the visible week from an iCalendar file. Navigating to
+/// another week re-reads the file for that week.
+fn render_calendar_window(app: &mut TemplateApp, ctx: &egui::Context) {
+    if !app.ui_state.show_calendar_window() {
+        return;
+    }
+
+    let now = duration::local_now();
+    let mut open = true;
+    let mut pick_file = false;
+    let mut apply = false;
+    egui::Window::new("Import calendar")
+        .collapsible(false)
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let week = app.state.current_week_key();
+            ui.horizontal(|ui| {
+                pick_file = ui.button("Choose file…").clicked();
+                ui.label(format!("Week {} of {}", week.week_nr, week.year));
+            });
+
+            let Some(calendar) = app.ui_state.calendar_import_mut() else {
+                ui.label("Pick an .ics file to add its events of the visible week as entries.");
+                return;
+            };
+            calendar.refresh(&app.state, now);
+            ui.label(RichText::new(&calendar.file_name).strong());
+            let events = match &calendar.events {
+                Ok(events) => events,
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                    return;
+                }
+            };
+            if events.is_empty() {
+                ui.label("No timed events in this week.");
+                return;
+            }
+
+            let statuses = ical::event_statuses(app.state.days(), events, &calendar.selected, now);
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                egui::Grid::new("calendar_events").striped(true).num_columns(4).show(ui, |ui| {
+                    for ((event, selected), status) in events.iter().zip(calendar.selected.iter_mut()).zip(&statuses) {
+                        ui.add_enabled(*status == EventStatus::New, egui::Checkbox::without_text(selected));
+                        ui.label(event.start.format("%a %d").to_string());
+                        let times = format!("{} – {}", event.start.format("%H:%M"), event.end.format("%H:%M"));
+                        if event.approximate {
+                            ui.label(format!("≈ {times}"))
+                                .on_hover_text("The event's time zone is not defined in the file, so its time is read as local time");
+                        } else {
+                            ui.label(times);
+                        }
+                        let summary = ui.label(&event.summary);
+                        match status {
+                            EventStatus::New => {}
+                            EventStatus::Overlaps => {
+                                summary.on_hover_text("Overlaps a recorded entry or another selected event");
+                            }
+                            EventStatus::NotShown => {
+                                summary.on_hover_text("This weekday is hidden in the week schedule");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+
+            let count = calendar
+                .selected
+                .iter()
+                .zip(&statuses)
+                .filter(|(selected, status)| **selected && **status == EventStatus::New)
+                .count();
+            ui.separator();
+            apply = ui
+                .add_enabled(count > 0, egui::Button::new(format!("Add {count} events")))
+                .on_disabled_hover_text("Select events that do not overlap recorded entries")
+                .clicked();
+        });
+
+    if pick_file {
+        app.pick_calendar_file(ctx.clone());
+    }
+    if apply {
+        app.apply_calendar_import();
+    }
+    if !open {
+        app.ui_state.set_show_calendar_window(false);
+    }
+}
+
+/// Column and format choices for a CSV import.
+fn import_mapping_editor(ui: &mut egui::Ui, mapping: &mut CsvMapping, header: &[String]) {
+    egui::Grid::new("import_mapping").num_columns(2).show(ui, |ui| {
+        ui.label("Separator:");
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut mapping.delimiter, b',', "Comma");
+            ui.selectable_value(&mut mapping.delimiter, b';', "Semicolon");
+            ui.selectable_value(&mut mapping.delimiter, b'\t', "Tab");
+            ui.checkbox(&mut mapping.has_header, "Header row");
+        });
+        ui.end_row();
+
+        ui.label("Date:");
+        ui.horizontal(|ui| {
+            column_combo(ui, "import_date_column", header, &mut mapping.date);
+            ui.label("format");
+            ui.add(egui::TextEdit::singleline(&mut mapping.date_format).desired_width(80.0))
+                .on_hover_text("chrono format, e.g. %Y-%m-%d or %d/%m/%Y");
+        });
+        ui.end_row();
+
+        ui.label("Times:");
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut mapping.time_columns, TimeColumns::StartEnd, "Start and end");
+            ui.selectable_value(&mut mapping.time_columns, TimeColumns::Duration, "Duration");
+        });
+        ui.end_row();
+
+        match mapping.time_columns {
+            TimeColumns::StartEnd => {
+                ui.label("Start:");
+                column_combo(ui, "import_start_column", header, &mut mapping.start);
+                ui.end_row();
+                ui.label("End:");
+                column_combo(ui, "import_end_column", header, &mut mapping.end);
+                ui.end_row();
+            }
+            TimeColumns::Duration => {
+                ui.label("Duration:");
+                column_combo(ui, "import_duration_column", header, &mut mapping.duration)
+                    .on_hover_text("H:MM or decimal hours; entries are placed after the day's last entry");
+                ui.end_row();
+            }
+        }
+
+        ui.label("Project:");
+        optional_column_combo(ui, "import_project_column", header, &mut mapping.project);
+        ui.end_row();
+        ui.label("Note:");
+        optional_column_combo(ui, "import_note_column", header, &mut mapping.note);
+        ui.end_row();
+    });
+}
+
+fn column_combo(ui: &mut egui::Ui, id_salt: &str, header: &[String], column: &mut usize) -> egui::Response {
+    let selected = header.get(*column).map_or("-", String::as_str);
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (ix, name) in header.iter().enumerate() {
+                ui.selectable_value(column, ix, name);
+            }
+        })
+        .response
+}
+
+fn optional_column_combo(ui: &mut egui::Ui, id_salt: &str, header: &[String], column: &mut Option<usize>) {
+    let selected = column.and_then(|ix| header.get(ix)).map_or("(none)", String::as_str);
+    egui::ComboBox::from_id_salt(id_salt).selected_text(selected).show_ui(ui, |ui| {
+        ui.selectable_value(column, None, "(none)");
+        for (ix, name) in header.iter().enumerate() {
+            ui.selectable_value(column, Some(ix), name);
+        }
+    });
+}
+
+/// Week / month / custom range picker shared by the report and export views.
+fn period_range_selector(ui: &mut egui::Ui, period: &mut ReportPeriod, range: &mut report::DateRange) {
+    ui.horizontal(|ui| {
+        let before = *period;
+        ui.selectable_value(period, ReportPeriod::Week, "Week");
+        ui.selectable_value(period, ReportPeriod::Month, "Month");
+        ui.selectable_value(period, ReportPeriod::Custom, "Custom");
+        if *period != before && *period != ReportPeriod::Custom {
+            *range = period.range_containing(range.0);
+        }
+        ui.separator();
+
+        if ui.button("<").clicked() {
+            *range = period.previous(*range);
+        }
+        if *period == ReportPeriod::Custom {
+            date_editor(ui, &mut range.0);
+            ui.label("to");
+            date_editor(ui, &mut range.1);
+            range.1 = range.1.max(range.0);
+        } else {
+            ui.label(RichText::new(format!("{} to {}", range.0, range.1)).strong());
+        }
+        if ui.button(">").clicked() {
+            *range = period.next(*range);
+        }
+    });
+}
+
+fn contract_profile_combo(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, profile: &mut ContractProfile) {
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(profile.name.clone())
+        .show_ui(ui, |ui| {
+            for preset in ContractProfile::presets() {
+                let selected = &preset == profile;
+                if ui.selectable_label(selected, &preset.name).clicked() {
+                    *profile = preset;
+                }
+            }
+        });
+}
+
+/// Edits a date as year/month/day fields, clamping the day to the month length.
+fn date_editor(ui: &mut egui::Ui, date: &mut NaiveDate) {
+    let mut year = date.year();
+    let mut month = date.month();
+    let mut day = date.day();
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        ui.add(egui::DragValue::new(&mut year).range(2000..=2100));
+        ui.label("-");
+        ui.add(egui::DragValue::new(&mut month).range(1..=12));
+        ui.label("-");
+        ui.add(egui::DragValue::new(&mut day).range(1..=31));
+    });
+    if let Some(new_date) = (1..=day).rev().find_map(|day| NaiveDate::from_ymd_opt(year, month, day)) {
+        *date = new_date;
+    }
+}
+
+/// Edits a whole-minute target as separate hour and minute fields.
+fn target_editor(ui: &mut egui::Ui, target: &mut time::Duration) {
+    let mut hours = target.whole_hours();
+    let mut minutes = target.whole_minutes() % 60;
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut hours).range(0..=24).suffix("h"));
+        ui.add(egui::DragValue::new(&mut minutes).range(0..=59).suffix("m"));
+    });
+    *target = time::Duration::hours(hours) + time::Duration::minutes(minutes);
+}
+
 fn render_main_panel(app: &mut TemplateApp, ctx: &egui::Context, _frame: &mut eframe::Frame) {
     egui::CentralPanel::default().show(ctx, |ui| {
         if let Some(status) = app.ui_state.status_message() {
@@ -182,46 +1189,260 @@ fn render_main_panel(app: &mut TemplateApp, ctx: &egui::Context, _frame: &mut ef
         if app.ui_state.status_message().is_some() || app.ui_state.error_message().is_some() {
             ui.separator();
         }
-
-        ui.horizontal_wrapped(|ui| {
-            for day in app.state.days_mut() {
-                ui.separator();
-                ui.vertical(|ui| {
-                    day.ui(ui);
-                });
-            }
+        if app.sync.is_changed_elsewhere(&app.state) {
+            ui.horizontal(|ui| {
+                ui.colored_label(Color32::YELLOW, "This week was changed on another device.");
+                if ui
+                    .button("Reload")
+                    .on_hover_text("Load the server version; days with unsaved edits keep them")
+                    .clicked()
+                {
+                    app.request_visible_week_load(ctx.clone());
+                }
+                if ui.button("Dismiss").clicked() {
+                    app.sync.dismiss_changed_elsewhere();
+                }
+            });
             ui.separator();
+        }
+
+        match app.ui_state.view() {
+            OverviewView::Week => render_week(app, ui),
+            OverviewView::Month => render_month_overview(app, ui, ctx),
+            OverviewView::Year => render_year_overview(app, ui, ctx),
+            OverviewView::Report => render_report(app, ui, ctx),
+        }
+
+        ui.separator();
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
+            powered_by_egui_and_eframe(ui);
+            egui::warn_if_debug_build(ui);
         });
+    });
+}
 
+fn render_week(app: &mut TemplateApp, ui: &mut egui::Ui) {
+    let mut cleared = None;
+    let rules = app.state.break_rules().clone();
+    ui.horizontal_wrapped(|ui| {
+        for day in app.state.days_mut() {
+            ui.separator();
+            ui.vertical(|ui| {
+                if let Some(entries) = day.ui(ui, &rules) {
+                    cleared = Some((day.date, entries));
+                }
+            });
+        }
         ui.separator();
+    });
+    if let Some((date, entries)) = cleared {
+        app.snapshot_cleared_day(date, entries);
+    }
 
-        egui::Grid::new("total_grid").striped(true).min_col_width(80.0).show(ui, |ui| {
-            let duration_days = app.duration();
-            let target_days = app.total_target();
-            ui.label("Week Target:");
-            ui.label(duration::format_duration(target_days, duration::DURATION_FORMAT));
-            ui.end_row();
-            ui.label("Week Total:");
-            ui.label(duration::format_duration(duration_days, duration::DURATION_FORMAT));
+    ui.separator();
+
+    egui::Grid::new("total_grid").striped(true).min_col_width(80.0).show(ui, |ui| {
+        let duration_days = app.duration();
+        let target_days = app.total_target();
+        ui.label("Week Target:");
+        ui.label(duration::format_duration(target_days, duration::DURATION_FORMAT));
+        ui.end_row();
+        ui.label("Week Total:");
+        ui.label(duration::format_duration(duration_days, duration::DURATION_FORMAT));
+        ui.end_row();
+        ui.label("Week Todo:");
+        let todo = target_days - duration_days;
+        let sign = if todo.is_negative() { "-" } else { "" };
+        ui.label(format!(
+            "{}{}",
+            sign,
+            duration::format_duration(todo.abs(), duration::DURATION_FORMAT)
+        ));
+        ui.end_row();
+    });
+}
+
+fn status_color(ui: &egui::Ui, status: DayStatus) -> Color32 {
+    match status {
+        DayStatus::Done => Color32::GREEN,
+        DayStatus::Partial => Color32::YELLOW,
+        DayStatus::Missed => Color32::RED,
+        DayStatus::Upcoming => ui.visuals().text_color(),
+        DayStatus::Off => ui.visuals().weak_text_color(),
+    }
+}
+
+fn render_month_overview(app: &mut TemplateApp, ui: &mut egui::Ui, ctx: &egui::Context) {
+    let (year, month) = app.ui_state.overview_month();
+    if let Some(range) = overview::month_range(year, month) {
+        app.request_history_range(ctx.clone(), range);
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("<").clicked() {
+            app.ui_state.set_overview_month(year, month as i32 - 1);
+        }
+        let title = NaiveDate::from_ymd_opt(year, month, 1).map_or_else(String::new, |first| first.format("%B %Y").to_string());
+        ui.label(RichText::new(title).strong());
+        if ui.button(">").clicked() {
+            app.ui_state.set_overview_month(year, month as i32 + 1);
+        }
+        if app.sync.is_loading_history() {
+            ui.spinner();
+        }
+    });
+
+    let today = chrono::Local::now().date_naive();
+    let rows = overview::month_rows(&app.state, app.sync.history_days(), year, month, today);
+    let mut open_week = None;
+    egui::Grid::new("month_grid").striped(true).min_col_width(90.0).show(ui, |ui| {
+        ui.label("Week");
+        for weekday in WEEKDAYS {
+            ui.label(RichText::new(weekday.to_string()).strong());
+        }
+        ui.end_row();
+
+        for (week, cells) in &rows {
+            if ui.link(week.week_nr.to_string()).on_hover_text("Open this week").clicked() {
+                open_week = Some(*week);
+            }
+            for cell in cells {
+                let Some(day) = cell else {
+                    ui.label("");
+                    continue;
+                };
+                let text = format!(
+                    "{}\n{} / {}",
+                    day.date.day(),
+                    duration::format_duration(day.done, duration::DURATION_FORMAT),
+                    duration::format_duration(day.target, duration::DURATION_FORMAT)
+                );
+                let mut label = RichText::new(text).color(status_color(ui, day.status));
+                if day.date == today {
+                    label = label.strong();
+                }
+                if ui.add(egui::Button::new(label).frame(false)).clicked() {
+                    open_week = Some(*week);
+                }
+            }
             ui.end_row();
-            ui.label("Week Todo:");
-            let todo = target_days - duration_days;
-            let sign = if todo.is_negative() { "-" } else { "" };
-            ui.label(format!(
-                "{}{}",
-                sign,
-                duration::format_duration(todo.abs(), duration::DURATION_FORMAT)
-            ));
+        }
+    });
+
+    if let Some(week) = open_week {
+        app.open_week(ctx.clone(), week);
+    }
+}
+
+fn render_year_overview(app: &mut TemplateApp, ui: &mut egui::Ui, ctx: &egui::Context) {
+    let year = app.ui_state.overview_year();
+    if let Some(range) = overview::year_range(year) {
+        app.request_history_range(ctx.clone(), range);
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("<").clicked() {
+            app.ui_state.set_overview_year(year - 1);
+        }
+        ui.label(RichText::new(year.to_string()).strong());
+        if ui.button(">").clicked() {
+            app.ui_state.set_overview_year(year + 1);
+        }
+        if app.sync.is_loading_history() {
+            ui.spinner();
+        }
+    });
+
+    let today = chrono::Local::now().date_naive();
+    let weeks = overview::year_weeks(&app.state, app.sync.history_days(), year, today);
+    let mut open_week = None;
+    egui::ScrollArea::vertical().auto_shrink([false, true]).show(ui, |ui| {
+        egui::Grid::new("year_grid").striped(true).min_col_width(80.0).show(ui, |ui| {
+            for header in ["Week", "Starts", "Done", "Target", "Balance"] {
+                ui.label(RichText::new(header).strong());
+            }
             ui.end_row();
+
+            for week in &weeks {
+                if ui.link(week.week.week_nr.to_string()).on_hover_text("Open this week").clicked() {
+                    open_week = Some(week.week);
+                }
+                ui.label(week.monday.format("%d %b").to_string());
+                ui.label(duration::format_duration(week.done, duration::DURATION_FORMAT));
+                ui.label(duration::format_duration(week.target, duration::DURATION_FORMAT));
+                match week.balance {
+                    Some(balance) => {
+                        let color = if balance.is_negative() { Color32::RED } else { Color32::GREEN };
+                        ui.colored_label(color, duration::format_signed_duration(balance, duration::DURATION_FORMAT));
+                    }
+                    None => {
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
         });
+    });
 
-        ui.separator();
+    if let Some(week) = open_week {
+        app.open_week(ctx.clone(), week);
+    }
+}
 
-        ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
-            powered_by_egui_and_eframe(ui);
-            egui::warn_if_debug_build(ui);
-        });
+fn render_report(app: &mut TemplateApp, ui: &mut egui::Ui, ctx: &egui::Context) {
+    let today = chrono::Local::now().date_naive();
+    let mut period = app.ui_state.report_period();
+    let mut range = app.ui_state.report_range(today);
+
+    ui.horizontal(|ui| {
+        period_range_selector(ui, &mut period, &mut range);
+        if app.sync.is_loading_history() {
+            ui.spinner();
+        }
     });
+    app.ui_state.set_report(period, range);
+
+    let previous = period.previous(range);
+    app.request_history_range(ctx.clone(), (previous.0, range.1));
+    let report = report::project_report(&app.state, app.sync.history_days(), range, previous, duration::local_now());
+
+    ui.label(format!("Compared with {} to {}", previous.0, previous.1));
+    egui::Grid::new("report_grid").striped(true).min_col_width(80.0).show(ui, |ui| {
+        for header in ["Project", "Hours", "Share", "Previous", "Change"] {
+            ui.label(RichText::new(header).strong());
+        }
+        ui.end_row();
+
+        for row in &report.rows {
+            ui.label(&row.project);
+            ui.label(duration::format_duration(row.current, duration::DURATION_FORMAT));
+            ui.label(format!("{:.0}%", report.share_percent(row)));
+            ui.label(duration::format_duration(row.previous, duration::DURATION_FORMAT));
+            change_label(ui, row.current - row.previous);
+            ui.end_row();
+        }
+
+        ui.label(RichText::new("Total").strong());
+        ui.label(RichText::new(duration::format_duration(report.total, duration::DURATION_FORMAT)).strong());
+        ui.label("");
+        ui.label(duration::format_duration(report.previous_total, duration::DURATION_FORMAT));
+        change_label(ui, report.total - report.previous_total);
+        ui.end_row();
+    });
+    if report.rows.is_empty() {
+        ui.label("No entries in this period.");
+    }
+}
+
+fn change_label(ui: &mut egui::Ui, change: time::Duration) {
+    let text = duration::format_signed_duration(change, duration::DURATION_FORMAT);
+    if change.is_zero() {
+        ui.label(text);
+    } else {
+        let color = if change.is_negative() { Color32::RED } else { Color32::GREEN };
+        ui.colored_label(color, text);
+    }
 }
 
 fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
@@ -1,5 +1,22 @@
 use crate::supabase::StoredSession;
 
+use super::backup::PendingRestore;
+use super::conflict::ConflictMerge;
+use super::export::ExportFormat;
+use super::ical::CalendarImport;
+use super::import::ImportSession;
+use super::overview::OverviewView;
+use super::report::{DateRange, ReportPeriod};
+
+/// How the login window asks for credentials.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum LoginMethod {
+    #[default]
+    Password,
+    /// A magic link or one-time code sent by email.
+    EmailCode,
+}
+
 // Purely UI-facing state for auth and sync surfaces. Keeping this separate from
 // `SyncState` lets the sync layer focus on orchestration rather than form fields
 // and banners.
@@ -9,9 +26,58 @@ pub struct AppUiState {
     #[serde(skip)]
     show_login_window: bool,
     #[serde(skip)]
+    show_schedule_window: bool,
+    #[serde(skip)]
+    show_balance_window: bool,
+    #[serde(skip)]
+    show_break_rules_window: bool,
+    #[serde(skip)]
+    view: OverviewView,
+    #[serde(skip)]
+    overview_year: i32,
+    #[serde(skip)]
+    overview_month: u32,
+    #[serde(skip)]
+    report_period: ReportPeriod,
+    #[serde(skip)]
+    report_range: Option<DateRange>,
+    #[serde(skip)]
+    show_export_window: bool,
+    #[serde(skip)]
+    export_period: ReportPeriod,
+    #[serde(skip)]
+    export_range: Option<DateRange>,
+    #[serde(skip)]
+    export_day_summary: bool,
+    #[serde(skip)]
+    export_format: ExportFormat,
+    #[serde(skip)]
+    show_import_window: bool,
+    #[serde(skip)]
+    import_session: Option<ImportSession>,
+    #[serde(skip)]
+    show_calendar_window: bool,
+    #[serde(skip)]
+    calendar_import: Option<CalendarImport>,
+    #[serde(skip)]
+    pending_restore: Option<PendingRestore>,
+    #[serde(skip)]
+    show_history_window: bool,
+    #[serde(skip)]
+    show_conflicts_window: bool,
+    #[serde(skip)]
+    conflict_merge: Option<ConflictMerge>,
+    #[serde(skip)]
     login_email: String,
     #[serde(skip)]
     login_password: String,
+    /// Remembered so users without a password land on the email tab.
+    login_method: LoginMethod,
+    #[serde(skip)]
+    login_code: String,
+    /// Address the last login email went to, while its code is awaited.
+    #[serde(skip)]
+    login_code_sent_to: Option<String>,
     #[serde(skip)]
     status_message: Option<String>,
     #[serde(skip)]
@@ -55,6 +121,172 @@ impl AppUiState {
         self.show_login_window = show;
     }
 
+    pub fn show_schedule_window(&self) -> bool {
+        self.show_schedule_window
+    }
+
+    pub fn set_show_schedule_window(&mut self, show: bool) {
+        self.show_schedule_window = show;
+    }
+
+    pub fn show_balance_window(&self) -> bool {
+        self.show_balance_window
+    }
+
+    pub fn set_show_balance_window(&mut self, show: bool) {
+        self.show_balance_window = show;
+    }
+
+    pub fn show_break_rules_window(&self) -> bool {
+        self.show_break_rules_window
+    }
+
+    pub fn set_show_break_rules_window(&mut self, show: bool) {
+        self.show_break_rules_window = show;
+    }
+
+    pub(crate) fn view(&self) -> OverviewView {
+        self.view
+    }
+
+    pub(crate) fn set_view(&mut self, view: OverviewView) {
+        self.view = view;
+    }
+
+    /// Month shown by the month overview as `(year, month)`.
+    pub fn overview_month(&self) -> (i32, u32) {
+        (self.overview_year, self.overview_month)
+    }
+
+    /// Selects a month, wrapping `month` outside 1..=12 into adjacent years.
+    pub fn set_overview_month(&mut self, year: i32, month: i32) {
+        let months = year * 12 + month - 1;
+        self.overview_year = months.div_euclid(12);
+        self.overview_month = months.rem_euclid(12) as u32 + 1;
+    }
+
+    pub fn overview_year(&self) -> i32 {
+        self.overview_year
+    }
+
+    pub fn set_overview_year(&mut self, year: i32) {
+        self.overview_year = year;
+    }
+
+    pub(crate) fn report_period(&self) -> ReportPeriod {
+        self.report_period
+    }
+
+    /// Range shown by the report, defaulting to the period containing `today`.
+    pub(crate) fn report_range(&self, today: chrono::NaiveDate) -> DateRange {
+        self.report_range.unwrap_or_else(|| self.report_period.range_containing(today))
+    }
+
+    pub(crate) fn set_report(&mut self, period: ReportPeriod, range: DateRange) {
+        self.report_period = period;
+        self.report_range = Some(range);
+    }
+
+    pub fn show_export_window(&self) -> bool {
+        self.show_export_window
+    }
+
+    /// Opens or closes the export window. Opening it starts from `range`.
+    pub(crate) fn set_show_export_window(&mut self, show: bool, range: DateRange) {
+        if show && !self.show_export_window {
+            self.export_period = ReportPeriod::Week;
+            self.export_range = Some(range);
+        }
+        self.show_export_window = show;
+    }
+
+    pub(crate) fn export_selection(&self, today: chrono::NaiveDate) -> (ReportPeriod, DateRange, bool) {
+        let range = self.export_range.unwrap_or_else(|| self.export_period.range_containing(today));
+        (self.export_period, range, self.export_day_summary)
+    }
+
+    pub(crate) fn set_export_selection(&mut self, period: ReportPeriod, range: DateRange, day_summary: bool) {
+        self.export_period = period;
+        self.export_range = Some(range);
+        self.export_day_summary = day_summary;
+    }
+
+    pub(crate) fn export_format(&self) -> ExportFormat {
+        self.export_format
+    }
+
+    pub(crate) fn set_export_format(&mut self, format: ExportFormat) {
+        self.export_format = format;
+    }
+
+    pub(crate) fn show_import_window(&self) -> bool {
+        self.show_import_window
+    }
+
+    /// Closing the import window also drops the picked file.
+    pub(crate) fn set_show_import_window(&mut self, show: bool) {
+        self.show_import_window = show;
+        if !show {
+            self.import_session = None;
+        }
+    }
+
+    pub(crate) fn import_session_mut(&mut self) -> Option<&mut ImportSession> {
+        self.import_session.as_mut()
+    }
+
+    pub(crate) fn set_import_session(&mut self, session: Option<ImportSession>) {
+        self.import_session = session;
+    }
+
+    pub(crate) fn show_calendar_window(&self) -> bool {
+        self.show_calendar_window
+    }
+
+    /// Closing the calendar window also drops the picked file.
+    pub(crate) fn set_show_calendar_window(&mut self, show: bool) {
+        self.show_calendar_window = show;
+        if !show {
+            self.calendar_import = None;
+        }
+    }
+
+    pub(crate) fn calendar_import_mut(&mut self) -> Option<&mut CalendarImport> {
+        self.calendar_import.as_mut()
+    }
+
+    pub(crate) fn set_calendar_import(&mut self, calendar: Option<CalendarImport>) {
+        self.calendar_import = calendar;
+    }
+
+    pub(crate) fn pending_restore(&self) -> Option<&PendingRestore> {
+        self.pending_restore.as_ref()
+    }
+
+    pub(crate) fn set_pending_restore(&mut self, restore: Option<PendingRestore>) {
+        self.pending_restore = restore;
+    }
+
+    pub(crate) fn show_history_window(&self) -> bool {
+        self.show_history_window
+    }
+
+    pub(crate) fn set_show_history_window(&mut self, show: bool) {
+        self.show_history_window = show;
+    }
+
+    pub(crate) fn show_conflicts_window(&self) -> bool {
+        self.show_conflicts_window
+    }
+
+    pub(crate) fn set_show_conflicts_window(&mut self, show: bool) {
+        self.show_conflicts_window = show;
+    }
+
+    pub(crate) fn conflict_merge_mut(&mut self) -> &mut Option<ConflictMerge> {
+        &mut self.conflict_merge
+    }
+
     pub fn login_email_mut(&mut self) -> &mut String {
         &mut self.login_email
     }
@@ -75,6 +307,31 @@ impl AppUiState {
         self.login_password.clear();
     }
 
+    pub(crate) fn login_method(&self) -> LoginMethod {
+        self.login_method
+    }
+
+    pub(crate) fn login_method_mut(&mut self) -> &mut LoginMethod {
+        &mut self.login_method
+    }
+
+    pub fn login_code_mut(&mut self) -> &mut String {
+        &mut self.login_code
+    }
+
+    pub fn login_code(&self) -> &str {
+        &self.login_code
+    }
+
+    pub fn login_code_sent_to(&self) -> Option<&str> {
+        self.login_code_sent_to.as_deref()
+    }
+
+    pub fn set_login_code_sent_to(&mut self, email: Option<String>) {
+        self.login_code_sent_to = email;
+        self.login_code.clear();
+    }
+
     pub fn set_login_email(&mut self, email: String) {
         self.login_email = email;
     }
@@ -0,0 +1,478 @@
+// Operation-based undo history for the visible week. Each frame the visible
+// days are compared with the last seen copy and the difference is recorded as
+// small edits (entry added, removed or changed, day toggled, target changed)
+// instead of whole `State` copies. The log is kept per ISO week and persisted
+// with the app, so undo survives restarts and week loads, and its size depends
+// only on the number of recorded steps, not on the amount of stored history.
+// Undone edits find their entry by content, so they still apply after a
+// reload from the server gave the rows new ids.
+
+use std::collections::VecDeque;
+
+use chrono::NaiveDate;
+
+use super::state::{State, WeekKey};
+use crate::ui;
+
+/// Undo steps kept per week; the oldest step is dropped beyond this.
+const MAX_STEPS: usize = 100;
+/// Weeks with history kept; the least recently edited week is dropped first.
+const MAX_WEEKS: usize = 12;
+/// Changes to the same entries within this many seconds form one step, so
+/// typing a note or a time undoes as a whole.
+const MERGE_SECONDS: f64 = 1.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub(crate) enum WeekEdit {
+    AddEntry {
+        date: NaiveDate,
+        index: usize,
+        entry: ui::Duration,
+    },
+    RemoveEntry {
+        date: NaiveDate,
+        index: usize,
+        entry: ui::Duration,
+    },
+    EditEntry {
+        date: NaiveDate,
+        index: usize,
+        before: ui::Duration,
+        after: ui::Duration,
+    },
+    SetEnabled {
+        date: NaiveDate,
+        before: bool,
+        after: bool,
+    },
+    SetTarget {
+        date: NaiveDate,
+        before: time::Duration,
+        after: time::Duration,
+    },
+}
+
+impl WeekEdit {
+    fn inverse(&self) -> Self {
+        match self.clone() {
+            Self::AddEntry { date, index, entry } => Self::RemoveEntry { date, index, entry },
+            Self::RemoveEntry { date, index, entry } => Self::AddEntry { date, index, entry },
+            Self::EditEntry {
+                date,
+                index,
+                before,
+                after,
+            } => Self::EditEntry {
+                date,
+                index,
+                before: after,
+                after: before,
+            },
+            Self::SetEnabled { date, before, after } => Self::SetEnabled {
+                date,
+                before: after,
+                after: before,
+            },
+            Self::SetTarget { date, before, after } => Self::SetTarget {
+                date,
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    /// Whether `later` changes the same entry or target again.
+    fn is_continued_by(&self, later: &Self) -> bool {
+        match (self, later) {
+            (
+                Self::EditEntry { date, index, .. },
+                Self::EditEntry {
+                    date: later_date,
+                    index: later_index,
+                    ..
+                },
+            ) => date == later_date && index == later_index,
+            (Self::SetTarget { date, .. }, Self::SetTarget { date: later_date, .. }) => date == later_date,
+            _ => false,
+        }
+    }
+
+    /// Folds a continuing change into this edit, keeping the original before.
+    fn merge(&mut self, later: &Self) {
+        match (self, later) {
+            (Self::EditEntry { after, .. }, Self::EditEntry { after: later_after, .. }) => *after = later_after.clone(),
+            (Self::SetTarget { after, .. }, Self::SetTarget { after: later_after, .. }) => *after = *later_after,
+            _ => {}
+        }
+    }
+
+    /// Applies the edit to `state`. Returns false when the day or entry it
+    /// refers to is gone, e.g. after it was changed on another device.
+    fn apply(&self, state: &mut State) -> bool {
+        let date = match self {
+            Self::AddEntry { date, .. }
+            | Self::RemoveEntry { date, .. }
+            | Self::EditEntry { date, .. }
+            | Self::SetEnabled { date, .. }
+            | Self::SetTarget { date, .. } => *date,
+        };
+        let Some(day) = state.days_mut().iter_mut().find(|day| day.date == date) else {
+            return false;
+        };
+        match self {
+            Self::AddEntry { index, entry, .. } => {
+                let index = (*index).min(day.durations.len());
+                day.durations.insert(index, entry.clone());
+            }
+            Self::RemoveEntry { index, entry, .. } => {
+                let Some(found) = find_entry(&day.durations, *index, entry) else {
+                    return false;
+                };
+                day.durations.remove(found);
+            }
+            Self::EditEntry { index, before, after, .. } => {
+                let Some(found) = find_entry(&day.durations, *index, before) else {
+                    return false;
+                };
+                // The entry keeps its identity, so the save updates its row.
+                let id = day.durations[found].id();
+                day.durations[found] = after.clone();
+                day.durations[found].set_id(id);
+            }
+            Self::SetEnabled { after, .. } => day.enabled = *after,
+            Self::SetTarget { after, .. } => day.set_target(*after),
+        }
+        true
+    }
+}
+
+/// Position of `entry`, preferring `index` when the entry is still there.
+fn find_entry(durations: &[ui::Duration], index: usize, entry: &ui::Duration) -> Option<usize> {
+    if durations.get(index).is_some_and(|found| found.same_entry(entry)) {
+        return Some(index);
+    }
+    durations.iter().position(|found| found.same_entry(entry))
+}
+
+/// The edits that turn `before` into `after`. Entries are compared after
+/// their common start and end, so an insert or removal anywhere in a day is
+/// a single edit instead of a chain of changed rows.
+fn diff_days(before: &[ui::Day], after: &[ui::Day]) -> Vec<WeekEdit> {
+    let mut edits = vec![];
+    for new in after {
+        let Some(old) = before.iter().find(|old| old.date == new.date) else {
+            continue;
+        };
+        let date = new.date;
+        if old.enabled != new.enabled {
+            edits.push(WeekEdit::SetEnabled {
+                date,
+                before: old.enabled,
+                after: new.enabled,
+            });
+        }
+        if old.configured_target() != new.configured_target() {
+            edits.push(WeekEdit::SetTarget {
+                date,
+                before: old.configured_target(),
+                after: new.configured_target(),
+            });
+        }
+
+        let (old, new) = (&old.durations, &new.durations);
+        let prefix = old.iter().zip(new).take_while(|(old, new)| old == new).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+        let (old, new) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+        for (offset, (before, after)) in old.iter().zip(new).enumerate() {
+            edits.push(WeekEdit::EditEntry {
+                date,
+                index: prefix + offset,
+                before: before.clone(),
+                after: after.clone(),
+            });
+        }
+        let paired = old.len().min(new.len());
+        // Removals go last to first so each recorded index is valid when the
+        // edits are replayed in order.
+        for (offset, entry) in old.iter().enumerate().skip(paired).rev() {
+            edits.push(WeekEdit::RemoveEntry {
+                date,
+                index: prefix + offset,
+                entry: entry.clone(),
+            });
+        }
+        for (offset, entry) in new.iter().enumerate().skip(paired) {
+            edits.push(WeekEdit::AddEntry {
+                date,
+                index: prefix + offset,
+                entry: entry.clone(),
+            });
+        }
+    }
+    edits
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+struct WeekLog {
+    week: WeekKey,
+    undo: VecDeque<Vec<WeekEdit>>,
+    redo: Vec<Vec<WeekEdit>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UndoLog {
+    /// Least recently edited week first.
+    weeks: Vec<WeekLog>,
+    /// The visible week as last recorded; edits are measured against it.
+    #[serde(skip)]
+    baseline: Option<(WeekKey, Vec<ui::Day>)>,
+    /// When the newest step last grew, to merge quick successive changes.
+    #[serde(skip)]
+    last_change: Option<f64>,
+}
+
+impl UndoLog {
+    /// Records what changed in the visible week since the last call. A
+    /// different visible week only moves the baseline.
+    pub(crate) fn feed(&mut self, time: f64, state: &State) {
+        let week = state.current_week_key();
+        let Some((baseline_week, days)) = &self.baseline else {
+            return self.rebase(state);
+        };
+        if *baseline_week != week {
+            return self.rebase(state);
+        }
+        if days.as_slice() == state.days() {
+            return;
+        }
+        let edits = diff_days(days, state.days());
+        if !edits.is_empty() {
+            self.record(week, edits, time);
+        }
+        self.baseline = Some((week, state.days().to_vec()));
+    }
+
+    /// Takes the visible week as it is without recording a change, e.g. after
+    /// it was loaded from the server.
+    pub(crate) fn rebase(&mut self, state: &State) {
+        self.baseline = Some((state.current_week_key(), state.days().to_vec()));
+        self.last_change = None;
+    }
+
+    /// Drops all history, e.g. when all data is reset.
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn record(&mut self, week: WeekKey, edits: Vec<WeekEdit>, time: f64) {
+        let merge = self.last_change.is_some_and(|last| time - last < MERGE_SECONDS);
+        let log = self.week_log(week);
+        log.redo.clear();
+        let step = log.undo.back_mut().filter(|step| {
+            // Only merge when every new edit continues one in the step.
+            merge && edits.iter().all(|edit| step.iter().any(|existing| existing.is_continued_by(edit)))
+        });
+        if let Some(step) = step {
+            for edit in &edits {
+                if let Some(existing) = step.iter_mut().find(|existing| existing.is_continued_by(edit)) {
+                    existing.merge(edit);
+                }
+            }
+        } else {
+            log.undo.push_back(edits);
+            if log.undo.len() > MAX_STEPS {
+                log.undo.pop_front();
+            }
+        }
+        self.last_change = Some(time);
+    }
+
+    /// The log of `week` for recording, created if needed and marked as most
+    /// recently used. Undo and redo only look up existing logs.
+    fn week_log(&mut self, week: WeekKey) -> &mut WeekLog {
+        let log = match self.weeks.iter().position(|log| log.week == week) {
+            Some(ix) => self.weeks.remove(ix),
+            None => WeekLog {
+                week,
+                undo: VecDeque::new(),
+                redo: vec![],
+            },
+        };
+        self.weeks.push(log);
+        if self.weeks.len() > MAX_WEEKS {
+            self.weeks.remove(0);
+        }
+        self.weeks.last_mut().expect("log was just pushed")
+    }
+
+    fn existing_log(&self, week: WeekKey) -> Option<&WeekLog> {
+        self.weeks.iter().find(|log| log.week == week)
+    }
+
+    fn existing_log_mut(&mut self, week: WeekKey) -> Option<&mut WeekLog> {
+        self.weeks.iter_mut().find(|log| log.week == week)
+    }
+
+    pub(crate) fn can_undo(&self, week: WeekKey) -> bool {
+        self.existing_log(week).is_some_and(|log| !log.undo.is_empty())
+    }
+
+    pub(crate) fn can_redo(&self, week: WeekKey) -> bool {
+        self.existing_log(week).is_some_and(|log| !log.redo.is_empty())
+    }
+
+    /// Reverts the newest step of the visible week. Returns false when some
+    /// of its edits no longer applied.
+    pub(crate) fn undo(&mut self, time: f64, state: &mut State) -> bool {
+        self.feed(time, state);
+        let week = state.current_week_key();
+        let Some(log) = self.existing_log_mut(week) else {
+            return true;
+        };
+        let Some(step) = log.undo.pop_back() else {
+            return true;
+        };
+        let mut applied = true;
+        for edit in step.iter().rev() {
+            applied &= edit.inverse().apply(state);
+        }
+        log.redo.push(step);
+        self.rebase(state);
+        applied
+    }
+
+    /// Repeats the newest undone step of the visible week.
+    pub(crate) fn redo(&mut self, time: f64, state: &mut State) -> bool {
+        self.feed(time, state);
+        let week = state.current_week_key();
+        let Some(log) = self.existing_log_mut(week) else {
+            return true;
+        };
+        let Some(step) = log.redo.pop() else {
+            return true;
+        };
+        let mut applied = true;
+        for edit in &step {
+            applied &= edit.apply(state);
+        }
+        log.undo.push_back(step);
+        self.rebase(state);
+        applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UndoLog, MAX_STEPS, MAX_WEEKS};
+    use crate::app::state::State;
+    use crate::ui;
+
+    fn entry(start: u8, end: u8) -> ui::Duration {
+        ui::Duration::from_clocks(ui::TimePoint::new(start, 0), Some(ui::TimePoint::new(end, 0)), 0)
+    }
+
+    #[test]
+    fn undo_and_redo_replay_entry_and_day_edits() {
+        let mut state = State::default();
+        let mut log = UndoLog::default();
+        log.feed(0.0, &state);
+
+        state.days_mut()[0].durations.push(entry(9, 12));
+        log.feed(10.0, &state);
+        state.days_mut()[0].durations.insert(0, entry(7, 8));
+        state.days_mut()[1].enabled = false;
+        log.feed(20.0, &state);
+        *state.days_mut()[0].durations[1].note_mut() = "a".to_string();
+        log.feed(30.0, &state);
+        *state.days_mut()[0].durations[1].note_mut() = "ab".to_string();
+        log.feed(30.5, &state);
+        let edited = state.clone();
+
+        // Typing "ab" is one step.
+        assert!(log.undo(40.0, &mut state));
+        assert_eq!(state.days()[0].durations[1].note(), "");
+        assert!(log.undo(41.0, &mut state));
+        assert_eq!(state.days()[0].durations.len(), 1);
+        assert!(state.days()[1].enabled);
+        assert!(log.redo(42.0, &mut state));
+        assert!(log.redo(43.0, &mut state));
+        assert_eq!(state, edited);
+        assert!(!log.can_redo(state.current_week_key()));
+    }
+
+    #[test]
+    fn history_survives_reload_and_restart() {
+        let mut state = State::default();
+        let mut log = UndoLog::default();
+        log.feed(0.0, &state);
+        state.days_mut()[2].durations.push(entry(9, 17));
+        log.feed(5.0, &state);
+
+        // A reload hands out new row ids; the edit still finds its entry.
+        let reloaded: Vec<ui::Day> = state
+            .days()
+            .iter()
+            .map(|day| {
+                let mut day = day.clone();
+                day.durations = day
+                    .durations
+                    .iter()
+                    .map(|duration| entry(duration.start_clock().hour(), 17))
+                    .collect();
+                day
+            })
+            .collect();
+        state.replace_current_week_days(reloaded);
+        log.rebase(&state);
+
+        let mut restored: UndoLog = serde_json::from_str(&serde_json::to_string(&log).unwrap()).unwrap();
+        assert!(restored.can_undo(state.current_week_key()));
+        assert!(restored.undo(10.0, &mut state));
+        assert!(state.days()[2].durations.is_empty());
+    }
+
+    #[test]
+    fn steps_are_bounded() {
+        let mut state = State::default();
+        let mut log = UndoLog::default();
+        log.feed(0.0, &state);
+        for step in 0..MAX_STEPS + 5 {
+            state.days_mut()[0].durations.push(entry(1, 2));
+            log.feed(step as f64 * 10.0, &state);
+        }
+
+        let week = state.current_week_key();
+        let mut undone = 0;
+        while log.can_undo(week) {
+            log.undo(1e9, &mut state);
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_STEPS);
+        assert_eq!(state.days()[0].durations.len(), 5);
+    }
+
+    #[test]
+    fn undo_in_weeks_without_history_keeps_other_logs() {
+        let mut state = State::default();
+        state.set_current_week_normalized(2026, 20);
+        let mut log = UndoLog::default();
+        log.feed(0.0, &state);
+        state.days_mut()[0].durations.push(entry(9, 10));
+        log.feed(10.0, &state);
+        let edited = state.current_week_key();
+
+        for week_nr in 1..=MAX_WEEKS as i32 + 1 {
+            state.set_current_week_normalized(2025, week_nr);
+            assert!(log.undo(20.0, &mut state));
+            assert!(log.redo(21.0, &mut state));
+        }
+        assert_eq!(log.weeks.len(), 1);
+        assert!(log.can_undo(edited));
+    }
+}
@@ -42,7 +42,10 @@ async fn main() {
         for entry in day.work_entries {
             println!(
                 "  [{}] {} -> {} {}",
-                entry.sort_index, entry.starts_at, entry.ends_at, entry.metadata
+                entry.sort_index,
+                entry.starts_at,
+                entry.ends_at.map_or_else(|| "running".to_string(), |ends_at| ends_at.to_string()),
+                entry.metadata
             );
         }
     }
@@ -5,6 +5,7 @@
 mod app;
 pub mod config;
 pub mod logging;
+pub mod realtime;
 pub mod supabase;
 mod ui;
 
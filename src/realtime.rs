@@ -0,0 +1,493 @@
+// Supabase Realtime client. Joins one Phoenix channel that streams changes to
+// the user's `work_days` rows over a websocket. The socket itself runs in the
+// background (a thread on native, browser callbacks on wasm); the app polls
+// the client every frame, which also keeps the heartbeat going.
+//
+// Every save goes through `save_work_day_with_entries`, which always updates
+// the day row, so day changes are enough to notice changed entries too.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Phoenix closes channels that stay silent for longer than 60 s.
+pub const HEARTBEAT_SECONDS: f64 = 25.0;
+const PROTOCOL_VERSION: &str = "1.0.0";
+
+/// Called from the socket whenever an event is waiting, e.g. to repaint.
+pub type Wake = Arc<dyn Fn() + Send + Sync>;
+
+/// A changed day row. Deleted rows only carry their id, so both fields can be
+/// missing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayChange {
+    pub work_date: Option<NaiveDate>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RealtimeEvent {
+    /// The channel is subscribed; changes from now on are delivered.
+    Joined,
+    DayChanged(DayChange),
+    /// The server refused the channel or reported an error on it.
+    Failed(String),
+    /// The connection is gone; a new client has to be connected.
+    Closed(String),
+}
+
+#[derive(Debug)]
+enum SocketEvent {
+    Opened,
+    Message(String),
+    Closed(String),
+}
+
+pub struct RealtimeClient {
+    socket: socket::Socket,
+    topic: String,
+    user_id: String,
+    access_token: String,
+    next_ref: u64,
+    join_ref: Option<String>,
+    opened: bool,
+    closed: bool,
+    last_heartbeat: Option<f64>,
+}
+
+impl RealtimeClient {
+    /// Starts connecting to `url` (see `realtime_url`). The channel is joined
+    /// once the socket is open.
+    pub fn connect(url: &str, user_id: &str, access_token: &str, wake: Wake) -> Self {
+        debug!(target = "realtime", user_id, "connecting to Supabase Realtime");
+        Self {
+            socket: socket::Socket::connect(url, wake),
+            topic: format!("realtime:work_days:{user_id}"),
+            user_id: user_id.to_string(),
+            access_token: access_token.to_string(),
+            next_ref: 0,
+            join_ref: None,
+            opened: false,
+            closed: false,
+            last_heartbeat: None,
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    /// Hands a refreshed session token to the channel, so it keeps receiving
+    /// changes after the old token expires.
+    pub fn set_access_token(&mut self, access_token: &str) {
+        self.access_token = access_token.to_string();
+        if self.join_ref.is_some() {
+            let message = json!({
+                "topic": self.topic,
+                "event": "access_token",
+                "payload": { "access_token": self.access_token },
+                "ref": self.take_ref(),
+                "join_ref": self.join_ref,
+            });
+            self.socket.send(message.to_string());
+        }
+    }
+
+    /// Takes the events that arrived since the last call and sends a
+    /// heartbeat when one is due.
+    pub fn poll(&mut self, time: f64) -> Vec<RealtimeEvent> {
+        let mut events = vec![];
+        for event in self.socket.take_events() {
+            match event {
+                SocketEvent::Opened => {
+                    self.opened = true;
+                    self.last_heartbeat = Some(time);
+                    self.join();
+                }
+                SocketEvent::Message(text) => events.extend(parse_message(&text, &self.topic, self.join_ref.as_deref())),
+                SocketEvent::Closed(reason) => {
+                    self.closed = true;
+                    events.push(RealtimeEvent::Closed(reason));
+                }
+            }
+        }
+        if self.opened && !self.closed && self.next_heartbeat_in(time) == Some(0.0) {
+            let message = json!({ "topic": "phoenix", "event": "heartbeat", "payload": {}, "ref": self.take_ref() });
+            self.socket.send(message.to_string());
+            self.last_heartbeat = Some(time);
+        }
+        events
+    }
+
+    /// Seconds until `poll` has to send the next heartbeat.
+    pub fn next_heartbeat_in(&self, time: f64) -> Option<f64> {
+        if !self.opened || self.closed {
+            return None;
+        }
+        Some(self.last_heartbeat.map_or(0.0, |last| (last + HEARTBEAT_SECONDS - time).max(0.0)))
+    }
+
+    fn join(&mut self) {
+        let join_ref = self.take_ref();
+        let message = json!({
+            "topic": self.topic,
+            "event": "phx_join",
+            "payload": {
+                "config": {
+                    "broadcast": { "ack": false, "self": false },
+                    "presence": { "key": "" },
+                    "postgres_changes": [{
+                        "event": "*",
+                        "schema": "public",
+                        "table": "work_days",
+                        "filter": format!("user_id=eq.{}", self.user_id),
+                    }],
+                    "private": false,
+                },
+                "access_token": self.access_token,
+            },
+            "ref": join_ref,
+            "join_ref": join_ref,
+        });
+        self.join_ref = Some(join_ref);
+        self.socket.send(message.to_string());
+    }
+
+    fn take_ref(&mut self) -> String {
+        self.next_ref += 1;
+        self.next_ref.to_string()
+    }
+}
+
+/// The websocket endpoint of a Supabase project, e.g.
+/// `wss://project.supabase.co/realtime/v1/websocket?apikey=...&vsn=1.0.0`.
+pub fn realtime_url(supabase_url: &str, api_key: &str) -> String {
+    let base = supabase_url.trim_end_matches('/');
+    let base = match base.split_once("://") {
+        Some(("http", rest)) => format!("ws://{rest}"),
+        Some(("https", rest)) => format!("wss://{rest}"),
+        _ => base.to_string(),
+    };
+    format!("{base}/realtime/v1/websocket?apikey={api_key}&vsn={PROTOCOL_VERSION}")
+}
+
+/// Translates one Phoenix message into an event. Heartbeat replies and
+/// messages for other topics yield nothing.
+fn parse_message(text: &str, topic: &str, join_ref: Option<&str>) -> Option<RealtimeEvent> {
+    let message: Value = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(err) => {
+            warn!(target = "realtime", error = %err, "ignoring malformed realtime message");
+            return None;
+        }
+    };
+    if message["topic"].as_str() != Some(topic) {
+        return None;
+    }
+    let payload = &message["payload"];
+    match message["event"].as_str()? {
+        "phx_reply" if join_ref.is_some() && message["ref"].as_str() == join_ref => match payload["status"].as_str() {
+            Some("ok") => Some(RealtimeEvent::Joined),
+            _ => Some(RealtimeEvent::Failed(describe_error(&payload["response"]))),
+        },
+        "postgres_changes" => {
+            let data = &payload["data"];
+            let record = &data["record"];
+            Some(RealtimeEvent::DayChanged(DayChange {
+                work_date: record["work_date"].as_str().and_then(|date| date.parse().ok()),
+                updated_at: record["updated_at"]
+                    .as_str()
+                    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    .map(|at| at.with_timezone(&Utc)),
+            }))
+        }
+        "system" if payload["status"].as_str() == Some("error") => Some(RealtimeEvent::Failed(describe_error(payload))),
+        "phx_error" => Some(RealtimeEvent::Failed("channel error".to_string())),
+        "phx_close" => Some(RealtimeEvent::Closed("channel closed by the server".to_string())),
+        _ => None,
+    }
+}
+
+fn describe_error(response: &Value) -> String {
+    ["reason", "message"]
+        .iter()
+        .find_map(|field| response[*field].as_str())
+        .unwrap_or("unknown error")
+        .to_string()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod socket {
+    use super::{SocketEvent, Wake};
+    use futures_util::{SinkExt, StreamExt};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// A websocket served by its own thread, like the other background tasks.
+    /// Dropping it closes the connection.
+    pub(super) struct Socket {
+        outgoing: mpsc::UnboundedSender<String>,
+        incoming: Arc<Mutex<Vec<SocketEvent>>>,
+    }
+
+    impl Socket {
+        pub(super) fn connect(url: &str, wake: Wake) -> Self {
+            let (outgoing, mut receiver) = mpsc::unbounded_channel::<String>();
+            let incoming = Arc::new(Mutex::new(Vec::new()));
+            let events = incoming.clone();
+            let url = url.to_string();
+            std::thread::spawn(move || {
+                let push = |event| {
+                    events.lock().unwrap().push(event);
+                    wake();
+                };
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("tokio runtime should initialize");
+                let reason = runtime.block_on(async {
+                    let mut stream = match tokio_tungstenite::connect_async(url.as_str()).await {
+                        Ok((stream, _)) => stream,
+                        Err(err) => return err.to_string(),
+                    };
+                    push(SocketEvent::Opened);
+                    loop {
+                        tokio::select! {
+                            text = receiver.recv() => match text {
+                                Some(text) => {
+                                    if let Err(err) = stream.send(Message::Text(text)).await {
+                                        return err.to_string();
+                                    }
+                                }
+                                None => {
+                                    let _ = stream.close(None).await;
+                                    return "closed by the app".to_string();
+                                }
+                            },
+                            message = stream.next() => match message {
+                                Some(Ok(Message::Text(text))) => push(SocketEvent::Message(text)),
+                                Some(Ok(Message::Close(frame))) => {
+                                    return frame.map_or_else(|| "closed by the server".to_string(), |frame| frame.reason.to_string());
+                                }
+                                Some(Ok(_)) => {}
+                                Some(Err(err)) => return err.to_string(),
+                                None => return "connection closed".to_string(),
+                            },
+                        }
+                    }
+                });
+                push(SocketEvent::Closed(reason));
+            });
+            Self { outgoing, incoming }
+        }
+
+        pub(super) fn send(&self, text: String) {
+            // A closed connection reports itself through `take_events`.
+            let _ = self.outgoing.send(text);
+        }
+
+        pub(super) fn take_events(&self) -> Vec<SocketEvent> {
+            std::mem::take(&mut *self.incoming.lock().unwrap())
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod socket {
+    use super::{SocketEvent, Wake};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use web_sys::wasm_bindgen::closure::Closure;
+    use web_sys::wasm_bindgen::JsCast;
+    use web_sys::{CloseEvent, Event, MessageEvent, WebSocket};
+
+    /// A browser websocket whose callbacks queue events for the next poll.
+    /// Dropping it closes the connection.
+    pub(super) struct Socket {
+        socket: Option<WebSocket>,
+        incoming: Rc<RefCell<Vec<SocketEvent>>>,
+        _on_open: Closure<dyn FnMut(Event)>,
+        _on_message: Closure<dyn FnMut(MessageEvent)>,
+        _on_close: Closure<dyn FnMut(CloseEvent)>,
+    }
+
+    impl Socket {
+        pub(super) fn connect(url: &str, wake: Wake) -> Self {
+            let incoming = Rc::new(RefCell::new(Vec::new()));
+            let push = {
+                let incoming = incoming.clone();
+                move |event| {
+                    incoming.borrow_mut().push(event);
+                    wake();
+                }
+            };
+            let on_open = {
+                let push = push.clone();
+                Closure::<dyn FnMut(Event)>::new(move |_| push(SocketEvent::Opened))
+            };
+            let on_message = {
+                let push = push.clone();
+                Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                    if let Some(text) = event.data().as_string() {
+                        push(SocketEvent::Message(text));
+                    }
+                })
+            };
+            let on_close = {
+                let push = push.clone();
+                Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+                    let reason = event.reason();
+                    push(SocketEvent::Closed(if reason.is_empty() {
+                        format!("closed with code {}", event.code())
+                    } else {
+                        reason
+                    }));
+                })
+            };
+            let socket = match WebSocket::new(url) {
+                Ok(socket) => {
+                    socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+                    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+                    socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+                    Some(socket)
+                }
+                Err(err) => {
+                    push(SocketEvent::Closed(format!("{err:?}")));
+                    None
+                }
+            };
+            Self {
+                socket,
+                incoming,
+                _on_open: on_open,
+                _on_message: on_message,
+                _on_close: on_close,
+            }
+        }
+
+        pub(super) fn send(&self, text: String) {
+            if let Some(socket) = &self.socket {
+                // A closed connection reports itself through `take_events`.
+                let _ = socket.send_with_str(&text);
+            }
+        }
+
+        pub(super) fn take_events(&self) -> Vec<SocketEvent> {
+            std::mem::take(&mut *self.incoming.borrow_mut())
+        }
+    }
+
+    impl Drop for Socket {
+        fn drop(&mut self) {
+            if let Some(socket) = &self.socket {
+                socket.set_onopen(None);
+                socket.set_onmessage(None);
+                socket.set_onclose(None);
+                let _ = socket.close();
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::{realtime_url, DayChange, RealtimeClient, RealtimeEvent};
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::Message;
+
+    #[test]
+    fn realtime_url_switches_to_websocket_schemes() {
+        assert_eq!(
+            realtime_url("https://project.supabase.co/", "key"),
+            "wss://project.supabase.co/realtime/v1/websocket?apikey=key&vsn=1.0.0"
+        );
+        assert_eq!(
+            realtime_url("http://127.0.0.1:54321", "key"),
+            "ws://127.0.0.1:54321/realtime/v1/websocket?apikey=key&vsn=1.0.0"
+        );
+    }
+
+    /// Runs a stand-in for the Realtime server that accepts one connection,
+    /// acknowledges the join and pushes a single day change.
+    fn spawn_stand_in() -> (String, std::sync::mpsc::Receiver<Value>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let (joined, join_message) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async move {
+                listener.set_nonblocking(true).unwrap();
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let Some(Ok(Message::Text(text))) = socket.next().await else {
+                    panic!("expected a join message");
+                };
+                let join: Value = serde_json::from_str(&text).unwrap();
+                let reply = json!({
+                    "topic": join["topic"],
+                    "event": "phx_reply",
+                    "payload": { "status": "ok", "response": { "postgres_changes": [] } },
+                    "ref": join["ref"],
+                });
+                let change = json!({
+                    "topic": join["topic"],
+                    "event": "postgres_changes",
+                    "payload": { "data": {
+                        "schema": "public",
+                        "table": "work_days",
+                        "type": "UPDATE",
+                        "commit_timestamp": "2026-05-04T16:30:00Z",
+                        "record": { "work_date": "2026-05-04", "updated_at": "2026-05-04T16:30:00.123+00:00" },
+                        "old_record": { "id": "day-1" },
+                    }, "ids": [1] },
+                    "ref": null,
+                });
+                joined.send(join).unwrap();
+                socket.send(Message::Text(reply.to_string())).await.unwrap();
+                socket.send(Message::Text(change.to_string())).await.unwrap();
+                socket.close(None).await.unwrap();
+                // Drain until the client saw the close.
+                while let Some(Ok(_)) = socket.next().await {}
+            });
+        });
+        (format!("ws://{address}/realtime/v1/websocket"), join_message)
+    }
+
+    #[test]
+    fn client_joins_and_reports_day_changes_from_a_local_server() {
+        let (url, join_message) = spawn_stand_in();
+        let mut client = RealtimeClient::connect(&url, "user-1", "token", Arc::new(|| {}));
+
+        let mut events = vec![];
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !matches!(events.last(), Some(RealtimeEvent::Closed(_))) {
+            assert!(std::time::Instant::now() < deadline, "stand-in did not answer: {events:?}");
+            events.extend(client.poll(0.0));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let join = join_message.recv().unwrap();
+        assert_eq!(join["event"], "phx_join");
+        assert_eq!(join["payload"]["access_token"], "token");
+        assert_eq!(join["payload"]["config"]["postgres_changes"][0]["filter"], "user_id=eq.user-1");
+        assert_eq!(events[0], RealtimeEvent::Joined);
+        assert_eq!(
+            events[1],
+            RealtimeEvent::DayChanged(DayChange {
+                work_date: NaiveDate::from_ymd_opt(2026, 5, 4),
+                updated_at: Some(Utc.with_ymd_and_hms(2026, 5, 4, 16, 30, 0).unwrap() + chrono::Duration::milliseconds(123)),
+            })
+        );
+        assert_eq!(client.next_heartbeat_in(0.0), None);
+    }
+}
@@ -4,6 +4,7 @@ use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
 #[cfg(target_arch = "wasm32")]
 use web_sys::wasm_bindgen::JsValue;
 
@@ -26,10 +27,11 @@ pub struct WorkDayRow {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WorkEntryRow {
-    pub id: String,
+    pub id: Uuid,
     pub work_day_id: String,
     pub starts_at: DateTime<Utc>,
-    pub ends_at: DateTime<Utc>,
+    /// `None` while the entry is still running.
+    pub ends_at: Option<DateTime<Utc>>,
     #[serde(default)]
     pub metadata: serde_json::Value,
     pub sort_index: i32,
@@ -54,8 +56,11 @@ pub struct UpsertWorkDayPayload {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct WorkEntryDraft {
+    /// The entry's row id; entries queued before ids were sent get a new one.
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub starts_at: DateTime<Utc>,
-    pub ends_at: DateTime<Utc>,
+    pub ends_at: Option<DateTime<Utc>>,
     #[serde(default)]
     pub metadata: serde_json::Value,
     pub sort_index: i32,
@@ -68,6 +73,57 @@ pub struct WorkDayDraft {
     pub enabled: bool,
     #[serde(default)]
     pub work_entries: Vec<WorkEntryDraft>,
+    /// Version of the server row this draft came from or was edited on top
+    /// of. `None` for days the app never saw on the server.
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A week save the database rolled back as a whole because some of its days
+/// could not be saved. The other days of the week were not saved either.
+#[derive(Debug, Clone)]
+pub struct WorkWeekRejected {
+    pub days: Vec<RejectedDay>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RejectedDay {
+    pub work_date: NaiveDate,
+    pub reason: String,
+    /// The server day, when the day was changed on another device.
+    pub conflict: Option<WorkDayDraft>,
+}
+
+impl std::fmt::Display for WorkWeekRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let days: Vec<_> = self.days.iter().map(|day| format!("{}: {}", day.work_date, day.reason)).collect();
+        write!(f, "week save rejected ({})", days.join("; "))
+    }
+}
+
+impl std::error::Error for WorkWeekRejected {}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractPeriodRow {
+    pub id: String,
+    pub user_id: String,
+    pub starts_on: NaiveDate,
+    pub ends_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub name: String,
+    pub weekday_target_minutes: Vec<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// One contract period as the app edits it. `weekday_target_minutes` is
+/// Monday-first and always has seven entries.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContractPeriodDraft {
+    pub starts_on: NaiveDate,
+    pub ends_on: Option<NaiveDate>,
+    pub name: String,
+    pub weekday_target_minutes: Vec<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -95,6 +151,14 @@ pub struct StoredSession {
     pub email: Option<String>,
 }
 
+/// Tokens Supabase appends to the app URL after a magic link was opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthRedirect {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_at: Option<i64>,
+}
+
 #[derive(Debug, Serialize)]
 struct PasswordSignInRequest<'a> {
     email: &'a str,
@@ -102,17 +166,52 @@ struct PasswordSignInRequest<'a> {
 }
 
 #[derive(Debug, Serialize)]
-struct SaveWorkDayRpcRequest<'a> {
-    p_work_date: NaiveDate,
-    p_target_minutes: i32,
-    p_enabled: bool,
-    p_entries: &'a [SaveWorkEntryRpcPayload],
+struct OtpRequest<'a> {
+    email: &'a str,
+    /// Only existing accounts get a login email; this is no sign-up form.
+    create_user: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyOtpRequest<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    email: &'a str,
+    token: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct SaveWorkWeekRpcRequest {
+    p_days: Vec<SaveWorkDayRpcPayload>,
+}
+
+#[derive(Debug, Serialize)]
+struct SaveWorkDayRpcPayload {
+    work_date: NaiveDate,
+    target_minutes: i32,
+    enabled: bool,
+    entries: Vec<SaveWorkEntryRpcPayload>,
+    expected_updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RejectedDayDetail {
+    work_date: NaiveDate,
+    message: String,
+    #[serde(default)]
+    server: Option<WorkDayWithEntries>,
+}
+
+#[derive(Debug, Serialize)]
+struct SaveContractPeriodsRpcRequest<'a> {
+    p_periods: &'a [ContractPeriodDraft],
 }
 
 #[derive(Debug, Serialize)]
 struct SaveWorkEntryRpcPayload {
+    id: Uuid,
     starts_at: DateTime<Utc>,
-    ends_at: DateTime<Utc>,
+    ends_at: Option<DateTime<Utc>>,
     metadata: serde_json::Value,
     sort_index: i32,
 }
@@ -154,6 +253,77 @@ impl SupabaseClient {
         Self::decode_json_response("password sign-in", response).await
     }
 
+    /// Emails a one-time login code and magic link. The link leads back to
+    /// `redirect_to` when given, which must be listed in the project's
+    /// redirect URLs; without it Supabase uses the project's site URL.
+    #[instrument(name = "supabase_send_otp", skip_all, fields(email = %email))]
+    pub async fn send_otp(&self, email: &str, redirect_to: Option<&str>) -> Result<()> {
+        let url = format!("{}/auth/v1/otp", self.url);
+        info!(target = "supabase", auth_url = %url, has_redirect = redirect_to.is_some(), "requesting Supabase login email");
+        let mut request = self
+            .http
+            .post(url.clone())
+            .header("apikey", &self.api_key)
+            .header(CONTENT_TYPE, JSON_CONTENT_TYPE)
+            .json(&OtpRequest { email, create_user: false });
+        if let Some(redirect_to) = redirect_to {
+            request = request.query(&[("redirect_to", redirect_to)]);
+        }
+        let response = request
+            .send()
+            .await
+            .inspect_err(|err| {
+                Self::log_transport_error("login email", &url, err);
+            })
+            .with_context(|| Self::transport_error_context("login email", &url))?;
+
+        Self::error_for_status("login email", response).await?;
+        Ok(())
+    }
+
+    /// Exchanges the one-time code from a login email for a session.
+    #[instrument(name = "supabase_verify_otp", skip_all, fields(email = %email))]
+    pub async fn verify_otp(&self, email: &str, token: &str) -> Result<AuthSession> {
+        let url = format!("{}/auth/v1/verify", self.url);
+        info!(target = "supabase", auth_url = %url, "verifying Supabase login code");
+        let response = self
+            .http
+            .post(url.clone())
+            .header("apikey", &self.api_key)
+            .header(CONTENT_TYPE, JSON_CONTENT_TYPE)
+            .json(&VerifyOtpRequest {
+                kind: "email",
+                email,
+                token,
+            })
+            .send()
+            .await
+            .inspect_err(|err| {
+                Self::log_transport_error("login code verification", &url, err);
+            })
+            .with_context(|| Self::transport_error_context("login code verification", &url))?;
+
+        Self::decode_json_response("login code verification", response).await
+    }
+
+    /// Loads the user an access token belongs to. A magic-link redirect only
+    /// carries tokens, so this completes the session.
+    #[instrument(name = "supabase_get_user", skip_all)]
+    pub async fn get_user(&self, access_token: &str) -> Result<AuthUser> {
+        let url = format!("{}/auth/v1/user", self.url);
+        info!(target = "supabase", auth_url = %url, "fetching Supabase auth user");
+        let response = self
+            .authed_get(url.clone(), access_token)
+            .send()
+            .await
+            .inspect_err(|err| {
+                Self::log_transport_error("auth user", &url, err);
+            })
+            .with_context(|| Self::transport_error_context("auth user", &url))?;
+
+        Self::decode_json_response("auth user", response).await
+    }
+
     #[instrument(name = "supabase_refresh_session", skip_all)]
     pub async fn refresh_session(&self, refresh_token: &str) -> Result<AuthSession> {
         let url = format!("{}/auth/v1/token?grant_type=refresh_token", self.url);
@@ -219,47 +389,79 @@ impl SupabaseClient {
         Self::decode_json_response("get work day range", response).await
     }
 
-    /// Saves the full day as the app understands it: one day row plus its
-    /// complete ordered entry list.
-    #[instrument(
-        name = "supabase_save_work_day",
-        skip_all,
-        fields(work_date = %draft.work_date, entry_count = draft.work_entries.len())
-    )]
-    pub async fn save_work_day(&self, access_token: &str, draft: &WorkDayDraft) -> Result<WorkDayWithEntries> {
-        let rpc_payload: Vec<_> = draft
-            .work_entries
+    /// Saves several days in one transaction: either every day is saved, or
+    /// none is and the error is a [`WorkWeekRejected`] naming each day that
+    /// failed. A day only saves while the server row still has the draft's
+    /// `updated_at`; otherwise its rejection carries the server day.
+    #[instrument(name = "supabase_save_work_week", skip_all, fields(day_count = drafts.len()))]
+    pub async fn save_work_week(&self, access_token: &str, drafts: &[WorkDayDraft]) -> Result<Vec<WorkDayWithEntries>> {
+        let days = drafts
             .iter()
-            .map(|entry| SaveWorkEntryRpcPayload {
-                starts_at: entry.starts_at,
-                ends_at: entry.ends_at,
-                metadata: entry.metadata.clone(),
-                sort_index: entry.sort_index,
+            .map(|draft| SaveWorkDayRpcPayload {
+                work_date: draft.work_date,
+                target_minutes: draft.target_minutes,
+                enabled: draft.enabled,
+                entries: entry_rpc_payload(draft),
+                expected_updated_at: draft.updated_at,
             })
             .collect();
 
-        let url = format!("{}/rest/v1/rpc/save_work_day_with_entries", self.url);
+        let url = format!("{}/rest/v1/rpc/save_work_week_with_entries", self.url);
         info!(
             target = "supabase",
-            work_date = %draft.work_date,
-            entry_count = draft.work_entries.len(),
-            enabled = draft.enabled,
-            target_minutes = draft.target_minutes,
-            "saving work day through RPC"
+            day_count = drafts.len(),
+            first_date = ?drafts.first().map(|draft| draft.work_date),
+            "saving work week through RPC"
         );
         let response = self
             .authed_request(reqwest::Method::POST, url, access_token)
-            .json(&SaveWorkDayRpcRequest {
-                p_work_date: draft.work_date,
-                p_target_minutes: draft.target_minutes,
-                p_enabled: draft.enabled,
-                p_entries: &rpc_payload,
-            })
+            .json(&SaveWorkWeekRpcRequest { p_days: days })
+            .send()
+            .await
+            .context("failed to save work week through Supabase RPC")?;
+
+        if response.status() == reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+            let body = response.text().await.unwrap_or_default();
+            warn!(target = "supabase", day_count = drafts.len(), "work week save rolled back");
+            return Err(parse_week_rejection(&body)
+                .map(anyhow::Error::new)
+                .unwrap_or_else(|| anyhow!("Supabase rejected the week save: {body}")));
+        }
+        Self::decode_json_response("save work week RPC", response).await
+    }
+
+    /// Loads all contract periods of the signed-in user, oldest first.
+    #[instrument(name = "supabase_get_contract_periods", skip_all)]
+    pub async fn get_contract_periods(&self, access_token: &str) -> Result<Vec<ContractPeriodRow>> {
+        let url = format!("{}/rest/v1/contract_periods?select=*&order=starts_on.asc", self.url);
+        info!(target = "supabase", "fetching contract periods");
+
+        let response = self
+            .authed_get(url, access_token)
+            .send()
+            .await
+            .context("failed to fetch contract periods from Supabase")?;
+
+        Self::decode_json_response("get contract periods", response).await
+    }
+
+    /// Replaces the complete contract period list in one RPC transaction.
+    #[instrument(name = "supabase_save_contract_periods", skip_all, fields(period_count = periods.len()))]
+    pub async fn save_contract_periods(&self, access_token: &str, periods: &[ContractPeriodDraft]) -> Result<Vec<ContractPeriodRow>> {
+        let url = format!("{}/rest/v1/rpc/save_contract_periods", self.url);
+        info!(
+            target = "supabase",
+            period_count = periods.len(),
+            "saving contract periods through RPC"
+        );
+        let response = self
+            .authed_request(reqwest::Method::POST, url, access_token)
+            .json(&SaveContractPeriodsRpcRequest { p_periods: periods })
             .send()
             .await
-            .context("failed to save work day through Supabase RPC")?;
+            .context("failed to save contract periods through Supabase RPC")?;
 
-        Self::decode_json_response("save work day RPC", response).await
+        Self::decode_json_response("save contract periods RPC", response).await
     }
 
     fn authed_get(&self, url: String, access_token: &str) -> reqwest::RequestBuilder {
@@ -340,6 +542,43 @@ impl From<AuthSession> for StoredSession {
     }
 }
 
+impl AuthRedirect {
+    /// Reads the fragment of a magic-link redirect, with or without the
+    /// leading `#`. Returns `None` when the fragment is not an auth redirect
+    /// and an error when Supabase reported one, e.g. for an expired link.
+    pub fn from_fragment(fragment: &str) -> Option<Result<Self>> {
+        let fields: std::collections::HashMap<String, String> = form_urlencoded::parse(fragment.trim_start_matches('#').as_bytes())
+            .into_owned()
+            .collect();
+        if let Some(error) = fields.get("error_description").or_else(|| fields.get("error")) {
+            return Some(Err(anyhow!("login link was rejected: {error}")));
+        }
+        let access_token = fields.get("access_token")?.clone();
+        let Some(refresh_token) = fields.get("refresh_token").cloned() else {
+            return Some(Err(anyhow!("login link did not include a refresh token")));
+        };
+        let expires_at = fields.get("expires_at").and_then(|value| value.parse().ok()).or_else(|| {
+            let expires_in: i64 = fields.get("expires_in")?.parse().ok()?;
+            Some(Utc::now().timestamp() + expires_in)
+        });
+        Some(Ok(Self {
+            access_token,
+            refresh_token,
+            expires_at,
+        }))
+    }
+
+    pub fn into_stored_session(self, user: AuthUser) -> StoredSession {
+        StoredSession {
+            access_token: self.access_token,
+            refresh_token: self.refresh_token,
+            expires_at: self.expires_at,
+            user_id: user.id,
+            email: user.email,
+        }
+    }
+}
+
 impl StoredSession {
     /// Returns true when the access token is already expired or close enough to
     /// expiry that the app should refresh it before making requests.
@@ -376,9 +615,10 @@ impl WorkDayDraft {
             .map(|(ix, entry)| {
                 let (starts_at, ends_at) = local_duration_to_utc_range(day.date, entry)?;
                 Ok(WorkEntryDraft {
+                    id: entry.id(),
                     starts_at,
                     ends_at,
-                    metadata: serde_json::Value::Object(Default::default()),
+                    metadata: entry_metadata(entry),
                     sort_index: i32::try_from(ix).context("too many entries in a single day")?,
                 })
             })
@@ -389,6 +629,7 @@ impl WorkDayDraft {
             target_minutes: duration_to_minutes(day.configured_target())?,
             enabled: day.enabled,
             work_entries,
+            updated_at: None,
         })
     }
 
@@ -401,19 +642,21 @@ impl WorkDayDraft {
             enabled = self.enabled,
             "converting Supabase draft into UI day"
         );
-        let mut day = ui::Day::new(self.work_date.format("%A").to_string());
+        let mut day = ui::Day::new(self.work_date.format("%A").to_string(), minutes_to_duration(self.target_minutes));
         day.date = self.work_date;
         day.enabled = self.enabled;
-        day.set_target(minutes_to_duration(self.target_minutes));
         day.durations = self
             .work_entries
             .into_iter()
             .map(|entry| {
-                Ok(ui::Duration::new(
-                    self.work_date,
-                    to_local_offset(entry.starts_at)?,
-                    to_local_offset(entry.ends_at)?,
-                ))
+                let starts_at = to_local_offset(entry.starts_at)?;
+                let mut duration = match entry.ends_at {
+                    Some(ends_at) => ui::Duration::new(self.work_date, starts_at, to_local_offset(ends_at)?),
+                    None => ui::Duration::new_open(starts_at),
+                };
+                duration.set_id(entry.id);
+                apply_entry_metadata(&mut duration, &entry.metadata);
+                Ok(duration)
             })
             .collect::<Result<Vec<_>>>()?;
         Ok(day)
@@ -426,6 +669,7 @@ impl From<WorkDayWithEntries> for WorkDayDraft {
             .work_entries
             .into_iter()
             .map(|entry| WorkEntryDraft {
+                id: entry.id,
                 starts_at: entry.starts_at,
                 ends_at: entry.ends_at,
                 metadata: entry.metadata,
@@ -438,11 +682,53 @@ impl From<WorkDayWithEntries> for WorkDayDraft {
             target_minutes: value.day.target_minutes,
             enabled: value.day.enabled,
             work_entries,
+            updated_at: value.day.updated_at,
+        }
+    }
+}
+
+impl From<ContractPeriodRow> for ContractPeriodDraft {
+    fn from(value: ContractPeriodRow) -> Self {
+        Self {
+            starts_on: value.starts_on,
+            ends_on: value.ends_on,
+            name: value.name,
+            weekday_target_minutes: value.weekday_target_minutes,
         }
     }
 }
 
-fn duration_to_minutes(duration: time::Duration) -> Result<i32> {
+/// Reads the failed days from a 422 answer of the week save RPC, whose
+/// `details` list each day with its error and, for conflicts, the server day.
+fn parse_week_rejection(body: &str) -> Option<WorkWeekRejected> {
+    let error: serde_json::Value = serde_json::from_str(body).ok()?;
+    let details: Vec<RejectedDayDetail> = serde_json::from_str(error.get("details")?.as_str()?).ok()?;
+    let days = details
+        .into_iter()
+        .map(|detail| RejectedDay {
+            work_date: detail.work_date,
+            reason: detail.message,
+            conflict: detail.server.map(WorkDayDraft::from),
+        })
+        .collect();
+    Some(WorkWeekRejected { days })
+}
+
+fn entry_rpc_payload(draft: &WorkDayDraft) -> Vec<SaveWorkEntryRpcPayload> {
+    draft
+        .work_entries
+        .iter()
+        .map(|entry| SaveWorkEntryRpcPayload {
+            id: entry.id,
+            starts_at: entry.starts_at,
+            ends_at: entry.ends_at,
+            metadata: entry.metadata.clone(),
+            sort_index: entry.sort_index,
+        })
+        .collect()
+}
+
+pub(crate) fn duration_to_minutes(duration: time::Duration) -> Result<i32> {
     if duration.whole_seconds() % 60 != 0 {
         // The database stores targets in whole minutes, so reject values that
         // would lose precision during serialization.
@@ -452,20 +738,75 @@ fn duration_to_minutes(duration: time::Duration) -> Result<i32> {
     i32::try_from(duration.whole_minutes()).context("duration does not fit in i32 minutes")
 }
 
-fn minutes_to_duration(minutes: i32) -> time::Duration {
+pub(crate) fn minutes_to_duration(minutes: i32) -> time::Duration {
     time::Duration::minutes(i64::from(minutes))
 }
 
-fn local_duration_to_utc_range(day_date: NaiveDate, entry: &ui::Duration) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+/// Builds the `metadata` object stored with an entry on top of the object it
+/// was loaded with. Only fields that differ from their default are written,
+/// so a plain work entry keeps the historical empty object.
+fn entry_metadata(entry: &ui::Duration) -> serde_json::Value {
+    let mut metadata = entry.metadata().clone();
+    for key in ["kind", "project", "note", "tags", "source"] {
+        metadata.remove(key);
+    }
+    if entry.is_break() {
+        metadata.insert("kind".to_string(), serde_json::to_value(entry.kind()).unwrap_or_default());
+    }
+    if !entry.project().trim().is_empty() {
+        metadata.insert("project".to_string(), entry.project().trim().into());
+    }
+    if !entry.note().trim().is_empty() {
+        metadata.insert("note".to_string(), entry.note().trim().into());
+    }
+    if !entry.tags().is_empty() {
+        metadata.insert("tags".to_string(), entry.tags().into());
+    }
+    if let Some(source) = entry.source() {
+        metadata.insert("source".to_string(), serde_json::to_value(source).unwrap_or_default());
+    }
+    serde_json::Value::Object(metadata)
+}
+
+/// Restores the fields of an entry that live in its `metadata` object. The
+/// whole object is kept on the entry, so keys the app does not know are
+/// written back unchanged.
+fn apply_entry_metadata(entry: &mut ui::Duration, metadata: &serde_json::Value) {
+    entry.set_metadata(metadata.as_object().cloned().unwrap_or_default());
+    if let Some(kind) = metadata.get("kind").and_then(|kind| serde_json::from_value(kind.clone()).ok()) {
+        entry.set_kind(kind);
+    }
+    if let Some(project) = metadata.get("project").and_then(serde_json::Value::as_str) {
+        *entry.project_mut() = project.to_string();
+    }
+    if let Some(note) = metadata.get("note").and_then(serde_json::Value::as_str) {
+        *entry.note_mut() = note.to_string();
+    }
+    if let Some(tags) = metadata.get("tags").and_then(serde_json::Value::as_array) {
+        entry.set_tags(tags.iter().filter_map(serde_json::Value::as_str).map(str::to_string).collect());
+    }
+    if let Some(source) = metadata
+        .get("source")
+        .and_then(|source| serde_json::from_value(source.clone()).ok())
+    {
+        entry.set_source(Some(source));
+    }
+}
+
+/// Converts a local duration into UTC timestamps. Open entries have no end.
+pub(crate) fn local_duration_to_utc_range(day_date: NaiveDate, entry: &ui::Duration) -> Result<(DateTime<Utc>, Option<DateTime<Utc>>)> {
     let start = local_clock_to_utc(day_date, entry.start_clock(), 0)?;
-    let end = local_clock_to_utc(day_date, entry.end_clock(), i64::from(entry.effective_end_day_offset()))?;
+    let end = entry
+        .end_clock()
+        .map(|end_clock| local_clock_to_utc(day_date, end_clock, i64::from(entry.effective_end_day_offset())))
+        .transpose()?;
     debug!(
         target = "supabase",
         work_date = %day_date,
         start_hour = entry.start_clock().hour(),
         start_minute = entry.start_clock().minute(),
-        end_hour = entry.end_clock().hour(),
-        end_minute = entry.end_clock().minute(),
+        end_hour = entry.end_clock().map(ui::TimePoint::hour),
+        end_minute = entry.end_clock().map(ui::TimePoint::minute),
         end_day_offset = entry.effective_end_day_offset(),
         "converted local duration into UTC range"
     );
@@ -504,7 +845,9 @@ fn current_fixed_offset() -> Result<FixedOffset> {
 
 #[cfg(test)]
 mod tests {
-    use super::{duration_to_minutes, minutes_to_duration, AuthSession, AuthUser, StoredSession, WorkDayDraft};
+    use super::{
+        duration_to_minutes, minutes_to_duration, parse_week_rejection, AuthRedirect, AuthSession, AuthUser, StoredSession, WorkDayDraft,
+    };
     use crate::ui;
     use chrono::NaiveDate;
     use serde_json::json;
@@ -523,21 +866,50 @@ mod tests {
         let start = time::OffsetDateTime::from_unix_timestamp(1_776_667_200).unwrap();
         let end = start + time::Duration::hours(2);
 
-        let mut day = ui::Day::new("Monday".to_string());
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
         day.date = date;
         day.enabled = true;
-        day.set_target(time::Duration::hours(8));
         day.durations = vec![ui::Duration::new(date, start, end)];
 
         let mut draft = WorkDayDraft::from_ui_day(&day).unwrap();
-        draft.work_entries[0].metadata = json!({ "note": "pairing" });
+        draft.work_entries[0].metadata = json!({
+            "project": "billing",
+            "note": "pairing",
+            "tags": ["client"],
+            "source": "seed",
+            "billable": true,
+            "invoice": { "id": 7 },
+        });
         let round_tripped = draft.into_ui_day().unwrap();
 
         assert_eq!(round_tripped.date, date);
         assert_eq!(round_tripped.enabled, day.enabled);
         assert_eq!(round_tripped.configured_target(), time::Duration::hours(8));
         assert_eq!(round_tripped.durations.len(), 1);
+        assert_eq!(round_tripped.durations[0].id(), day.durations[0].id());
         assert_eq!(round_tripped.durations[0].duration(), time::Duration::hours(2));
+        assert_eq!(round_tripped.durations[0].project(), "billing");
+        assert_eq!(round_tripped.durations[0].note(), "pairing");
+        assert_eq!(round_tripped.durations[0].tags(), ["client"]);
+        // "source" is a known key, so one the app cannot read is dropped.
+        assert_eq!(
+            WorkDayDraft::from_ui_day(&round_tripped).unwrap().work_entries[0].metadata,
+            json!({
+                "project": "billing",
+                "note": "pairing",
+                "tags": ["client"],
+                "billable": true,
+                "invoice": { "id": 7 },
+            })
+        );
+
+        // Clearing a known field removes its key but keeps the unknown ones.
+        let mut edited = round_tripped.clone();
+        edited.durations[0].note_mut().clear();
+        assert_eq!(
+            WorkDayDraft::from_ui_day(&edited).unwrap().work_entries[0].metadata,
+            json!({ "project": "billing", "tags": ["client"], "billable": true, "invoice": { "id": 7 } })
+        );
     }
 
     #[test]
@@ -546,7 +918,7 @@ mod tests {
         let start = time::OffsetDateTime::from_unix_timestamp(1_776_718_200).unwrap();
         let end = start + time::Duration::hours(3);
 
-        let mut day = ui::Day::new("Monday".to_string());
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
         day.date = date;
         day.enabled = true;
         day.durations = vec![ui::Duration::new(date, start, end)];
@@ -563,7 +935,7 @@ mod tests {
         let date = NaiveDate::from_ymd_opt(2026, 4, 20).unwrap();
         let start = time::OffsetDateTime::from_unix_timestamp(1_776_667_200).unwrap();
 
-        let mut day = ui::Day::new("Monday".to_string());
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
         day.date = date;
         day.enabled = true;
         day.durations = vec![ui::Duration::new(date, start, start)];
@@ -573,6 +945,70 @@ mod tests {
         assert!(draft.work_entries.is_empty());
     }
 
+    #[test]
+    fn open_entry_round_trips_through_supabase_draft() {
+        let date = NaiveDate::from_ymd_opt(2026, 4, 20).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_776_667_200).unwrap();
+
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
+        day.date = date;
+        day.durations = vec![ui::Duration::new_open(start)];
+
+        let draft = WorkDayDraft::from_ui_day(&day).unwrap();
+        assert_eq!(draft.work_entries.len(), 1);
+        assert_eq!(draft.work_entries[0].ends_at, None);
+        assert!(serde_json::to_value(&draft.work_entries[0]).unwrap()["ends_at"].is_null());
+
+        let round_tripped = draft.into_ui_day().unwrap();
+        assert!(round_tripped.durations[0].is_open());
+    }
+
+    #[test]
+    fn break_kind_round_trips_through_metadata() {
+        let date = NaiveDate::from_ymd_opt(2026, 4, 20).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_776_667_200).unwrap();
+
+        let mut pause = ui::Duration::new(date, start, start + time::Duration::minutes(30));
+        pause.set_kind(ui::EntryKind::Break);
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
+        day.date = date;
+        day.durations = vec![ui::Duration::new(date, start - time::Duration::hours(1), start), pause];
+
+        let draft = WorkDayDraft::from_ui_day(&day).unwrap();
+        assert_eq!(draft.work_entries[0].metadata, json!({}));
+        assert_eq!(draft.work_entries[1].metadata, json!({ "kind": "break" }));
+
+        let round_tripped = draft.into_ui_day().unwrap();
+        assert!(!round_tripped.durations[0].is_break());
+        assert!(round_tripped.durations[1].is_break());
+    }
+
+    #[test]
+    fn import_source_round_trips_through_metadata() {
+        let date = NaiveDate::from_ymd_opt(2026, 4, 20).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_776_667_200).unwrap();
+        let mut meeting = ui::Duration::new(date, start, start + time::Duration::minutes(15));
+        meeting.set_source(Some(ui::EntrySource {
+            kind: "ics".to_string(),
+            id: "standup@example.com".to_string(),
+        }));
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
+        day.date = date;
+        day.durations = vec![meeting];
+
+        let draft = WorkDayDraft::from_ui_day(&day).unwrap();
+        assert_eq!(
+            draft.work_entries[0].metadata,
+            json!({ "source": { "kind": "ics", "id": "standup@example.com" } })
+        );
+        let mut round_tripped = draft.into_ui_day().unwrap();
+        assert_eq!(round_tripped.durations[0].source(), day.durations[0].source());
+
+        round_tripped.durations[0].set_source(None);
+        let draft = WorkDayDraft::from_ui_day(&round_tripped).unwrap();
+        assert_eq!(draft.work_entries[0].metadata, json!({}));
+    }
+
     #[test]
     fn stored_session_is_built_from_auth_session() {
         let stored = StoredSession::from(AuthSession {
@@ -591,4 +1027,62 @@ mod tests {
         assert_eq!(stored.email.as_deref(), Some("user@example.com"));
         assert_eq!(stored.refresh_token, "refresh");
     }
+
+    #[test]
+    fn magic_link_fragment_becomes_a_session() {
+        let redirect = AuthRedirect::from_fragment(
+            "#access_token=access&expires_at=1800000000&expires_in=3600&refresh_token=refresh&token_type=bearer&type=magiclink",
+        )
+        .unwrap()
+        .unwrap();
+        let stored = redirect.into_stored_session(AuthUser {
+            id: "user-123".to_string(),
+            email: Some("user@example.com".to_string()),
+        });
+        assert_eq!(stored.access_token, "access");
+        assert_eq!(stored.refresh_token, "refresh");
+        assert_eq!(stored.expires_at, Some(1_800_000_000));
+        assert_eq!(stored.user_id, "user-123");
+
+        let expired = AuthRedirect::from_fragment(
+            "error=access_denied&error_code=otp_expired&error_description=Email+link+is+invalid+or+has+expired",
+        )
+        .unwrap();
+        assert!(expired.unwrap_err().to_string().contains("Email link is invalid or has expired"));
+        assert!(AuthRedirect::from_fragment("").is_none());
+        assert!(AuthRedirect::from_fragment("#section-2").is_none());
+    }
+
+    #[test]
+    fn week_rejection_lists_each_failed_day() {
+        let server = json!({
+            "id": "day-2",
+            "user_id": "user-123",
+            "work_date": "2026-05-06",
+            "target_minutes": 480,
+            "enabled": true,
+            "created_at": null,
+            "updated_at": "2026-05-06T16:30:00+00:00",
+            "work_entries": []
+        });
+        let details = json!([
+            { "work_date": "2026-05-05", "code": "22007", "message": "invalid input syntax for type timestamp with time zone", "server": null },
+            { "work_date": "2026-05-06", "code": "PT409", "message": "work day 2026-05-06 was changed elsewhere", "server": server }
+        ]);
+        let body = json!({
+            "code": "PT422",
+            "details": details.to_string(),
+            "hint": null,
+            "message": "2 of 5 days could not be saved"
+        });
+
+        let rejected = parse_week_rejection(&body.to_string()).unwrap();
+        assert_eq!(rejected.days.len(), 2);
+        assert_eq!(rejected.days[0].work_date, NaiveDate::from_ymd_opt(2026, 5, 5).unwrap());
+        assert!(rejected.days[0].reason.contains("invalid input syntax"));
+        assert!(rejected.days[0].conflict.is_none());
+        let conflict = rejected.days[1].conflict.as_ref().unwrap();
+        assert_eq!(conflict.work_date, NaiveDate::from_ymd_opt(2026, 5, 6).unwrap());
+        assert!(conflict.updated_at.is_some());
+    }
 }
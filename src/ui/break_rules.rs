@@ -0,0 +1,68 @@
+// Automatic break deduction. Labour rules require an unpaid break once a day
+// gets long enough; when the recorded breaks fall short, the missing part is
+// deducted from the worked time instead.
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BreakRule {
+    /// The rule applies once the worked time exceeds this.
+    pub worked_over: time::Duration,
+    /// Total break the day must contain when the rule applies.
+    pub min_break: time::Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BreakRules {
+    pub rules: Vec<BreakRule>,
+}
+
+impl BreakRules {
+    /// Unpaid break of 30 minutes once a day exceeds 6 hours.
+    pub fn six_hour_rule() -> BreakRule {
+        BreakRule {
+            worked_over: time::Duration::hours(6),
+            min_break: time::Duration::minutes(30),
+        }
+    }
+
+    /// Returns how much of `worked` is deducted because `recorded_break` is
+    /// shorter than the longest break required by any applicable rule.
+    pub fn deduction(&self, worked: time::Duration, recorded_break: time::Duration) -> time::Duration {
+        let required = self
+            .rules
+            .iter()
+            .filter(|rule| worked > rule.worked_over)
+            .map(|rule| rule.min_break)
+            .max()
+            .unwrap_or(time::Duration::ZERO);
+        (required - recorded_break).max(time::Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BreakRules;
+
+    #[test]
+    fn deducts_missing_break_after_threshold() {
+        let rules = BreakRules {
+            rules: vec![BreakRules::six_hour_rule()],
+        };
+
+        assert_eq!(
+            rules.deduction(time::Duration::hours(6), time::Duration::ZERO),
+            time::Duration::ZERO
+        );
+        assert_eq!(
+            rules.deduction(time::Duration::hours(7), time::Duration::ZERO),
+            time::Duration::minutes(30)
+        );
+        assert_eq!(
+            rules.deduction(time::Duration::hours(7), time::Duration::minutes(20)),
+            time::Duration::minutes(10)
+        );
+        assert_eq!(
+            rules.deduction(time::Duration::hours(7), time::Duration::minutes(45)),
+            time::Duration::ZERO
+        );
+    }
+}
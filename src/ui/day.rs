@@ -4,7 +4,8 @@ use chrono::NaiveDate;
 use egui::{Align, Layout, RichText};
 
 // A single visible work day in the UI: target, enabled flag, date, and an
-// ordered list of time ranges for that day.
+// ordered list of time ranges for that day. Break entries and the break rules
+// passed in by the app state decide how much of it counts.
 
 #[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Day {
@@ -16,17 +17,12 @@ pub struct Day {
 }
 
 impl Day {
-    /// Default daily target for newly created weekdays.
-    pub fn default_target() -> time::Duration {
-        time::Duration::hours(7) + time::Duration::minutes(36)
-    }
-
-    /// Creates a new enabled day with the default target.
-    pub fn new(name: String) -> Self {
+    /// Creates a new enabled day with the given target.
+    pub fn new(name: String, target: time::Duration) -> Self {
         Day {
             name,
             enabled: true,
-            total_target: Self::default_target(),
+            total_target: target,
             ..Default::default()
         }
     }
@@ -60,24 +56,49 @@ impl Day {
         self.total_target = target;
     }
 
-    /// Returns the sum of all durations that should count for this day.
-    pub fn duration(&self) -> time::Duration {
+    /// Returns the time that counts for this day: worked time minus the break
+    /// deduction of `rules`, with open entries counted up to the current time.
+    pub fn duration(&self, rules: &ui::BreakRules) -> time::Duration {
+        self.duration_at(ui::duration::local_now(), rules)
+    }
+
+    /// Returns the time that counts for this day, with open entries counted up
+    /// to `now`.
+    pub fn duration_at(&self, now: time::OffsetDateTime, rules: &ui::BreakRules) -> time::Duration {
         if !self.enabled {
             return time::Duration::ZERO;
         }
+        let worked = self.entries_at(now, false);
+        worked - rules.deduction(worked, self.entries_at(now, true))
+    }
 
-        let mut duration = time::Duration::ZERO;
-        for dur in &self.durations {
-            duration += dur.duration();
+    /// Returns the recorded breaks plus the automatic deduction, i.e. all time
+    /// that does not count even though it may lie between work entries.
+    pub fn break_at(&self, now: time::OffsetDateTime, rules: &ui::BreakRules) -> time::Duration {
+        if !self.enabled {
+            return time::Duration::ZERO;
         }
-        duration
+        let recorded = self.entries_at(now, true);
+        recorded + rules.deduction(self.entries_at(now, false), recorded)
     }
 
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
+    fn entries_at(&self, now: time::OffsetDateTime, breaks: bool) -> time::Duration {
+        self.durations
+            .iter()
+            .filter(|dur| dur.is_break() == breaks)
+            .fold(time::Duration::ZERO, |sum, dur| sum + dur.duration_at(self.date, now))
+    }
+
+    /// Draws the day editor, with totals after the break deduction of
+    /// `rules`. Returns the entries removed by "Clear", so the caller can keep
+    /// a copy of the day as it was.
+    pub fn ui(&mut self, ui: &mut egui::Ui, rules: &ui::BreakRules) -> Option<Vec<ui::Duration>> {
         for duration in &self.durations {
             duration.reserve_row_id();
         }
 
+        let mut cleared = None;
+
         let frame_width = 180.0;
         egui::Frame::new()
             // .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_GRAY))
@@ -111,14 +132,32 @@ impl Day {
                                 ui.label(ui::duration::format_duration(self.total_target, ui::duration::DURATION_FORMAT));
                                 ui.end_row();
                             }
+                            let now = ui::duration::local_now();
+                            {
+                                ui.label("Break:");
+                                let recorded = self.entries_at(now, true);
+                                let total = self.break_at(now, rules);
+                                let label = ui.label(ui::duration::format_duration(total, ui::duration::DURATION_FORMAT));
+                                if total > recorded && self.enabled {
+                                    label.on_hover_text(format!(
+                                        "{} recorded, {} deducted automatically",
+                                        ui::duration::format_duration(recorded, ui::duration::DURATION_FORMAT),
+                                        ui::duration::format_duration(total - recorded, ui::duration::DURATION_FORMAT)
+                                    ));
+                                }
+                                ui.end_row();
+                            }
                             {
                                 ui.label("Done:");
-                                ui.label(ui::duration::format_duration(self.duration(), ui::duration::DURATION_FORMAT));
+                                ui.label(ui::duration::format_duration(
+                                    self.duration_at(now, rules),
+                                    ui::duration::DURATION_FORMAT,
+                                ));
                                 ui.end_row();
                             }
                             {
                                 ui.label("Todo:");
-                                let todo = self.total_target - self.duration();
+                                let todo = self.total_target - self.duration_at(now, rules);
                                 let sign = if todo.is_negative() { "-" } else { "" };
                                 ui.label(format!(
                                     "{}{}",
@@ -148,7 +187,7 @@ impl Day {
                                 .on_hover_text(format!("Remove all durations for {}", self.name))
                                 .clicked()
                             {
-                                self.durations.clear();
+                                cleared = Some(std::mem::take(&mut self.durations));
                             }
                         });
                     });
@@ -168,6 +207,14 @@ impl Day {
                             //     ui.separator();
                             // }
                             ui.horizontal(|ui| {
+                                let mut is_break = duration.is_break();
+                                if ui
+                                    .toggle_value(&mut is_break, "☕")
+                                    .on_hover_text("Break: this range does not count as worked time")
+                                    .changed()
+                                {
+                                    duration.set_kind(if is_break { ui::EntryKind::Break } else { ui::EntryKind::Work });
+                                }
                                 // add duration
                                 let duration_output = duration.ui(ui);
 
@@ -216,6 +263,21 @@ impl Day {
                                     remove_ix = Some(ix);
                                 }
                             });
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(duration.project_mut())
+                                        .hint_text("project")
+                                        .desired_width(70.0),
+                                );
+                                ui.add(egui::TextEdit::singleline(duration.note_mut()).hint_text("note"));
+                                if let Some(source) = duration.source() {
+                                    ui.weak("↧").on_hover_text(format!("Imported from {}", source.kind));
+                                }
+                                if !duration.tags().is_empty() {
+                                    let tags: Vec<_> = duration.tags().iter().map(|tag| format!("#{tag}")).collect();
+                                    ui.weak(tags.join(" "));
+                                }
+                            });
                         }
                         // We assume only 1 remove button could have been clicked during the loop
                         if let Some(my_ix) = remove_ix {
@@ -229,13 +291,48 @@ impl Day {
                 // Add the margin around the label
                 // ui.add(margin, egui::Label::new("Hello, egui!"));
             });
+        cleared
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::ui;
     use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
 
+    #[test]
+    fn break_entries_and_rules_reduce_day_duration() {
+        let date = NaiveDate::from_ymd_opt(2026, 4, 20).unwrap();
+        let start = time::OffsetDateTime::from_unix_timestamp(1_776_667_200).unwrap();
+        let mut day = ui::Day::new("Monday".to_string(), time::Duration::hours(8));
+        day.date = date;
+        let rules = ui::BreakRules {
+            rules: vec![ui::BreakRules::six_hour_rule()],
+        };
+        day.durations = vec![ui::Duration::new(date, start, start + time::Duration::hours(7))];
+
+        // No break recorded: the rule deducts the full 30 minutes.
+        assert_eq!(
+            day.duration_at(start, &rules),
+            time::Duration::hours(6) + time::Duration::minutes(30)
+        );
+        assert_eq!(day.break_at(start, &rules), time::Duration::minutes(30));
+
+        // A recorded 10 minute break does not count and only 20 minutes are deducted.
+        let mut pause = ui::Duration::new(
+            date,
+            start + time::Duration::hours(7),
+            start + time::Duration::hours(7) + time::Duration::minutes(10),
+        );
+        pause.set_kind(ui::EntryKind::Break);
+        day.durations.push(pause);
+        assert_eq!(
+            day.duration_at(start, &rules),
+            time::Duration::hours(6) + time::Duration::minutes(40)
+        );
+        assert_eq!(day.break_at(start, &rules), time::Duration::minutes(30));
+    }
+
     #[test]
     fn test_date() {
         let now_utc: DateTime<Utc> = Utc::now();
@@ -4,22 +4,62 @@ use crate::ui::digitwise_number_editor::{
 };
 use chrono::NaiveDate;
 use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
 
 // UI model for one work range inside a day. It stores local clock times and an
 // optional overnight offset relative to the owning work day, which keeps the UI
 // model explicit while leaving absolute timestamp conversion to the Supabase
-// boundary.
+// boundary. A missing end marks an entry that is still running.
+//
+// Every entry has a UUID that stays with it across devices: new entries get a
+// random one, loaded entries keep the id of their server row.
 
 static NEXT_DURATION_ROW_ID: AtomicU64 = AtomicU64::new(1);
 
+/// Whether an entry counts as worked time or as an unpaid break.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    #[default]
+    Work,
+    Break,
+}
+
+/// Where an entry was imported from, e.g. a calendar event and its UID.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct EntrySource {
+    pub kind: String,
+    pub id: String,
+}
+
 #[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Duration {
     #[serde(default = "next_duration_row_id")]
     row_id: u64,
+    /// Identity of the entry in the database.
+    #[serde(default = "Uuid::new_v4")]
+    id: Uuid,
     start: ui::TimePoint,
-    end: ui::TimePoint,
+    #[serde(deserialize_with = "deserialize_end")]
+    end: Option<ui::TimePoint>,
     #[serde(default)]
     end_day_offset: i8,
+    #[serde(default)]
+    kind: EntryKind,
+    /// Project or task the time was spent on, empty when untagged.
+    #[serde(default)]
+    project: String,
+    #[serde(default)]
+    note: String,
+    /// Free-form labels, e.g. carried over from another time tracker.
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    source: Option<EntrySource>,
+    /// The stored metadata object as loaded, so keys the app does not know
+    /// survive saving the entry again.
+    #[serde(default)]
+    metadata: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,9 +71,16 @@ impl Default for Duration {
     fn default() -> Self {
         Self {
             row_id: next_duration_row_id(),
+            id: Uuid::new_v4(),
             start: ui::TimePoint::now(),
-            end: ui::TimePoint::now(),
+            end: Some(ui::TimePoint::now()),
             end_day_offset: 0,
+            kind: EntryKind::Work,
+            project: String::new(),
+            note: String::new(),
+            tags: Vec::new(),
+            source: None,
+            metadata: serde_json::Map::new(),
         }
     }
 }
@@ -46,9 +93,52 @@ impl Duration {
 
         Self {
             row_id: next_duration_row_id(),
+            id: Uuid::new_v4(),
             start: ui::TimePoint::from_offset_datetime(start),
-            end: ui::TimePoint::from_offset_datetime(end),
+            end: Some(ui::TimePoint::from_offset_datetime(end)),
             end_day_offset,
+            kind: EntryKind::Work,
+            project: String::new(),
+            note: String::new(),
+            tags: Vec::new(),
+            source: None,
+            metadata: serde_json::Map::new(),
+        }
+    }
+
+    /// Creates a duration from local clock values. With an `end_day_offset` of
+    /// 0, an end before the start is read as ending on the next day; `None`
+    /// creates an open entry.
+    pub fn from_clocks(start: ui::TimePoint, end: Option<ui::TimePoint>, end_day_offset: i8) -> Self {
+        Self {
+            row_id: next_duration_row_id(),
+            id: Uuid::new_v4(),
+            start,
+            end,
+            end_day_offset,
+            kind: EntryKind::Work,
+            project: String::new(),
+            note: String::new(),
+            tags: Vec::new(),
+            source: None,
+            metadata: serde_json::Map::new(),
+        }
+    }
+
+    /// Creates an open entry that starts at `start` and has no end yet.
+    pub fn new_open(start: time::OffsetDateTime) -> Self {
+        Self {
+            row_id: next_duration_row_id(),
+            id: Uuid::new_v4(),
+            start: ui::TimePoint::from_offset_datetime(start),
+            end: None,
+            end_day_offset: 0,
+            kind: EntryKind::Work,
+            project: String::new(),
+            note: String::new(),
+            tags: Vec::new(),
+            source: None,
+            metadata: serde_json::Map::new(),
         }
     }
 
@@ -75,7 +165,12 @@ impl Duration {
         }
 
         ui.label("->");
-        let end_output = self.end.ui(ui, (self.row_id, "end"));
+        let Some(end) = &mut self.end else {
+            ui.label("running")
+                .on_hover_text("Open entry, counted up to now until it is stopped");
+            return DurationOutput { focus_transfer };
+        };
+        let end_output = end.ui(ui, (self.row_id, "end"));
 
         if let Some(transfer) = end_output.focus_transfer {
             match (transfer.direction, transfer.trigger) {
@@ -98,26 +193,114 @@ impl Duration {
         DurationOutput { focus_transfer }
     }
 
+    /// Moves the end to `end`, recomputing the overnight offset relative to
+    /// the owning work day.
+    pub fn set_end(&mut self, work_date: NaiveDate, end: time::OffsetDateTime) {
+        self.end = Some(ui::TimePoint::from_offset_datetime(end));
+        self.end_day_offset = (local_date(end) - work_date).num_days().clamp(0, i64::from(i8::MAX)) as i8;
+    }
+
     pub fn reserve_row_id(&self) {
         reserve_duration_row_id(self.row_id);
     }
 
+    /// Equality that ignores the row id, which differs between sessions and
+    /// after reloading the same entry from the server, and the entry id,
+    /// which local entries recorded before ids were saved do not share with
+    /// their server row.
+    pub fn same_entry(&self, other: &Self) -> bool {
+        Self {
+            row_id: other.row_id,
+            id: other.id,
+            ..self.clone()
+        } == *other
+    }
+
     pub fn row_id(&self) -> u64 {
         self.row_id
     }
 
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Takes over the identity of a stored entry, e.g. when loading it.
+    pub fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+
     pub fn start_clock(&self) -> &ui::TimePoint {
         &self.start
     }
 
-    pub fn end_clock(&self) -> &ui::TimePoint {
-        &self.end
+    /// Returns the end clock, or `None` while the entry is still running.
+    pub fn end_clock(&self) -> Option<&ui::TimePoint> {
+        self.end.as_ref()
+    }
+
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    pub fn set_kind(&mut self, kind: EntryKind) {
+        self.kind = kind;
+    }
+
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    pub fn project_mut(&mut self) -> &mut String {
+        &mut self.project
+    }
+
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    pub fn note_mut(&mut self) -> &mut String {
+        &mut self.note
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    pub fn source(&self) -> Option<&EntrySource> {
+        self.source.as_ref()
+    }
+
+    pub fn set_source(&mut self, source: Option<EntrySource>) {
+        self.source = source;
+    }
+
+    pub fn metadata(&self) -> &serde_json::Map<String, serde_json::Value> {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: serde_json::Map<String, serde_json::Value>) {
+        self.metadata = metadata;
+    }
+
+    pub fn is_break(&self) -> bool {
+        self.kind == EntryKind::Break
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.end.is_none()
     }
 
     pub fn effective_end_day_offset(&self) -> i8 {
+        let Some(end) = &self.end else {
+            return 0;
+        };
         if self.end_day_offset > 0 {
             self.end_day_offset
-        } else if self.end.total_minutes() < self.start.total_minutes() {
+        } else if end.total_minutes() < self.start.total_minutes() {
             1
         } else {
             0
@@ -125,20 +308,54 @@ impl Duration {
     }
 
     /// Returns the signed difference between end and start, respecting the
-    /// overnight offset relative to the owning work day.
+    /// overnight offset relative to the owning work day. Open entries have no
+    /// length of their own; use `duration_at` to count them up to a moment.
     pub fn duration(&self) -> time::Duration {
+        let Some(end) = &self.end else {
+            return time::Duration::ZERO;
+        };
         let start_minutes = self.start.total_minutes();
-        let end_minutes = self.end.total_minutes() + i64::from(self.effective_end_day_offset()) * 24 * 60;
+        let end_minutes = end.total_minutes() + i64::from(self.effective_end_day_offset()) * 24 * 60;
         time::Duration::minutes(end_minutes - start_minutes)
     }
 
+    /// Like `duration`, but counts an open entry from its start on
+    /// `work_date` up to `now`.
+    pub fn duration_at(&self, work_date: NaiveDate, now: time::OffsetDateTime) -> time::Duration {
+        if self.end.is_some() {
+            return self.duration();
+        }
+        let now_date = local_date(now);
+        let now_minutes = (now_date - work_date).num_days() * 24 * 60 + i64::from(now.hour()) * 60 + i64::from(now.minute());
+        time::Duration::minutes(now_minutes - self.start.total_minutes()).max(time::Duration::ZERO)
+    }
+
     /// Returns true when the row still represents an unfilled draft rather
-    /// than a meaningful work entry.
+    /// than a meaningful work entry. Open entries are never considered empty.
     pub fn is_zero_length(&self) -> bool {
-        self.duration().is_zero()
+        self.end.is_some() && self.duration().is_zero()
     }
 }
 
+/// Reads `end` both as an option and as the bare time point that states saved
+/// before running entries existed contain.
+fn deserialize_end<'de, D>(deserializer: D) -> Result<Option<ui::TimePoint>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum End {
+        Closed(ui::TimePoint),
+        Optional(Option<ui::TimePoint>),
+    }
+
+    Ok(match serde::Deserialize::deserialize(deserializer)? {
+        End::Closed(end) => Some(end),
+        End::Optional(end) => end,
+    })
+}
+
 fn next_duration_row_id() -> u64 {
     NEXT_DURATION_ROW_ID.fetch_add(1, Ordering::Relaxed)
 }
@@ -155,7 +372,14 @@ fn reserve_duration_row_id(row_id: u64) {
     }
 }
 
-fn local_date(value: time::OffsetDateTime) -> NaiveDate {
+/// Current wall-clock time in the local offset, falling back to UTC where the
+/// local offset cannot be determined.
+pub fn local_now() -> time::OffsetDateTime {
+    time::OffsetDateTime::now_local().unwrap_or_else(|_| time::OffsetDateTime::now_utc())
+}
+
+/// Local calendar date of a timestamp.
+pub fn local_date(value: time::OffsetDateTime) -> NaiveDate {
     NaiveDate::from_ymd_opt(value.year(), value.month() as u32, value.day() as u32).expect("OffsetDateTime should map to a valid NaiveDate")
 }
 
@@ -173,3 +397,32 @@ pub fn format_duration(duration: time::Duration, format: &str) -> String {
         .replace("%M", &format!("{:02}", minutes))
         .replace("%S", &format!("{:02}", seconds))
 }
+
+/// Formats a duration with an explicit `+`/`-` sign, for balances that can go
+/// either way.
+pub fn format_signed_duration(duration: time::Duration, format: &str) -> String {
+    let sign = if duration.is_negative() { "-" } else { "+" };
+    format!("{}{}", sign, format_duration(duration.abs(), format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Duration;
+    use crate::ui;
+
+    #[test]
+    fn entries_saved_before_running_entries_still_load() {
+        let old: Duration = ron::from_str("(row_id: 7, start: (hour: 9, minute: 0), end: (hour: 10, minute: 0))").unwrap();
+        assert_eq!(old.end, Some(ui::TimePoint::new(10, 0)));
+        assert!(!old.is_open());
+
+        let running: Duration = ron::from_str("(row_id: 8, start: (hour: 9, minute: 0), end: None)").unwrap();
+        assert!(running.is_open());
+
+        let mut current = ui::Duration::from_clocks(ui::TimePoint::new(9, 0), Some(ui::TimePoint::new(10, 0)), 0);
+        let metadata = serde_json::json!({ "billable": true, "invoice": { "id": 7, "lines": ["a"] } });
+        current.set_metadata(metadata.as_object().unwrap().clone());
+        let round_tripped: Duration = ron::from_str(&ron::to_string(&current).unwrap()).unwrap();
+        assert_eq!(round_tripped, current);
+    }
+}
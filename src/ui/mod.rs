@@ -1,10 +1,12 @@
 // Reusable UI widgets used by the app shell and the persisted state model.
 // These modules own editing behavior for days, durations, and time points.
+pub mod break_rules;
 pub mod day;
 pub mod digitwise_number_editor;
 pub mod duration;
 pub mod time_point;
 
+pub use break_rules::BreakRules;
 pub use day::Day;
-pub use duration::Duration;
+pub use duration::{Duration, EntryKind, EntrySource};
 pub use time_point::TimePoint;
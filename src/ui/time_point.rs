@@ -28,6 +28,14 @@ impl TimePoint {
         }
     }
 
+    /// Builds a local clock value, clamping out-of-range fields.
+    pub fn new(hour: u8, minute: u8) -> Self {
+        Self {
+            hour: hour.min(23),
+            minute: minute.min(59),
+        }
+    }
+
     /// Builds a local clock value from an existing timestamp.
     pub fn from_offset_datetime(value: time::OffsetDateTime) -> Self {
         Self {
@@ -0,0 +1,68 @@
+create table if not exists public.contract_periods (
+    id uuid primary key default gen_random_uuid(),
+    user_id uuid not null default auth.uid() references auth.users (id) on delete cascade,
+    starts_on date not null,
+    ends_on date,
+    name text not null default '',
+    weekday_target_minutes integer[] not null,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now(),
+    constraint contract_periods_date_order check (ends_on is null or ends_on >= starts_on),
+    constraint contract_periods_seven_weekdays check (cardinality(weekday_target_minutes) = 7),
+    constraint contract_periods_targets_non_negative check (0 <= all (weekday_target_minutes)),
+    constraint contract_periods_user_start_unique unique (user_id, starts_on)
+);
+
+create index if not exists contract_periods_user_start_idx on public.contract_periods (user_id, starts_on);
+
+drop trigger if exists set_contract_periods_updated_at on public.contract_periods;
+create trigger set_contract_periods_updated_at
+before update on public.contract_periods
+for each row
+execute function public.set_updated_at();
+
+alter table public.contract_periods enable row level security;
+
+drop policy if exists "users_manage_own_contract_periods" on public.contract_periods;
+create policy "users_manage_own_contract_periods"
+on public.contract_periods
+for all
+to authenticated
+using (user_id = auth.uid())
+with check (user_id = auth.uid());
+
+-- The app edits the whole period list at once, so replace it in one
+-- transaction instead of diffing rows on the client.
+create or replace function public.save_contract_periods(
+    p_periods jsonb
+)
+returns setof public.contract_periods
+language plpgsql
+security invoker
+as $$
+begin
+    delete from public.contract_periods
+    where user_id = auth.uid();
+
+    insert into public.contract_periods (user_id, starts_on, ends_on, name, weekday_target_minutes)
+    select
+        auth.uid(),
+        (period->>'starts_on')::date,
+        (period->>'ends_on')::date,
+        coalesce(period->>'name', ''),
+        array(
+            select t.minutes::integer
+            from jsonb_array_elements_text(period->'weekday_target_minutes') with ordinality as t(minutes, ix)
+            order by t.ix
+        )
+    from jsonb_array_elements(coalesce(p_periods, '[]'::jsonb)) as period;
+
+    return query
+    select *
+    from public.contract_periods
+    where user_id = auth.uid()
+    order by starts_on;
+end;
+$$;
+
+grant execute on function public.save_contract_periods(jsonb) to authenticated;
@@ -0,0 +1,14 @@
+-- Entries without an end are still running, e.g. a clock-in timer that can be
+-- stopped later from any device.
+alter table public.work_entries alter column ends_at drop not null;
+
+alter table public.work_entries drop constraint if exists work_entries_time_order;
+alter table public.work_entries
+    add constraint work_entries_time_order check (ends_at is null or ends_at > starts_at);
+
+create index if not exists work_entries_open_idx
+on public.work_entries (work_day_id)
+where ends_at is null;
+
+-- `save_work_day_with_entries(...)` reads `ends_at` with `->>`, which already
+-- turns a JSON null into SQL null, so the RPC itself needs no change.
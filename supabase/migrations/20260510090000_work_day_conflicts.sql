@@ -0,0 +1,95 @@
+-- Optimistic concurrency for day saves. The client sends the `updated_at` it
+-- last saw for the day; when the row changed since, the save is rejected and
+-- the current server day is returned so the client can merge.
+--
+-- A null expectation means the client never saw a server version of the day
+-- (e.g. a day first recorded offline), so that save is not checked.
+drop function if exists public.save_work_day_with_entries(date, integer, boolean, jsonb);
+
+create or replace function public.save_work_day_with_entries(
+    p_work_date date,
+    p_target_minutes integer,
+    p_enabled boolean,
+    p_entries jsonb,
+    p_expected_updated_at timestamptz default null
+)
+returns jsonb
+language plpgsql
+security invoker
+as $$
+declare
+    v_current public.work_days%rowtype;
+    v_day public.work_days%rowtype;
+begin
+    select *
+    into v_current
+    from public.work_days
+    where user_id = auth.uid()
+      and work_date = p_work_date
+    for update;
+
+    if found and p_expected_updated_at is not null and v_current.updated_at <> p_expected_updated_at then
+        -- PostgREST answers PT-prefixed SQLSTATEs with the HTTP status that
+        -- follows, so this is a 409 whose details hold the server day.
+        raise sqlstate 'PT409' using
+            message = format('work day %s was changed elsewhere', p_work_date),
+            detail = (
+                to_jsonb(v_current) || jsonb_build_object(
+                    'work_entries',
+                    coalesce(
+                        (
+                            select jsonb_agg(to_jsonb(e) order by e.sort_index)
+                            from public.work_entries e
+                            where e.work_day_id = v_current.id
+                        ),
+                        '[]'::jsonb
+                    )
+                )
+            )::text;
+    end if;
+
+    insert into public.work_days (user_id, work_date, target_minutes, enabled)
+    values (auth.uid(), p_work_date, p_target_minutes, p_enabled)
+    on conflict (user_id, work_date)
+    do update
+    set target_minutes = excluded.target_minutes,
+        enabled = excluded.enabled
+    returning * into v_day;
+
+    delete from public.work_entries
+    where work_day_id = v_day.id
+      and not exists (
+          select 1
+          from jsonb_array_elements(coalesce(p_entries, '[]'::jsonb)) as entry
+          where (entry->>'sort_index')::integer = public.work_entries.sort_index
+      );
+
+    insert into public.work_entries (work_day_id, starts_at, ends_at, metadata, sort_index)
+    select
+        v_day.id,
+        (entry->>'starts_at')::timestamptz,
+        (entry->>'ends_at')::timestamptz,
+        coalesce(entry->'metadata', '{}'::jsonb),
+        (entry->>'sort_index')::integer
+    from jsonb_array_elements(coalesce(p_entries, '[]'::jsonb)) as entry
+    on conflict (work_day_id, sort_index)
+    do update
+    set starts_at = excluded.starts_at,
+        ends_at = excluded.ends_at,
+        metadata = excluded.metadata;
+
+    return to_jsonb(v_day) || jsonb_build_object(
+        'work_entries',
+        coalesce(
+            (
+                select jsonb_agg(to_jsonb(e) order by e.sort_index)
+                from public.work_entries e
+                where e.work_day_id = v_day.id
+            ),
+            '[]'::jsonb
+        )
+    );
+end;
+$$;
+
+grant execute on function public.save_work_day_with_entries(date, integer, boolean, jsonb, timestamptz) to authenticated;
@@ -0,0 +1,19 @@
+-- Streams changes to `work_days` over Supabase Realtime, so open apps notice
+-- days saved on another device. Realtime checks the table's RLS policies for
+-- every subscriber, so users only receive their own rows.
+--
+-- Entries are not published: every save goes through
+-- `save_work_day_with_entries(...)`, which also updates the day row.
+do $$
+begin
+    if not exists (
+        select 1
+        from pg_publication_tables
+        where pubname = 'supabase_realtime'
+          and schemaname = 'public'
+          and tablename = 'work_days'
+    ) then
+        alter publication supabase_realtime add table public.work_days;
+    end if;
+end;
+$$;
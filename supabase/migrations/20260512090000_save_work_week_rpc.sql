@@ -0,0 +1,67 @@
+-- Saves several days in one transaction. Each element of `p_days` holds the
+-- arguments of `save_work_day_with_entries(...)`:
+--
+--   {"work_date": "2026-05-04", "target_minutes": 480, "enabled": true,
+--    "entries": [...], "expected_updated_at": "2026-05-04T16:30:00Z"}
+--
+-- Every day is attempted so that all failures can be reported together. If
+-- any day fails, the whole call is rolled back and raises SQLSTATE 'PT422'
+-- (HTTP 422) whose detail lists the failed days:
+--
+--   [{"work_date": "...", "code": "...", "message": "...", "server": {...}}]
+--
+-- `server` holds the current server day when the day was changed elsewhere
+-- (SQLSTATE 'PT409' of the single-day RPC) and is null otherwise.
+create or replace function public.save_work_week_with_entries(p_days jsonb)
+returns jsonb
+language plpgsql
+security invoker
+as $$
+declare
+    v_day jsonb;
+    v_saved jsonb := '[]'::jsonb;
+    v_errors jsonb := '[]'::jsonb;
+    v_state text;
+    v_message text;
+    v_detail text;
+begin
+    for v_day in
+        select value from jsonb_array_elements(coalesce(p_days, '[]'::jsonb))
+    loop
+        begin
+            v_saved := v_saved || jsonb_build_array(
+                public.save_work_day_with_entries(
+                    (v_day->>'work_date')::date,
+                    (v_day->>'target_minutes')::integer,
+                    (v_day->>'enabled')::boolean,
+                    coalesce(v_day->'entries', '[]'::jsonb),
+                    (v_day->>'expected_updated_at')::timestamptz
+                )
+            );
+        exception when others then
+            get stacked diagnostics
+                v_state = returned_sqlstate,
+                v_message = message_text,
+                v_detail = pg_exception_detail;
+            v_errors := v_errors || jsonb_build_array(
+                jsonb_build_object(
+                    'work_date', v_day->>'work_date',
+                    'code', v_state,
+                    'message', v_message,
+                    'server', case when v_state = 'PT409' then v_detail::jsonb end
+                )
+            );
+        end;
+    end loop;
+
+    if jsonb_array_length(v_errors) > 0 then
+        raise sqlstate 'PT422' using
+            message = format('%s of %s days could not be saved', jsonb_array_length(v_errors), jsonb_array_length(p_days)),
+            detail = v_errors::text;
+    end if;
+
+    return v_saved;
+end;
+$$;
+
+grant execute on function public.save_work_week_with_entries(jsonb) to authenticated;
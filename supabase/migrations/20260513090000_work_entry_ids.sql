@@ -0,0 +1,97 @@
+-- Entries keep the uuid the client gave them, so saves upsert entries by id
+-- instead of by their position in the day. Reordering or inserting an entry
+-- no longer rewrites its neighbours.
+--
+-- Two entries may now share a sort_index for a moment while a day is saved,
+-- so the unique index the old upsert relied on goes away.
+drop index if exists public.work_entries_work_day_sort_unique_idx;
+
+create or replace function public.save_work_day_with_entries(
+    p_work_date date,
+    p_target_minutes integer,
+    p_enabled boolean,
+    p_entries jsonb,
+    p_expected_updated_at timestamptz default null
+)
+returns jsonb
+language plpgsql
+security invoker
+as $$
+declare
+    v_current public.work_days%rowtype;
+    v_day public.work_days%rowtype;
+begin
+    select *
+    into v_current
+    from public.work_days
+    where user_id = auth.uid()
+      and work_date = p_work_date
+    for update;
+
+    if found and p_expected_updated_at is not null and v_current.updated_at <> p_expected_updated_at then
+        raise sqlstate 'PT409' using
+            message = format('work day %s was changed elsewhere', p_work_date),
+            detail = (
+                to_jsonb(v_current) || jsonb_build_object(
+                    'work_entries',
+                    coalesce(
+                        (
+                            select jsonb_agg(to_jsonb(e) order by e.sort_index)
+                            from public.work_entries e
+                            where e.work_day_id = v_current.id
+                        ),
+                        '[]'::jsonb
+                    )
+                )
+            )::text;
+    end if;
+
+    insert into public.work_days (user_id, work_date, target_minutes, enabled)
+    values (auth.uid(), p_work_date, p_target_minutes, p_enabled)
+    on conflict (user_id, work_date)
+    do update
+    set target_minutes = excluded.target_minutes,
+        enabled = excluded.enabled
+    returning * into v_day;
+
+    delete from public.work_entries
+    where work_day_id = v_day.id
+      and not exists (
+          select 1
+          from jsonb_array_elements(coalesce(p_entries, '[]'::jsonb)) as entry
+          where (entry->>'id')::uuid = public.work_entries.id
+      );
+
+    -- Clients that predate entry ids send none; those entries get a new one.
+    insert into public.work_entries (id, work_day_id, starts_at, ends_at, metadata, sort_index)
+    select
+        coalesce((entry->>'id')::uuid, gen_random_uuid()),
+        v_day.id,
+        (entry->>'starts_at')::timestamptz,
+        (entry->>'ends_at')::timestamptz,
+        coalesce(entry->'metadata', '{}'::jsonb),
+        (entry->>'sort_index')::integer
+    from jsonb_array_elements(coalesce(p_entries, '[]'::jsonb)) as entry
+    on conflict (id)
+    do update
+    set work_day_id = excluded.work_day_id,
+        starts_at = excluded.starts_at,
+        ends_at = excluded.ends_at,
+        metadata = excluded.metadata,
+        sort_index = excluded.sort_index;
+
+    return to_jsonb(v_day) || jsonb_build_object(
+        'work_entries',
+        coalesce(
+            (
+                select jsonb_agg(to_jsonb(e) order by e.sort_index)
+                from public.work_entries e
+                where e.work_day_id = v_day.id
+            ),
+            '[]'::jsonb
+        )
+    );
+end;
+$$;
+
+grant execute on function public.save_work_day_with_entries(date, integer, boolean, jsonb, timestamptz) to authenticated;